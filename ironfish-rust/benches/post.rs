@@ -0,0 +1,89 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use ironfish_rust::note::Memo;
+use ironfish_rust::sapling_bls12::{
+    Bls12, Fr, Key, MerkleNoteHash, Note, ProposedTransaction, SAPLING,
+};
+use ironfish_rust::witness::{Witness, WitnessNode};
+
+const SPENDS: usize = 3;
+const OUTPUTS: usize = 3;
+const TREE_DEPTH: usize = 32;
+
+/// Witness placing the note in a tree with fixed sibling hashes.
+fn make_witness(note: &Note, index: usize) -> Witness<Bls12> {
+    let mut cursor: std::io::Cursor<[u8; 32]> = std::io::Cursor::new(note.commitment());
+    let mut current = MerkleNoteHash::read(&mut cursor).unwrap().0;
+
+    let mut auth_path = Vec::with_capacity(TREE_DEPTH);
+    for depth in 0..TREE_DEPTH {
+        let sibling = Fr::from((index * TREE_DEPTH + depth) as u64);
+        current = MerkleNoteHash::combine_hash(&SAPLING, depth, &current, &sibling);
+        auth_path.push(WitnessNode::Left(sibling));
+    }
+
+    Witness {
+        hasher: SAPLING.clone(),
+        tree_size: index + 1,
+        root_hash: current,
+        auth_path,
+    }
+}
+
+fn bench_post(c: &mut Criterion) {
+    let sapling = SAPLING.clone();
+    let key = Key::generate_key(sapling.clone());
+    let spends: Vec<(Note, Witness<Bls12>)> = (0..SPENDS)
+        .map(|i| {
+            let note = Note::new(
+                sapling.clone(),
+                key.generate_public_address(),
+                10,
+                Memo([0; 32]),
+            );
+            let witness = make_witness(&note, i);
+            (note, witness)
+        })
+        .collect();
+    let outputs: Vec<Note> = (0..OUTPUTS)
+        .map(|_| {
+            Note::new(
+                sapling.clone(),
+                key.generate_public_address(),
+                1,
+                Memo([0; 32]),
+            )
+        })
+        .collect();
+    let fee = (SPENDS * 10 - OUTPUTS) as u64;
+
+    let mut group = c.benchmark_group("post transaction with 3 spends and 3 outputs");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            let mut transaction = ProposedTransaction::new(sapling.clone());
+            for (note, witness) in spends.iter() {
+                transaction.spend(key.clone(), note, witness).unwrap();
+            }
+            for note in outputs.iter() {
+                transaction.receive(&key, note).unwrap();
+            }
+            transaction.post(&key, None, fee).unwrap()
+        })
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            let mut transaction = ProposedTransaction::new(sapling.clone());
+            transaction.spend_parallel(&key, &spends).unwrap();
+            transaction.receive_parallel(&key, &outputs).unwrap();
+            transaction.post(&key, None, fee).unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_post);
+criterion_main!(benches);
@@ -0,0 +1,56 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use ironfish_rust::note::Memo;
+use ironfish_rust::sapling_bls12::{Key, Note, ProposedTransaction, Transaction, SAPLING};
+use ironfish_rust::transaction::verify_transactions_parallel;
+
+const BLOCK_SIZE: usize = 100;
+
+/// Build a block's worth of miners fee transactions. These only contain a
+/// single receipt, but verifying them exercises the same proof and
+/// signature checks as any other transaction.
+fn make_block() -> Vec<Transaction> {
+    let sapling = SAPLING.clone();
+    let key = Key::generate_key(sapling.clone());
+    (0..BLOCK_SIZE)
+        .map(|i| {
+            let note = Note::new(
+                sapling.clone(),
+                key.generate_public_address(),
+                i as u64 + 1,
+                Memo([0; 32]),
+            );
+            let mut transaction = ProposedTransaction::new(sapling.clone());
+            transaction.receive(&key, &note).unwrap();
+            transaction.post_miners_fee().unwrap()
+        })
+        .collect()
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let block = make_block();
+
+    let mut group = c.benchmark_group("verify block of 100 transactions");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            for transaction in block.iter() {
+                transaction.verify().unwrap();
+            }
+        })
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            for result in verify_transactions_parallel(&block) {
+                result.unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify);
+criterion_main!(benches);
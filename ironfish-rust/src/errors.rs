@@ -19,6 +19,8 @@ pub enum SaplingKeyError {
     DiversificationError,
     InvalidLanguageEncoding,
     InvalidWord,
+    InvalidBech32Encoding,
+    InvalidAddressPrefix,
 }
 
 impl fmt::Display for SaplingKeyError {
@@ -45,6 +47,10 @@ pub enum SaplingProofError {
     SigningError,
     VerificationFailed,
     InconsistentWitness,
+    DuplicateNullifier,
+    /// The note being spent has a value of zero. Zero value notes can be
+    /// created to pad a transaction's outputs, but never spent.
+    ZeroValueSpend,
 }
 
 impl fmt::Display for SaplingProofError {
@@ -82,6 +88,11 @@ pub enum TransactionError {
     ProvingError,
     IoError(io::Error),
     VerificationFailed,
+    ExpiredError,
+    DuplicateNullifier,
+    /// The transaction was written in a format version newer than this
+    /// crate understands.
+    UnsupportedVersion(u8),
 }
 
 impl fmt::Display for TransactionError {
@@ -97,6 +108,8 @@ impl From<SaplingProofError> for TransactionError {
         match e {
             SaplingProofError::SigningError => TransactionError::SigningError,
             SaplingProofError::VerificationFailed => TransactionError::VerificationFailed,
+            SaplingProofError::DuplicateNullifier => TransactionError::DuplicateNullifier,
+            SaplingProofError::ZeroValueSpend => TransactionError::IllegalValueError,
             _ => TransactionError::ProvingError,
         }
     }
@@ -108,6 +121,35 @@ impl From<io::Error> for TransactionError {
     }
 }
 
+/// Reason a posted transaction failed `Transaction::verify_detailed`.
+/// Indexes are positions in the transaction's spends or receipts.
+#[derive(Debug, PartialEq)]
+pub enum VerificationError {
+    /// The spend's proof or value commitment is invalid.
+    SpendProofInvalid(usize),
+    /// The spend was not authorized by the owner of the note.
+    SpendSignatureInvalid(usize),
+    /// The receipt's proof, value commitment or ephemeral key is invalid.
+    ReceiptProofInvalid(usize),
+    /// The same note is spent more than once.
+    DuplicateNullifier,
+    /// The binding signature doesn't verify. This is also how spends,
+    /// outputs and a fee that don't balance show up, since the signature is
+    /// what proves that they do.
+    BindingSignatureInvalid,
+    /// The fee is `i64::MIN`, which has no value commitment, so it can't
+    /// balance any spends and outputs.
+    FeeMismatch,
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for VerificationError {}
+
 /// Errors raised when constructing a note
 #[derive(Debug)]
 pub enum NoteError {
@@ -136,3 +178,21 @@ impl From<SaplingKeyError> for NoteError {
         NoteError::KeyError
     }
 }
+
+/// Errors raised when choosing notes to spend
+#[derive(Debug)]
+pub enum SelectionError {
+    InsufficientFunds,
+    /// The output at this index can't be paid by a transaction under the
+    /// size limit, even on its own, with the notes left after paying the
+    /// outputs before it.
+    OutputTooLarge(usize),
+}
+
+impl fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl Error for SelectionError {}
@@ -7,18 +7,20 @@ use super::serializing::{
     bytes_to_hex, hex_to_bytes, point_to_bytes, read_scalar, scalar_to_bytes,
 };
 use super::Sapling;
-use bip39::{Language, Mnemonic};
+use bip39::{Language, Mnemonic, Seed};
 use blake2b_simd::Params as Blake2b;
 use blake2s_simd::Params as Blake2s;
+use ff::Field;
 use rand::prelude::*;
 // use rand_core::{OsRng, RngCore};
 use zcash_primitives::constants::CRH_IVK_PERSONALIZATION;
 
-use std::{io, sync::Arc};
+use std::{io, ptr, sync::atomic, sync::Arc};
 use zcash_primitives::jubjub::{
     edwards, FixedGenerators, JubjubEngine, JubjubParams, PrimeOrder, ToUniform,
 };
 use zcash_primitives::primitives::{ProofGenerationKey, ViewingKey};
+use zeroize::Zeroize;
 
 mod public_address;
 pub use public_address::*;
@@ -29,6 +31,8 @@ pub use view_keys::*;
 mod test;
 
 const EXPANDED_SPEND_BLAKE2_KEY: &[u8; 16] = b"Beanstalk Money ";
+const CHILD_KEY_PERSONALIZATION: &[u8; 16] = b"Iron Fish childk";
+const PASSPHRASE_KEY_PERSONALIZATION: &[u8; 16] = b"Iron Fish pphrsk";
 
 /// A single private key generates multiple other key parts that can
 /// be used to allow various forms of access to a commitment note:
@@ -85,10 +89,16 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SaplingKey<J> {
         sapling: Arc<Sapling<J>>,
         spending_key: [u8; 32],
     ) -> Result<Self, errors::SaplingKeyError> {
-        let spend_authorizing_key = J::Fs::to_uniform(&Self::convert_key(spending_key, 0));
-        let proof_authorizing_key = J::Fs::to_uniform(&Self::convert_key(spending_key, 1));
+        // The expanded key is as secret as the spending key, so the buffer
+        // is reused for each part and wiped afterwards
+        let mut expanded_key = Self::convert_key(spending_key, 0);
+        let spend_authorizing_key = J::Fs::to_uniform(&expanded_key);
+        expanded_key = Self::convert_key(spending_key, 1);
+        let proof_authorizing_key = J::Fs::to_uniform(&expanded_key);
+        expanded_key = Self::convert_key(spending_key, 2);
         let mut outgoing_viewing_key = [0; 32];
-        outgoing_viewing_key[0..32].clone_from_slice(&Self::convert_key(spending_key, 2)[0..32]);
+        outgoing_viewing_key[0..32].clone_from_slice(&expanded_key[0..32]);
+        expanded_key[..].zeroize();
         let outgoing_viewing_key = OutgoingViewKey {
             sapling: sapling.clone(),
             view_key: outgoing_viewing_key,
@@ -125,7 +135,9 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SaplingKey<J> {
     ) -> Result<Self, errors::SaplingKeyError> {
         let mut spending_key = [0; 32];
         reader.read_exact(&mut spending_key)?;
-        Self::new(sapling, spending_key)
+        let key = Self::new(sapling, spending_key);
+        spending_key.zeroize();
+        key
     }
 
     /// Load a key from a string of hexadecimal digits
@@ -135,14 +147,18 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SaplingKey<J> {
     ) -> Result<Self, errors::SaplingKeyError> {
         match hex_to_bytes(value) {
             Err(()) => Err(errors::SaplingKeyError::InvalidPaymentAddress),
-            Ok(bytes) => {
-                if bytes.len() != 32 {
+            Ok(mut bytes) => {
+                let key = if bytes.len() != 32 {
                     Err(errors::SaplingKeyError::InvalidPaymentAddress)
                 } else {
                     let mut byte_arr = [0; 32];
                     byte_arr.clone_from_slice(&bytes[0..32]);
-                    Self::new(sapling, byte_arr)
-                }
+                    let key = Self::new(sapling, byte_arr);
+                    byte_arr.zeroize();
+                    key
+                };
+                bytes.zeroize();
+                key
             }
         }
     }
@@ -160,7 +176,45 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SaplingKey<J> {
         let bytes = mnemonic.entropy();
         let mut byte_arr = [0; 32];
         byte_arr.clone_from_slice(&bytes[0..32]);
-        Self::new(sapling, byte_arr)
+        let key = Self::new(sapling, byte_arr);
+        byte_arr.zeroize();
+        key
+    }
+
+    /// Load a key from a string of words protected by a passphrase.
+    ///
+    /// With an empty passphrase this is `from_words`, so the words written
+    /// by `words_spending_key` load the same key back. Otherwise the words
+    /// and passphrase are stretched into a BIP39 seed, which is hashed into
+    /// the spending key, so the same words give an unrelated key for every
+    /// passphrase. Fails with `InvalidWord` if a word isn't in the word list
+    /// or the checksum of the words doesn't match.
+    pub fn from_words_with_passphrase(
+        sapling: Arc<Sapling<J>>,
+        language_code: &str,
+        value: String,
+        passphrase: &str,
+    ) -> Result<Self, errors::SaplingKeyError> {
+        if passphrase.is_empty() {
+            return Self::from_words(sapling, language_code, value);
+        }
+        let language = Language::from_language_code(language_code)
+            .ok_or(errors::SaplingKeyError::InvalidLanguageEncoding)?;
+        let mnemonic = Mnemonic::from_phrase(&value, language)
+            .map_err(|_| errors::SaplingKeyError::InvalidWord)?;
+        let seed = Seed::new(&mnemonic, passphrase);
+
+        let mut spending_key = [0; 32];
+        spending_key.copy_from_slice(
+            Blake2b::new()
+                .hash_length(32)
+                .personal(PASSPHRASE_KEY_PERSONALIZATION)
+                .hash(seed.as_bytes())
+                .as_ref(),
+        );
+        let key = Self::new(sapling, spending_key);
+        spending_key.zeroize();
+        key
     }
 
     /// Generate a new random secret key.
@@ -169,15 +223,38 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SaplingKey<J> {
     /// first time.
     /// Note that unlike `new`, this function always successfully returns a value.
     pub fn generate_key(sapling: Arc<Sapling<J>>) -> Self {
-        let spending_key: [u8; 32] = random();
+        let mut spending_key: [u8; 32] = random();
         // OsRng.fill_bytes(&mut spending_key);
         loop {
             if let Ok(key) = Self::new(sapling.clone(), spending_key) {
+                spending_key.zeroize();
                 return key;
             }
         }
     }
 
+    /// Derive the key of the subaccount at `index` from this key.
+    ///
+    /// The child spending key is a hash of this spending key and the index,
+    /// so every subaccount has its own, unrelated view keys and addresses,
+    /// but all of them can be recreated from this key alone. The derivation
+    /// is one way: a child key reveals nothing about its parent or its
+    /// siblings.
+    pub fn derive_child(&self, index: u32) -> Result<Self, errors::SaplingKeyError> {
+        let mut hasher = Blake2b::new()
+            .hash_length(32)
+            .personal(CHILD_KEY_PERSONALIZATION)
+            .to_state();
+
+        hasher.update(&self.spending_key);
+        hasher.update(&index.to_le_bytes());
+        let mut child_spending_key = [0; 32];
+        child_spending_key.copy_from_slice(hasher.finalize().as_ref());
+        let child = Self::new(self.sapling.clone(), child_spending_key);
+        child_spending_key.zeroize();
+        child
+    }
+
     /// Generate a public address from the incoming viewing key, given a specific
     /// 11 byte diversifier.
     ///
@@ -260,6 +337,12 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SaplingKey<J> {
         }
     }
 
+    /// Short, stable identifier for this account. Identical to the
+    /// fingerprint of this key's view keys.
+    pub fn fingerprint(&self) -> [u8; FINGERPRINT_SIZE] {
+        self.view_keys().fingerprint()
+    }
+
     #[deprecated(note = "I'm not aware that this ever needs to be publicly visible")]
     /// Retrieve the spend authorizing key
     pub fn spend_authorizing_key(&self) -> [u8; 32] {
@@ -360,4 +443,34 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SaplingKey<J> {
         }
         Ok(read_scalar(&hash_result[..])?)
     }
+
+    /// Overwrite the secret parts of this key with zeros.
+    ///
+    /// Called when the key is dropped, so the spending key and the values
+    /// derived from it don't linger in memory after the key is no longer
+    /// needed. The viewing keys and points are left alone, as they can't be
+    /// used to spend.
+    fn wipe(&mut self) {
+        self.spending_key.zeroize();
+        self.outgoing_viewing_key.view_key.zeroize();
+        wipe_scalar(&mut self.spend_authorizing_key);
+        wipe_scalar(&mut self.proof_authorizing_key);
+    }
+}
+
+/// Overwrite a scalar with zero in a way the optimizer can't remove, the
+/// same way `Zeroize` does for the byte arrays; the field types don't
+/// implement it themselves.
+fn wipe_scalar<F: Field>(scalar: &mut F) {
+    // Safe because the pointer comes from a mutable reference, so it is
+    // valid, aligned and not aliased, and `F` is `Copy`, so nothing needs
+    // to be dropped before it is overwritten.
+    unsafe { ptr::write_volatile(scalar, F::zero()) };
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> Drop for SaplingKey<J> {
+    fn drop(&mut self) {
+        self.wipe();
+    }
 }
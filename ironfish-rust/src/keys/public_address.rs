@@ -2,8 +2,10 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use crate::serializing::{bytes_to_hex, hex_to_bytes, point_to_bytes};
-use rand::{thread_rng, Rng};
+use crate::serializing::{bytes_to_hex, hex_digit, hex_to_bytes, point_to_bytes};
+use bech32::{FromBase32, ToBase32};
+use rand::{thread_rng, RngCore};
+use subtle::ConstantTimeEq;
 use zcash_primitives::primitives::{Diversifier, PaymentAddress};
 
 use std::{io, sync::Arc};
@@ -11,6 +13,9 @@ use zcash_primitives::jubjub::{edwards, JubjubEngine, PrimeOrder, ToUniform, Unk
 
 use super::{errors, IncomingViewKey, Sapling, SaplingKey};
 
+/// Human readable part of bech32 encoded public addresses.
+pub const PUBLIC_ADDRESS_BECH32_PREFIX: &str = "iron";
+
 /// The address to which funds can be sent, stored as a diversifier and public
 /// transmission key. Combining a diversifier with an incoming_viewing_key allows
 /// the creation of multiple public addresses without revealing the viewing key.
@@ -109,6 +114,51 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> PublicAddress<J> {
         }
     }
 
+    /// Whether `value` is the hex encoding of a valid public address, as
+    /// accepted by `from_hex`. Meant for validating user input as it is
+    /// typed, so it decodes into a buffer on the stack and returns false on
+    /// the first problem instead of building an error.
+    pub fn is_valid_hex(sapling: &Sapling<J>, value: &str) -> bool {
+        let hex = value.as_bytes();
+        if hex.len() != 86 {
+            return false;
+        }
+        let mut address_bytes = [0; 43];
+        for (byte, pair) in address_bytes.iter_mut().zip(hex.chunks(2)) {
+            match (hex_digit(pair[0]), hex_digit(pair[1])) {
+                (Some(high), Some(low)) => *byte = high << 4 | low,
+                _ => return false,
+            }
+        }
+        PublicAddress::<J>::load_diversifier(&sapling.jubjub, &address_bytes[..11]).is_ok()
+            && PublicAddress::<J>::load_transmission_key(&sapling.jubjub, &address_bytes[11..])
+                .is_ok()
+    }
+
+    /// Convert a bech32 string, as produced by `to_bech32`, to a
+    /// PublicAddress. Fails with `InvalidBech32Encoding` if the string is
+    /// malformed or its checksum doesn't match, so a mistyped address is
+    /// never silently accepted, and with `InvalidAddressPrefix` if it isn't
+    /// an Iron Fish address.
+    pub fn from_bech32(
+        sapling: Arc<Sapling<J>>,
+        value: &str,
+    ) -> Result<Self, errors::SaplingKeyError> {
+        let (prefix, data) =
+            bech32::decode(value).map_err(|_| errors::SaplingKeyError::InvalidBech32Encoding)?;
+        if prefix != PUBLIC_ADDRESS_BECH32_PREFIX {
+            return Err(errors::SaplingKeyError::InvalidAddressPrefix);
+        }
+        let bytes = Vec::<u8>::from_base32(&data)
+            .map_err(|_| errors::SaplingKeyError::InvalidBech32Encoding)?;
+        if bytes.len() != 43 {
+            return Err(errors::SaplingKeyError::InvalidPublicAddress);
+        }
+        let mut byte_arr = [0; 43];
+        byte_arr.copy_from_slice(&bytes);
+        Self::new(sapling, &byte_arr)
+    }
+
     /// Retrieve the public address in byte form. It is comprised of the
     /// 11 byte diversifier followed by the 32 byte transmission key.
     pub fn public_address(&self) -> [u8; 43] {
@@ -126,6 +176,27 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> PublicAddress<J> {
         bytes_to_hex(&self.public_address())
     }
 
+    /// Retrieve the public address as a bech32 string with the
+    /// `PUBLIC_ADDRESS_BECH32_PREFIX` prefix. Unlike hex, this includes a
+    /// checksum that catches typos.
+    pub fn to_bech32(&self) -> String {
+        bech32::encode(
+            PUBLIC_ADDRESS_BECH32_PREFIX,
+            self.public_address().to_base32(),
+        )
+        .expect("public address should fit in a bech32 string")
+    }
+
+    /// Compare two addresses in constant time.
+    ///
+    /// Use this instead of `==` when either side is derived from a secret,
+    /// such as checking whether a decrypted note's owner is one of your own
+    /// addresses, so the time taken doesn't reveal how much of the address
+    /// matched.
+    pub fn ct_eq(&self, other: &PublicAddress<J>) -> bool {
+        self.public_address().ct_eq(&other.public_address()).into()
+    }
+
     /// Store the bytes of this public address in the given writer.
     pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
         writer.write_all(&self.public_address())?;
@@ -169,9 +240,19 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> PublicAddress<J> {
     pub fn generate_diffie_hellman_keys(
         &self,
         jubjub: &J::Params,
+    ) -> (J::Fs, edwards::Point<J, PrimeOrder>) {
+        self.generate_diffie_hellman_keys_with_rng(jubjub, &mut thread_rng())
+    }
+
+    /// `generate_diffie_hellman_keys`, drawing the ephemeral secret key from
+    /// the given rng instead of the thread rng.
+    pub fn generate_diffie_hellman_keys_with_rng<R: RngCore + ?Sized>(
+        &self,
+        jubjub: &J::Params,
+        rng: &mut R,
     ) -> (J::Fs, edwards::Point<J, PrimeOrder>) {
         let mut buffer = [0u8; 64];
-        thread_rng().fill(&mut buffer[..]);
+        rng.fill_bytes(&mut buffer[..]);
 
         let secret_key: J::Fs = J::Fs::to_uniform(&buffer[..]);
         let public_key = self.diversifier_point.mul(secret_key, jubjub);
@@ -194,6 +275,8 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> std::fmt::Debug for PublicAddre
     }
 }
 
+/// Not constant time; see `PublicAddress::ct_eq` for comparisons involving
+/// secret-derived addresses.
 impl<J: JubjubEngine + pairing::MultiMillerLoop> std::cmp::PartialEq for PublicAddress<J> {
     fn eq(&self, other: &Self) -> bool {
         self.hex_public_address() == other.hex_public_address()
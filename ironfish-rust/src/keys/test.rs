@@ -2,8 +2,10 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use super::{shared_secret, PublicAddress, SaplingKey};
+use super::{shared_secret, IncomingViewKey, OutgoingViewKey, PublicAddress, SaplingKey, ViewKeys};
+use crate::errors::SaplingKeyError;
 use crate::sapling_bls12;
+use ff::Field;
 use pairing::bls12_381::Bls12;
 
 #[test]
@@ -19,6 +21,19 @@ fn test_key_generation_and_construction() {
     key2.generate_public_address();
 }
 
+#[test]
+fn test_key_wipe() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let mut key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    assert!(key.spending_key != [0; 32]);
+
+    key.wipe();
+    assert_eq!(key.spending_key, [0; 32]);
+    assert_eq!(key.outgoing_viewing_key.view_key, [0; 32]);
+    assert!(key.spend_authorizing_key.is_zero());
+    assert!(key.proof_authorizing_key.is_zero());
+}
+
 #[test]
 fn test_diffie_hellman_shared_key() {
     let sapling = &*sapling_bls12::SAPLING;
@@ -102,3 +117,155 @@ fn test_hex_conversion() {
 
     assert!(PublicAddress::from_hex(sapling.clone(), "invalid").is_err());
 }
+
+#[test]
+fn test_public_address_is_valid_hex() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let hex = key.generate_public_address().hex_public_address();
+    assert!(PublicAddress::is_valid_hex(sapling, &hex));
+    assert!(PublicAddress::is_valid_hex(sapling, &hex.to_uppercase()));
+
+    assert!(!PublicAddress::is_valid_hex(sapling, ""));
+    assert!(!PublicAddress::is_valid_hex(sapling, "invalid"));
+    assert!(!PublicAddress::is_valid_hex(sapling, &hex[..84]));
+    assert!(!PublicAddress::is_valid_hex(
+        sapling,
+        &format!("{}zz", &hex[..84])
+    ));
+    assert!(!PublicAddress::is_valid_hex(
+        sapling,
+        &format!("{}é", &hex[..84])
+    ));
+    // Not a point on the curve
+    assert!(!PublicAddress::is_valid_hex(
+        sapling,
+        &format!("{}{}", &hex[..22], "ff".repeat(32))
+    ));
+}
+
+#[test]
+fn test_words_with_passphrase() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let words = key.words_spending_key("en").unwrap();
+
+    let read_back =
+        SaplingKey::from_words_with_passphrase(sapling.clone(), "en", words.clone(), "").unwrap();
+    assert_eq!(read_back.spending_key, key.spending_key);
+
+    let protected =
+        SaplingKey::from_words_with_passphrase(sapling.clone(), "en", words.clone(), "secret")
+            .unwrap();
+    assert_ne!(protected.spending_key, key.spending_key);
+    let protected_again =
+        SaplingKey::from_words_with_passphrase(sapling.clone(), "en", words.clone(), "secret")
+            .unwrap();
+    assert_eq!(protected_again.spending_key, protected.spending_key);
+
+    // 24 times "abandon" is only valid with "art" as the last word
+    let bad_checksum = vec!["abandon"; 24].join(" ");
+    let not_a_word = format!("{} notaword", vec!["abandon"; 23].join(" "));
+    for phrase in vec![bad_checksum, not_a_word] {
+        assert!(matches!(
+            SaplingKey::<Bls12>::from_words_with_passphrase(
+                sapling.clone(),
+                "en",
+                phrase,
+                "secret"
+            ),
+            Err(SaplingKeyError::InvalidWord)
+        ));
+    }
+}
+
+#[test]
+fn test_bech32_conversion() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let address = key.generate_public_address();
+
+    let encoded = address.to_bech32();
+    assert!(encoded.starts_with("iron1"));
+    let decoded = PublicAddress::from_bech32(sapling.clone(), &encoded).unwrap();
+    assert_eq!(decoded, address);
+
+    // Changing any character breaks the checksum
+    let mut typo = encoded.clone().into_bytes();
+    let last = typo.len() - 1;
+    typo[last] = if typo[last] == b'q' { b'p' } else { b'q' };
+    assert!(matches!(
+        PublicAddress::from_bech32(sapling.clone(), &String::from_utf8(typo).unwrap()),
+        Err(SaplingKeyError::InvalidBech32Encoding)
+    ));
+
+    let wrong_prefix =
+        bech32::encode("zs", bech32::ToBase32::to_base32(&address.public_address())).unwrap();
+    assert!(matches!(
+        PublicAddress::from_bech32(sapling.clone(), &wrong_prefix),
+        Err(SaplingKeyError::InvalidAddressPrefix)
+    ));
+}
+
+#[test]
+fn test_constant_time_comparison() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let other_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+
+    let address = key.generate_public_address();
+    let read_back =
+        PublicAddress::from_hex(sapling.clone(), &address.hex_public_address()).unwrap();
+    assert!(address.ct_eq(&read_back));
+    assert!(!address.ct_eq(&key.generate_public_address()));
+    assert!(!address.ct_eq(&other_key.generate_public_address()));
+
+    let incoming = key.incoming_view_key();
+    assert!(
+        incoming.ct_eq(&IncomingViewKey::from_hex(sapling.clone(), &incoming.hex_key()).unwrap())
+    );
+    assert!(!incoming.ct_eq(other_key.incoming_view_key()));
+
+    let outgoing = key.outgoing_view_key();
+    assert!(
+        outgoing.ct_eq(&OutgoingViewKey::from_hex(sapling.clone(), &outgoing.hex_key()).unwrap())
+    );
+    assert!(!outgoing.ct_eq(other_key.outgoing_view_key()));
+}
+
+#[test]
+fn test_fingerprint() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let other_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+
+    let view_keys: ViewKeys<Bls12> = ViewKeys {
+        incoming: IncomingViewKey::from_hex(sapling.clone(), &key.incoming_view_key().hex_key())
+            .unwrap(),
+        outgoing: OutgoingViewKey::from_hex(sapling.clone(), &key.outgoing_view_key().hex_key())
+            .unwrap(),
+    };
+
+    assert_eq!(key.fingerprint(), view_keys.fingerprint());
+    assert_eq!(key.fingerprint(), key.fingerprint());
+    assert_ne!(key.fingerprint(), other_key.fingerprint());
+}
+
+#[test]
+fn test_derive_child() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+
+    let child0 = key.derive_child(0).unwrap();
+    let child1 = key.derive_child(1).unwrap();
+    assert_eq!(
+        child0.spending_key,
+        key.derive_child(0).unwrap().spending_key
+    );
+    assert_ne!(child0.spending_key, key.spending_key);
+    assert_ne!(child0.spending_key, child1.spending_key);
+    assert_ne!(
+        child0.incoming_view_key().hex_key(),
+        child1.incoming_view_key().hex_key()
+    );
+}
@@ -19,11 +19,16 @@ use crate::serializing::{
 use bip39::{Language, Mnemonic};
 use blake2b_simd::Params as Blake2b;
 use rand::{thread_rng, Rng};
+use subtle::ConstantTimeEq;
 
 use std::{io, sync::Arc};
 use zcash_primitives::jubjub::{edwards, JubjubEngine, PrimeOrder};
 
 const DIFFIE_HELLMAN_PERSONALIZATION: &[u8; 16] = b"Beanstalk shared";
+const FINGERPRINT_PERSONALIZATION: &[u8; 16] = b"Iron Fish fprint";
+
+/// Size in bytes of an account fingerprint.
+pub const FINGERPRINT_SIZE: usize = 16;
 
 /// Key that allows someone to view a transaction that you have received.
 ///
@@ -106,6 +111,13 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> IncomingViewKey<J> {
         PublicAddress::from_view_key(self, diversifier)
     }
 
+    /// Compare two incoming view keys in constant time.
+    pub fn ct_eq(&self, other: &IncomingViewKey<J>) -> bool {
+        scalar_to_bytes(&self.view_key)
+            .ct_eq(&scalar_to_bytes(&other.view_key))
+            .into()
+    }
+
     /// Generate a public address from this key,
     /// picking a diversifier that is guaranteed to work with it.
     ///
@@ -199,6 +211,11 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> OutgoingViewKey<J> {
         let mnemonic = Mnemonic::from_entropy(&self.view_key, language).unwrap();
         Ok(mnemonic.phrase().to_string())
     }
+
+    /// Compare two outgoing view keys in constant time.
+    pub fn ct_eq(&self, other: &OutgoingViewKey<J>) -> bool {
+        self.view_key.ct_eq(&other.view_key).into()
+    }
 }
 
 /// Pair of outgoing and incoming view keys for a complete audit
@@ -209,6 +226,27 @@ pub struct ViewKeys<J: JubjubEngine + pairing::MultiMillerLoop> {
     pub outgoing: OutgoingViewKey<J>,
 }
 
+impl<J: JubjubEngine + pairing::MultiMillerLoop> ViewKeys<J> {
+    /// Short, stable identifier for the account these view keys belong to.
+    ///
+    /// This is a hash of both view keys, so a watch-only account imported
+    /// from its view keys gets the same fingerprint as the full account.
+    /// It is meant for naming and deduplicating accounts, and reveals
+    /// nothing about the keys themselves.
+    pub fn fingerprint(&self) -> [u8; FINGERPRINT_SIZE] {
+        let mut hasher = Blake2b::new()
+            .hash_length(FINGERPRINT_SIZE)
+            .personal(FINGERPRINT_PERSONALIZATION)
+            .to_state();
+
+        hasher.update(&scalar_to_bytes(&self.incoming.view_key));
+        hasher.update(&self.outgoing.view_key);
+        let mut fingerprint = [0; FINGERPRINT_SIZE];
+        fingerprint.copy_from_slice(hasher.finalize().as_ref());
+        fingerprint
+    }
+}
+
 /// Derive a shared secret key from a secret key and the other person's public
 /// key.
 ///
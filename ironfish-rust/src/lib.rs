@@ -18,11 +18,22 @@ pub mod keys;
 pub mod merkle_note;
 pub mod merkle_note_hash;
 pub mod note;
+pub mod note_selection;
 pub mod nullifiers;
+pub mod proof_verifier;
 pub mod receiving;
+pub mod signed_receipt;
 pub mod spending;
 pub mod transaction;
 pub mod witness;
+
+#[cfg(feature = "test-mocks")]
+mod test_mocks;
+#[cfg(all(feature = "test-mocks", not(debug_assertions)))]
+compile_error!(
+    "the `test-mocks` feature replaces proofs with placeholders and can't be used in release builds"
+);
+
 pub use {
     keys::{IncomingViewKey, OutgoingViewKey, PublicAddress, SaplingKey, ViewKeys},
     merkle_note::MerkleNote,
@@ -30,7 +41,9 @@ pub use {
     note::Note,
     receiving::{ReceiptParams, ReceiptProof},
     spending::{SpendParams, SpendProof},
-    transaction::{ProposedTransaction, SimpleTransaction, Transaction},
+    transaction::{
+        ProposedTransaction, SimpleTransaction, Transaction, TransactionStream, UnsignedTransaction,
+    },
 };
 pub mod sapling_bls12;
 
@@ -89,6 +102,15 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> Sapling<J> {
     fn load_params(bytes: &[u8]) -> groth16::Parameters<J> {
         groth16::Parameters::read(bytes, false).unwrap()
     }
+
+    /// The verifying keys of these parameters, to check transactions with
+    /// elsewhere without loading the proving parameters.
+    pub fn verifying_keys(&self) -> proof_verifier::VerifyingKeys<J> {
+        proof_verifier::VerifyingKeys::new(
+            self.spend_params.vk.clone(),
+            self.receipt_params.vk.clone(),
+        )
+    }
 }
 
 // TODO: This belongs in a utility library if we ever need one
@@ -8,7 +8,7 @@ use super::{
     errors,
     keys::{shared_secret, IncomingViewKey, OutgoingViewKey, PublicAddress, SaplingKey},
     note::{Note, ENCRYPTED_NOTE_SIZE},
-    serializing::{aead, read_scalar},
+    serializing::{aead, point_to_bytes, read_scalar, scalar_to_bytes},
     witness::{WitnessNode, WitnessTrait},
     MerkleNoteHash, Sapling,
 };
@@ -21,6 +21,18 @@ use std::{convert::TryInto, io, sync::Arc};
 use zcash_primitives::jubjub::{edwards, JubjubEngine, PrimeOrder, Unknown};
 
 pub const ENCRYPTED_SHARED_KEY_SIZE: usize = 64;
+
+/// Size in bytes of a serialized `MerkleNote`: value commitment (32), note
+/// commitment (32), ephemeral public key (32), the encrypted note and the
+/// encrypted note encryption keys, each followed by their MAC.
+pub const MERKLE_NOTE_SIZE: usize = 32
+    + 32
+    + 32
+    + ENCRYPTED_NOTE_SIZE
+    + aead::MAC_SIZE
+    + ENCRYPTED_SHARED_KEY_SIZE
+    + aead::MAC_SIZE;
+
 /// The note encryption keys are used to allow the spender to
 /// read notes that they have themselves have spent.
 /// In the case of miner notes, the note is created out of thin air
@@ -140,6 +152,11 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> MerkleNote<J> {
         })
     }
 
+    /// Number of bytes written by `write`. Always `MERKLE_NOTE_SIZE`.
+    pub fn serialized_size() -> usize {
+        MERKLE_NOTE_SIZE
+    }
+
     pub fn write<W: io::Write>(&self, mut writer: &mut W) -> io::Result<()> {
         self.value_commitment.write(&mut writer)?;
         writer.write_all(self.note_commitment.to_repr().as_ref())?;
@@ -153,6 +170,29 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> MerkleNote<J> {
         MerkleNoteHash::new(self.note_commitment)
     }
 
+    /// The note commitment (`cm` in the literature) as bytes. This is the
+    /// value that gets added to the merkle tree.
+    pub fn note_commitment(&self) -> [u8; 32] {
+        scalar_to_bytes(&self.note_commitment)
+    }
+
+    /// The value commitment (`cv` in the literature) as bytes. It hides the
+    /// value of the note, and is what the binding signature balances.
+    pub fn value_commitment(&self) -> [u8; 32] {
+        let mut result = [0; 32];
+        self.value_commitment
+            .write(&mut result[..])
+            .expect("value commitment should be convertible to bytes");
+        result
+    }
+
+    /// The public part of the ephemeral diffie-hellman key pair (`epk` in the
+    /// literature) as bytes. Needed to attempt trial decryption of the note.
+    pub fn ephemeral_public_key(&self) -> [u8; 32] {
+        point_to_bytes(&self.ephemeral_public_key)
+            .expect("ephemeral public key should be convertible to bytes")
+    }
+
     pub fn decrypt_note_for_owner(
         &self,
         owner_view_key: &IncomingViewKey<J>,
@@ -270,7 +310,7 @@ fn calculate_key_for_encryption_keys<J: JubjubEngine + pairing::MultiMillerLoop>
 
 #[cfg(test)]
 mod test {
-    use super::MerkleNote;
+    use super::{MerkleNote, MERKLE_NOTE_SIZE};
     use crate::{
         keys::SaplingKey,
         note::{Memo, Note},
@@ -318,6 +358,90 @@ mod test {
             .expect("should be able to decrypt note");
     }
 
+    #[test]
+    fn test_child_key_decryption() {
+        let sapling = &*sapling_bls12::SAPLING;
+        let master_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+        let child1 = master_key.derive_child(1).unwrap();
+        let child2 = master_key.derive_child(2).unwrap();
+        let note = Note::new(
+            sapling.clone(),
+            child2.generate_public_address(),
+            42,
+            Memo([0; 32]),
+        );
+        let diffie_hellman_keys = note.owner.generate_diffie_hellman_keys(&sapling.jubjub);
+        let value_commitment = ValueCommitment::<Bls12> {
+            value: note.value,
+            randomness: Fs::to_uniform(&[1u8; 64][..]),
+        };
+
+        let merkle_note =
+            MerkleNote::new(&master_key, &note, &value_commitment, &diffie_hellman_keys);
+        merkle_note
+            .decrypt_note_for_owner(master_key.derive_child(2).unwrap().incoming_view_key())
+            .expect("subaccount should be able to decrypt its own note");
+        assert!(merkle_note
+            .decrypt_note_for_owner(child1.incoming_view_key())
+            .is_err());
+        assert!(merkle_note
+            .decrypt_note_for_owner(master_key.incoming_view_key())
+            .is_err());
+    }
+
+    #[test]
+    fn test_sync_data_accessors() {
+        let sapling = &*sapling_bls12::SAPLING;
+        let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+        let note = Note::new(
+            sapling.clone(),
+            spender_key.generate_public_address(),
+            42,
+            Memo([0; 32]),
+        );
+        let diffie_hellman_keys = note.owner.generate_diffie_hellman_keys(&sapling.jubjub);
+        let value_commitment = ValueCommitment::<Bls12> {
+            value: note.value,
+            randomness: Fs::to_uniform(&[7; 64]),
+        };
+
+        let merkle_note =
+            MerkleNote::new(&spender_key, &note, &value_commitment, &diffie_hellman_keys);
+        let mut serialized = vec![];
+        merkle_note.write(&mut serialized).unwrap();
+
+        assert_eq!(merkle_note.note_commitment()[..], serialized[32..64]);
+        assert_eq!(merkle_note.ephemeral_public_key()[..], serialized[64..96]);
+    }
+
+    #[test]
+    fn test_serialized_size() {
+        // Pinned so that any change to the merkle note format is deliberate;
+        // the size is part of the transaction wire format.
+        assert_eq!(MERKLE_NOTE_SIZE, 275);
+        assert_eq!(MerkleNote::<Bls12>::serialized_size(), MERKLE_NOTE_SIZE);
+
+        let sapling = &*sapling_bls12::SAPLING;
+        let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+        let note = Note::new(
+            sapling.clone(),
+            spender_key.generate_public_address(),
+            42,
+            Memo([0; 32]),
+        );
+        let diffie_hellman_keys = note.owner.generate_diffie_hellman_keys(&sapling.jubjub);
+        let value_commitment = ValueCommitment::<Bls12> {
+            value: note.value,
+            randomness: Fs::to_uniform(&[7; 64]),
+        };
+
+        let merkle_note =
+            MerkleNote::new(&spender_key, &note, &value_commitment, &diffie_hellman_keys);
+        let mut serialized = vec![];
+        merkle_note.write(&mut serialized).unwrap();
+        assert_eq!(serialized.len(), MERKLE_NOTE_SIZE);
+    }
+
     #[test]
     fn test_receipt_invalid_commitment() {
         let sapling = &*sapling_bls12::SAPLING;
@@ -11,7 +11,7 @@ use super::{
 };
 use byteorder::{ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
 use ff::PrimeField;
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, RngCore};
 use zcash_primitives::primitives::Note as SaplingNote;
 
 use std::{fmt, io, io::Read, sync::Arc};
@@ -19,16 +19,34 @@ use zcash_primitives::jubjub::{edwards, JubjubEngine, PrimeOrder, ToUniform};
 
 pub const ENCRYPTED_NOTE_SIZE: usize = 83;
 
+/// Size in bytes of the memo field of a note. Shorter memos are padded with
+/// zeros.
+pub const MEMO_SIZE: usize = 32;
+
 /// Memo field on a Note. Used to encode transaction IDs or other information
 /// about the transaction.
 #[derive(Shrinkwrap, Debug, Clone, Copy, PartialEq)]
-pub struct Memo(pub [u8; 32]);
+pub struct Memo(pub [u8; MEMO_SIZE]);
+
+impl Memo {
+    /// The memo as a string, without the zero padding. Fails if the memo
+    /// isn't valid UTF-8.
+    pub fn as_str(&self) -> Result<&str, std::str::Utf8Error> {
+        let length = self
+            .0
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |last| last + 1);
+        std::str::from_utf8(&self.0[..length])
+    }
+}
 
+/// Truncates strings longer than `MEMO_SIZE` bytes.
 impl From<&str> for Memo {
     fn from(string: &str) -> Self {
         let memo_as_bytes = string.as_bytes();
-        let num_to_clone = std::cmp::min(memo_as_bytes.len(), 32);
-        let mut memo_bytes = [0; 32];
+        let num_to_clone = std::cmp::min(memo_as_bytes.len(), MEMO_SIZE);
+        let mut memo_bytes = [0; MEMO_SIZE];
         memo_bytes[..num_to_clone].clone_from_slice(&memo_as_bytes[..num_to_clone]);
         Memo(memo_bytes)
     }
@@ -81,8 +99,20 @@ pub struct Note<J: JubjubEngine + pairing::MultiMillerLoop> {
 impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> Note<J> {
     /// Construct a new Note.
     pub fn new(sapling: Arc<Sapling<J>>, owner: PublicAddress<J>, value: u64, memo: Memo) -> Self {
+        Self::new_with_rng(sapling, owner, value, memo, &mut thread_rng())
+    }
+
+    /// Construct a new Note, drawing its randomness from the given rng
+    /// instead of the thread rng.
+    pub fn new_with_rng<R: RngCore + ?Sized>(
+        sapling: Arc<Sapling<J>>,
+        owner: PublicAddress<J>,
+        value: u64,
+        memo: Memo,
+        rng: &mut R,
+    ) -> Self {
         let mut buffer = [0u8; 64];
-        thread_rng().fill(&mut buffer[..]);
+        rng.fill_bytes(&mut buffer[..]);
 
         let randomness: J::Fs = J::Fs::to_uniform(&buffer[..]);
 
@@ -306,7 +336,7 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> Note<J> {
 
 #[cfg(test)]
 mod test {
-    use super::{Memo, Note};
+    use super::{Memo, Note, MEMO_SIZE};
     use crate::{
         keys::{shared_secret, SaplingKey},
         sapling_bls12,
@@ -394,4 +424,15 @@ mod test {
         let memo = Memo::from(string);
         assert_eq!(&memo.0[..6], b"a memo");
     }
+
+    #[test]
+    fn test_memo_as_str() {
+        assert_eq!(Memo::from("a memo").as_str().unwrap(), "a memo");
+        assert_eq!(Memo::from("").as_str().unwrap(), "");
+        // Zeros inside the memo are kept
+        assert_eq!(Memo::from("a\0memo").as_str().unwrap(), "a\0memo");
+        let mut bytes = [0; MEMO_SIZE];
+        bytes[0] = 0xff;
+        assert!(Memo(bytes).as_str().is_err());
+    }
 }
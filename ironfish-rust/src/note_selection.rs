@@ -0,0 +1,362 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Choosing which of a wallet's notes to spend.
+
+use super::{
+    errors::SelectionError,
+    keys::PublicAddress,
+    note::Note,
+    receiving::RECEIPT_PROOF_SIZE,
+    spending::SPEND_PROOF_SIZE,
+    transaction::{BINDING_SIGNATURE_SIZE, TRANSACTION_HEADER_SIZE},
+};
+use zcash_primitives::jubjub::JubjubEngine;
+
+/// Pick notes whose values add up to at least `target + fee`, returning
+/// their indices in `notes`.
+///
+/// Notes are taken largest first, which spends as few notes as possible and
+/// so keeps the transaction, and its fee, small. The indices are returned in
+/// the order the notes were picked. Anything above `target + fee` is left
+/// for change.
+///
+/// Fails with `InsufficientFunds` if all the notes together are not enough.
+pub fn select_notes<J: JubjubEngine + pairing::MultiMillerLoop>(
+    notes: &[Note<J>],
+    target: u64,
+    fee: u64,
+) -> Result<Vec<usize>, SelectionError> {
+    // Sums are kept in u128 so no set of u64 values can overflow them
+    let needed = target as u128 + fee as u128;
+
+    let mut by_value: Vec<usize> = (0..notes.len()).collect();
+    // Stable, so notes of equal value are picked in their original order
+    by_value.sort_by(|a, b| notes[*b].value().cmp(&notes[*a].value()));
+
+    let mut selected = vec![];
+    let mut total: u128 = 0;
+    for index in by_value {
+        if total >= needed {
+            break;
+        }
+        total += notes[index].value() as u128;
+        selected.push(index);
+    }
+
+    if total < needed {
+        return Err(SelectionError::InsufficientFunds);
+    }
+    Ok(selected)
+}
+
+/// One of the transactions `plan_transactions` splits a send into.
+#[derive(Debug, PartialEq)]
+pub struct TransactionPlan {
+    /// Indices in `notes` of the notes to spend, in the order they were
+    /// picked.
+    pub spends: Vec<usize>,
+    /// Indices in `outputs` of the outputs to pay, in their original order.
+    pub outputs: Vec<usize>,
+    /// Fee to post the transaction with. Whatever the spends hold beyond the
+    /// outputs and this fee goes back to the sender as change.
+    pub fee: u64,
+}
+
+/// Split a send of `outputs` into transactions that each serialize to at
+/// most `max_size` bytes, paying `fee_per_byte` for every byte.
+///
+/// Every plan leaves room for a change output, so its size and fee hold
+/// whether or not `ProposedTransaction::post` ends up adding change. The
+/// sizes are those of transactions that don't expire.
+///
+/// Outputs are paid in order, filling a transaction until the next output
+/// doesn't fit, and are never split between transactions. Each output is
+/// paid with the smallest unspent note that covers what it still needs,
+/// taking the largest notes first only while no single note does. That
+/// spends as few notes as possible for the output while keeping the large
+/// notes for the outputs that come after it.
+///
+/// Fails with `OutputTooLarge` if the notes left after paying the earlier
+/// outputs can't pay an output in a transaction of its own under the size
+/// limit, and with `InsufficientFunds` if the notes don't cover all the
+/// outputs and fees.
+pub fn plan_transactions<J: JubjubEngine + pairing::MultiMillerLoop>(
+    notes: &[Note<J>],
+    outputs: &[(PublicAddress<J>, u64)],
+    max_size: usize,
+    fee_per_byte: u64,
+) -> Result<Vec<TransactionPlan>, SelectionError> {
+    // Largest first; notes are removed as they are spent
+    let mut unspent: Vec<usize> = (0..notes.len()).collect();
+    unspent.sort_by(|a, b| notes[*b].value().cmp(&notes[*a].value()));
+
+    let mut plans = vec![];
+    let mut current = PlanBuilder::default();
+    for (index, (_, value)) in outputs.iter().enumerate() {
+        let mut needed_notes =
+            current.notes_to_pay(notes, &unspent, *value, max_size, fee_per_byte)?;
+        if needed_notes.is_none() && !current.outputs.is_empty() {
+            plans.push(current.finish(fee_per_byte));
+            current = PlanBuilder::default();
+            needed_notes = current.notes_to_pay(notes, &unspent, *value, max_size, fee_per_byte)?;
+        }
+        let needed_notes = needed_notes.ok_or(SelectionError::OutputTooLarge(index))?;
+        for position in needed_notes.iter() {
+            let note = unspent[*position];
+            current.spends.push(note);
+            current.spent += notes[note].value() as u128;
+        }
+        // The positions are increasing, so remove from the back
+        for position in needed_notes.iter().rev() {
+            unspent.remove(*position);
+        }
+        current.outputs.push(index);
+        current.paid += *value as u128;
+    }
+    if !current.outputs.is_empty() {
+        plans.push(current.finish(fee_per_byte));
+    }
+    Ok(plans)
+}
+
+/// Transaction being filled by `plan_transactions`. Sums are kept in u128
+/// so they can't overflow.
+#[derive(Default)]
+struct PlanBuilder {
+    spends: Vec<usize>,
+    outputs: Vec<usize>,
+    spent: u128,
+    paid: u128,
+}
+
+impl PlanBuilder {
+    /// Positions in `unspent`, in increasing order, of the notes this
+    /// transaction needs to also pay an output of `value`, or `None` if they
+    /// wouldn't fit in `max_size` bytes. `unspent` must be sorted largest
+    /// first.
+    fn notes_to_pay<J>(
+        &self,
+        notes: &[Note<J>],
+        unspent: &[usize],
+        value: u64,
+        max_size: usize,
+        fee_per_byte: u64,
+    ) -> Result<Option<Vec<usize>>, SelectionError>
+    where
+        J: JubjubEngine + pairing::MultiMillerLoop,
+    {
+        let outputs = self.outputs.len() + 1;
+        let paid = self.paid + value as u128;
+        let mut spends = self.spends.len();
+        let mut spent = self.spent;
+        // The largest notes taken so far are the first `largest` ones
+        let mut largest = 0;
+        loop {
+            let size = planned_size(spends, outputs);
+            if size > max_size {
+                return Ok(None);
+            }
+            if spent >= paid + size as u128 * fee_per_byte as u128 {
+                return Ok(Some((0..largest).collect()));
+            }
+            let rest = &unspent[largest..];
+            if rest.is_empty() {
+                return Err(SelectionError::InsufficientFunds);
+            }
+
+            // What is still missing once one more spend is paid for. The
+            // rest is sorted largest first, so the last note that covers
+            // it is the smallest one that does.
+            let next_size = planned_size(spends + 1, outputs);
+            let shortfall = paid + next_size as u128 * fee_per_byte as u128 - spent;
+            let covering = rest
+                .iter()
+                .rposition(|note| notes[*note].value() as u128 >= shortfall);
+            if let Some(position) = covering {
+                if next_size > max_size {
+                    return Ok(None);
+                }
+                let mut positions: Vec<usize> = (0..largest).collect();
+                positions.push(largest + position);
+                return Ok(Some(positions));
+            }
+            spent += notes[rest[0]].value() as u128;
+            spends += 1;
+            largest += 1;
+        }
+    }
+
+    fn finish(self, fee_per_byte: u64) -> TransactionPlan {
+        let size = planned_size(self.spends.len(), self.outputs.len());
+        TransactionPlan {
+            fee: (size as u64).saturating_mul(fee_per_byte),
+            spends: self.spends,
+            outputs: self.outputs,
+        }
+    }
+}
+
+/// Serialized size of a transaction that doesn't expire, with `spends`
+/// spends and `outputs` outputs plus a change output.
+fn planned_size(spends: usize, outputs: usize) -> usize {
+    TRANSACTION_HEADER_SIZE
+        + spends * SPEND_PROOF_SIZE
+        + (outputs + 1) * RECEIPT_PROOF_SIZE
+        + BINDING_SIGNATURE_SIZE
+}
+
+#[cfg(test)]
+mod test {
+    use super::{plan_transactions, planned_size, select_notes, TransactionPlan};
+    use crate::{
+        errors::SelectionError,
+        keys::SaplingKey,
+        note::{Memo, Note},
+        sapling_bls12,
+    };
+    use pairing::bls12_381::Bls12;
+
+    #[test]
+    fn test_select_notes() {
+        let sapling = &*sapling_bls12::SAPLING;
+        let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+        let notes: Vec<Note<Bls12>> = [3, 10, 1, 7, 10]
+            .iter()
+            .map(|value| {
+                Note::new(
+                    sapling.clone(),
+                    key.generate_public_address(),
+                    *value,
+                    Memo([0; 32]),
+                )
+            })
+            .collect();
+
+        assert_eq!(select_notes(&notes, 0, 0).unwrap(), Vec::<usize>::new());
+        assert_eq!(select_notes(&notes, 9, 1).unwrap(), vec![1]);
+        assert_eq!(select_notes(&notes, 15, 1).unwrap(), vec![1, 4]);
+        assert_eq!(select_notes(&notes, 20, 1).unwrap(), vec![1, 4, 3]);
+        assert_eq!(select_notes(&notes, 30, 1).unwrap(), vec![1, 4, 3, 0, 2]);
+        assert!(matches!(
+            select_notes(&notes, 30, 2),
+            Err(SelectionError::InsufficientFunds)
+        ));
+        assert!(matches!(
+            select_notes(&notes, u64::MAX, u64::MAX),
+            Err(SelectionError::InsufficientFunds)
+        ));
+        assert!(matches!(
+            select_notes::<Bls12>(&[], 1, 0),
+            Err(SelectionError::InsufficientFunds)
+        ));
+    }
+
+    #[test]
+    fn test_plan_transactions() {
+        let sapling = &*sapling_bls12::SAPLING;
+        let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+        let notes: Vec<Note<Bls12>> = [3, 10, 1, 7, 10]
+            .iter()
+            .map(|value| {
+                Note::new(
+                    sapling.clone(),
+                    key.generate_public_address(),
+                    *value,
+                    Memo([0; 32]),
+                )
+            })
+            .collect();
+        let outputs: Vec<_> = [5, 8, 9]
+            .iter()
+            .map(|value| (key.generate_public_address(), *value))
+            .collect();
+
+        assert_eq!(
+            plan_transactions(&notes, &[], 10_000, 0).unwrap(),
+            Vec::<TransactionPlan>::new()
+        );
+
+        // everything fits in one transaction
+        assert_eq!(
+            plan_transactions(&notes, &outputs, 10_000, 0).unwrap(),
+            vec![TransactionPlan {
+                spends: vec![3, 4, 1],
+                outputs: vec![0, 1, 2],
+                fee: 0,
+            }]
+        );
+
+        // room for a single spend and output per transaction; the smaller
+        // outputs leave the notes of 10 for the larger ones
+        let max_size = planned_size(1, 1);
+        assert_eq!(
+            plan_transactions(&notes, &outputs, max_size, 0).unwrap(),
+            vec![
+                TransactionPlan {
+                    spends: vec![3],
+                    outputs: vec![0],
+                    fee: 0,
+                },
+                TransactionPlan {
+                    spends: vec![4],
+                    outputs: vec![1],
+                    fee: 0,
+                },
+                TransactionPlan {
+                    spends: vec![1],
+                    outputs: vec![2],
+                    fee: 0,
+                },
+            ]
+        );
+        // no single note pays 11, and two spends don't fit in the smaller
+        // transactions
+        let too_large = vec![outputs[0].clone(), (key.generate_public_address(), 11)];
+        assert!(matches!(
+            plan_transactions(&notes, &too_large, max_size, 0),
+            Err(SelectionError::OutputTooLarge(1))
+        ));
+        assert_eq!(
+            plan_transactions(&notes, &too_large, planned_size(2, 1), 0).unwrap(),
+            vec![
+                TransactionPlan {
+                    spends: vec![3],
+                    outputs: vec![0],
+                    fee: 0,
+                },
+                TransactionPlan {
+                    spends: vec![1, 2],
+                    outputs: vec![1],
+                    fee: 0,
+                },
+            ]
+        );
+
+        // the fee of every byte, change output included, must be covered
+        let fee_per_byte = 1;
+        let big_notes: Vec<Note<Bls12>> = [5_000, 3_000]
+            .iter()
+            .map(|value| {
+                Note::new(
+                    sapling.clone(),
+                    key.generate_public_address(),
+                    *value,
+                    Memo([0; 32]),
+                )
+            })
+            .collect();
+        let plans = plan_transactions(&big_notes, &outputs, 10_000, fee_per_byte).unwrap();
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].fee, planned_size(plans[0].spends.len(), 3) as u64);
+        assert!(matches!(
+            plan_transactions(&big_notes, &outputs, 10_000, 100),
+            Err(SelectionError::InsufficientFunds)
+        ));
+        assert!(matches!(
+            plan_transactions(&notes, &outputs, 0, 0),
+            Err(SelectionError::OutputTooLarge(0))
+        ));
+    }
+}
@@ -0,0 +1,155 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Pluggable verification of the zero knowledge proofs in a transaction.
+//!
+//! `Transaction::verify` checks proofs on the CPU with bellman. Other
+//! backends (GPUs, hardware accelerators, or a verifier running outside of
+//! wasm) can implement `ProofVerifier` and be passed to
+//! `Transaction::verify_with` instead. Everything that isn't a groth16 proof
+//! check, such as the signatures and small order checks, is still done by
+//! the transaction itself.
+//!
+//! `VerifyingKeys` checks proofs against verifying keys of its own rather
+//! than those of the transaction's `Sapling`, for example those of another
+//! network.
+
+use super::{errors::SaplingProofError, ReceiptProof, Sapling, SpendProof};
+use bellman::groth16;
+use ff::PrimeField;
+use std::io;
+use zcash_primitives::jubjub::JubjubEngine;
+
+pub trait ProofVerifier<J: JubjubEngine + pairing::MultiMillerLoop> {
+    /// Verify the groth16 proof on a spend against its public inputs, as
+    /// given by `SpendProof::public_inputs`.
+    fn verify_spend_proof(
+        &self,
+        sapling: &Sapling<J>,
+        spend: &SpendProof<J>,
+    ) -> Result<(), SaplingProofError>;
+
+    /// Verify the groth16 proof on a receipt against its public inputs, as
+    /// given by `ReceiptProof::public_inputs`.
+    fn verify_receipt_proof(
+        &self,
+        sapling: &Sapling<J>,
+        receipt: &ReceiptProof<J>,
+    ) -> Result<(), SaplingProofError>;
+}
+
+/// Default verifier, checking proofs with bellman on the current thread.
+pub struct CpuProofVerifier;
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> ProofVerifier<J> for CpuProofVerifier {
+    fn verify_spend_proof(
+        &self,
+        sapling: &Sapling<J>,
+        spend: &SpendProof<J>,
+    ) -> Result<(), SaplingProofError> {
+        match groth16::verify_proof(
+            &sapling.spend_verifying_key,
+            spend.proof(),
+            &spend.public_inputs()[..],
+        ) {
+            Ok(true) => Ok(()),
+            _ => Err(SaplingProofError::VerificationFailed),
+        }
+    }
+
+    fn verify_receipt_proof(
+        &self,
+        sapling: &Sapling<J>,
+        receipt: &ReceiptProof<J>,
+    ) -> Result<(), SaplingProofError> {
+        match groth16::verify_proof(
+            &sapling.receipt_verifying_key,
+            receipt.proof(),
+            &receipt.public_inputs()[..],
+        ) {
+            Ok(true) => Ok(()),
+            _ => Err(SaplingProofError::VerificationFailed),
+        }
+    }
+}
+
+/// Verifier that checks proofs with bellman against the verifying keys of a
+/// given parameter set, instead of the ones the transaction was read with.
+///
+/// Only the verifying keys are needed, which are small enough to ship with a
+/// network's configuration, unlike the proving parameters.
+/// `Sapling::verifying_keys` gives the keys of the loaded parameters.
+pub struct VerifyingKeys<J: JubjubEngine + pairing::MultiMillerLoop> {
+    spend_key: groth16::VerifyingKey<J>,
+    receipt_key: groth16::VerifyingKey<J>,
+    prepared_spend_key: groth16::PreparedVerifyingKey<J>,
+    prepared_receipt_key: groth16::PreparedVerifyingKey<J>,
+}
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> VerifyingKeys<J> {
+    pub fn new(spend_key: groth16::VerifyingKey<J>, receipt_key: groth16::VerifyingKey<J>) -> Self {
+        VerifyingKeys {
+            prepared_spend_key: groth16::prepare_verifying_key(&spend_key),
+            prepared_receipt_key: groth16::prepare_verifying_key(&receipt_key),
+            spend_key,
+            receipt_key,
+        }
+    }
+
+    /// Load the keys written by `write`: the spend verifying key followed by
+    /// the receipt verifying key.
+    pub fn read<R: io::Read>(mut reader: R) -> io::Result<Self> {
+        let spend_key = groth16::VerifyingKey::read(&mut reader)?;
+        let receipt_key = groth16::VerifyingKey::read(&mut reader)?;
+        Ok(VerifyingKeys::new(spend_key, receipt_key))
+    }
+
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.spend_key.write(&mut writer)?;
+        self.receipt_key.write(&mut writer)?;
+        Ok(())
+    }
+}
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> ProofVerifier<J> for VerifyingKeys<J> {
+    fn verify_spend_proof(
+        &self,
+        _sapling: &Sapling<J>,
+        spend: &SpendProof<J>,
+    ) -> Result<(), SaplingProofError> {
+        match groth16::verify_proof(
+            &self.prepared_spend_key,
+            spend.proof(),
+            &spend.public_inputs()[..],
+        ) {
+            Ok(true) => Ok(()),
+            _ => Err(SaplingProofError::VerificationFailed),
+        }
+    }
+
+    fn verify_receipt_proof(
+        &self,
+        _sapling: &Sapling<J>,
+        receipt: &ReceiptProof<J>,
+    ) -> Result<(), SaplingProofError> {
+        match groth16::verify_proof(
+            &self.prepared_receipt_key,
+            receipt.proof(),
+            &receipt.public_inputs()[..],
+        ) {
+            Ok(true) => Ok(()),
+            _ => Err(SaplingProofError::VerificationFailed),
+        }
+    }
+}
+
+/// Concatenate the 32 byte little endian representations of the public
+/// inputs, for verifiers that run outside of rust.
+pub fn serialize_public_inputs<F: PrimeField>(inputs: &[F]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(inputs.len() * 32);
+    for input in inputs {
+        result.extend_from_slice(input.to_repr().as_ref());
+    }
+    result
+}
@@ -3,17 +3,27 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use super::{
-    errors, is_small_order, keys::SaplingKey, merkle_note::MerkleNote, note::Note, Sapling,
+    errors, is_small_order,
+    keys::SaplingKey,
+    merkle_note::{MerkleNote, MERKLE_NOTE_SIZE},
+    note::Note,
+    proof_verifier::{CpuProofVerifier, ProofVerifier},
+    serializing::read_scalar,
+    Sapling,
 };
 use bellman::groth16;
-use ff::Field;
-use rand::{rngs::OsRng, thread_rng, Rng};
+use ff::{Field, PrimeField};
+use rand::{rngs::OsRng, RngCore};
 use zcash_primitives::jubjub::{JubjubEngine, ToUniform};
 use zcash_primitives::primitives::ValueCommitment;
 use zcash_proofs::circuit::sapling::Output;
 
 use std::{io, sync::Arc};
 
+/// Size in bytes of a serialized `ReceiptProof`: the groth16 proof (192)
+/// followed by the merkle note.
+pub const RECEIPT_PROOF_SIZE: usize = 192 + MERKLE_NOTE_SIZE;
+
 /// Parameters used when constructing proof that a new note exists. The owner
 /// of this note is the recipient of funds in a transaction. The note is signed
 /// with the owners public key so only they can read it.
@@ -40,10 +50,23 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ReceiptParams<J> {
         spender_key: &SaplingKey<J>,
         note: &Note<J>,
     ) -> Result<ReceiptParams<J>, errors::SaplingProofError> {
-        let diffie_hellman_keys = note.owner.generate_diffie_hellman_keys(&sapling.jubjub);
+        Self::new_with_rng(sapling, spender_key, note, &mut OsRng)
+    }
+
+    /// `new`, drawing the ephemeral key, the value commitment randomness and
+    /// the proof randomness from the given rng.
+    pub(crate) fn new_with_rng<R: RngCore + ?Sized>(
+        sapling: Arc<Sapling<J>>,
+        spender_key: &SaplingKey<J>,
+        note: &Note<J>,
+        mut rng: &mut R,
+    ) -> Result<ReceiptParams<J>, errors::SaplingProofError> {
+        let diffie_hellman_keys = note
+            .owner
+            .generate_diffie_hellman_keys_with_rng(&sapling.jubjub, rng);
 
         let mut buffer = [0u8; 64];
-        thread_rng().fill(&mut buffer[..]);
+        rng.fill_bytes(&mut buffer[..]);
 
         let value_commitment_randomness: J::Fs = J::Fs::to_uniform(&buffer[..]);
 
@@ -62,8 +85,15 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ReceiptParams<J> {
             commitment_randomness: Some(note.randomness),
             esk: Some(diffie_hellman_keys.0),
         };
+        #[cfg(not(feature = "test-mocks"))]
         let proof =
-            groth16::create_random_proof(output_circuit, &sapling.receipt_params, &mut OsRng)?;
+            groth16::create_random_proof(output_circuit, &sapling.receipt_params, &mut rng)?;
+        #[cfg(feature = "test-mocks")]
+        let proof = crate::test_mocks::create_placeholder_proof(
+            output_circuit,
+            &sapling.receipt_params,
+            &mut rng,
+        );
 
         let receipt_proof = ReceiptParams {
             sapling,
@@ -83,15 +113,27 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ReceiptParams<J> {
     /// Verifies the proof before returning to prevent posting broken
     /// transactions.
     pub fn post(&self) -> Result<ReceiptProof<J>, errors::SaplingProofError> {
-        let receipt_proof = ReceiptProof {
-            proof: self.proof.clone(),
-            merkle_note: self.merkle_note.clone(),
-        };
+        let receipt_proof = self.receipt_proof();
+        // A placeholder proof from `test-mocks` would fail this check
+        #[cfg(not(feature = "test-mocks"))]
         receipt_proof.verify_proof(&self.sapling)?;
 
         Ok(receipt_proof)
     }
 
+    /// Check the proof generated for this receipt, before the transaction is
+    /// posted.
+    pub fn verify_proof(&self) -> Result<(), errors::SaplingProofError> {
+        self.receipt_proof().verify_proof(&self.sapling)
+    }
+
+    fn receipt_proof(&self) -> ReceiptProof<J> {
+        ReceiptProof {
+            proof: self.proof.clone(),
+            merkle_note: self.merkle_note.clone(),
+        }
+    }
+
     /// Write the signature of this proof to the provided writer.
     ///
     /// The signature is used by the transaction to calculate the signature
@@ -102,6 +144,31 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ReceiptParams<J> {
         self.merkle_note.write(&mut writer)?;
         Ok(())
     }
+
+    /// Write the receipt for a draft of its transaction: the proof and the
+    /// merkle note, followed by the randomness of the value commitment.
+    pub(crate) fn write_draft<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.serialize_signature_fields(&mut writer)?;
+        writer.write_all(self.value_commitment_randomness.to_repr().as_ref())?;
+        Ok(())
+    }
+
+    /// Load a receipt written by `write_draft`.
+    pub(crate) fn read_draft<R: io::Read>(
+        sapling: Arc<Sapling<J>>,
+        mut reader: R,
+    ) -> Result<Self, errors::SaplingProofError> {
+        let proof = groth16::Proof::read(&mut reader)?;
+        let merkle_note = MerkleNote::read(&mut reader, sapling.clone())?;
+        let value_commitment_randomness = read_scalar(&mut reader)?;
+
+        Ok(ReceiptParams {
+            sapling,
+            proof,
+            value_commitment_randomness,
+            merkle_note,
+        })
+    }
 }
 
 /// The publicly visible values of a received note in a transaction. These
@@ -140,11 +207,33 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ReceiptProof<J> {
     /// Verify that the proof demonstrates knowledge that a note exists with
     /// the value_commitment, public_key, and note_commitment on this proof.
     pub fn verify_proof(&self, sapling: &Sapling<J>) -> Result<(), errors::SaplingProofError> {
+        self.verify_points(sapling)?;
+        CpuProofVerifier.verify_receipt_proof(sapling, self)
+    }
+
+    /// Reject value commitments and ephemeral keys of small order. This check
+    /// is independent of the proof, so it is done whichever `ProofVerifier`
+    /// checks the proof.
+    pub(crate) fn verify_points(
+        &self,
+        sapling: &Sapling<J>,
+    ) -> Result<(), errors::SaplingProofError> {
         if is_small_order(&sapling.jubjub, &self.merkle_note.value_commitment)
             || is_small_order(&sapling.jubjub, &self.merkle_note.ephemeral_public_key)
         {
             return Err(errors::SaplingProofError::VerificationFailed);
         }
+        Ok(())
+    }
+
+    /// The groth16 proof that the output is valid.
+    pub fn proof(&self) -> &groth16::Proof<J> {
+        &self.proof
+    }
+
+    /// The public inputs the output proof must be verified against, in the
+    /// order the output circuit expects them.
+    pub fn public_inputs(&self) -> [J::Fr; 5] {
         let mut public_input = [J::Fr::zero(); 5];
         let (x, y) = self.merkle_note.value_commitment.to_xy();
         public_input[0] = x;
@@ -156,14 +245,7 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ReceiptProof<J> {
 
         public_input[4] = self.merkle_note.note_commitment;
 
-        match groth16::verify_proof(
-            &sapling.receipt_verifying_key,
-            &self.proof,
-            &public_input[..],
-        ) {
-            Ok(true) => Ok(()),
-            _ => Err(errors::SaplingProofError::VerificationFailed),
-        }
+        public_input
     }
     /// Get a MerkleNote, which can be used as a node in a Merkle Tree.
     pub fn merkle_note(&self) -> MerkleNote<J> {
@@ -184,7 +266,7 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ReceiptProof<J> {
 
 #[cfg(test)]
 mod test {
-    use super::{ReceiptParams, ReceiptProof};
+    use super::{ReceiptParams, ReceiptProof, RECEIPT_PROOF_SIZE};
     use crate::{
         keys::SaplingKey,
         note::{Memo, Note},
@@ -218,6 +300,7 @@ mod test {
         proof
             .write(&mut serialized_proof)
             .expect("Should be able to serialize proof");
+        assert_eq!(serialized_proof.len(), RECEIPT_PROOF_SIZE);
         let read_back_proof: ReceiptProof<Bls12> =
             ReceiptProof::read(sapling.clone(), &mut serialized_proof[..].as_ref())
                 .expect("Should be able to deserialize valid proof");
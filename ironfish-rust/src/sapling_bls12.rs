@@ -16,15 +16,23 @@ pub type Sapling = super::Sapling<Bls12>;
 pub type ProposedTransaction = super::ProposedTransaction<Bls12>;
 pub type ProposedSpend = super::SpendParams<Bls12>;
 pub type Transaction = super::Transaction<Bls12>;
+pub type TransactionStream<R> = super::TransactionStream<Bls12, R>;
+pub type UnsignedTransaction = super::UnsignedTransaction<Bls12>;
 pub type ReceiptProof = super::ReceiptProof<Bls12>;
 pub type SimpleTransaction = super::SimpleTransaction<Bls12>;
 pub type SpendProof = super::SpendProof<Bls12>;
 pub type Note = super::Note<Bls12>;
 pub type MerkleNote = super::MerkleNote<Bls12>;
 pub type MerkleNoteHash = super::MerkleNoteHash<Bls12>;
+pub type VerifyingKeys = super::proof_verifier::VerifyingKeys<Bls12>;
 
 // Loads the Sapling object once when dereferenced,
 // then reuses the reference on future calls.
+//
+// The parameters are large, so they are only ever shared through this Arc:
+// `SAPLING.clone()` is a reference count increment, and every key, note and
+// transaction created from it, including transactions read back for
+// verification, points at this same instance.
 lazy_static! {
     pub static ref SAPLING: Arc<Sapling> = Arc::new(load());
 }
@@ -56,12 +56,7 @@ pub(crate) fn bytes_to_hex(bytes: &[u8]) -> String {
 
 /// Output the hexadecimal String as bytes
 pub(crate) fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, ()> {
-    let mut bite_iterator = hex.as_bytes().iter().map(|b| match b {
-        b'0'..=b'9' => Ok(b - b'0'),
-        b'a'..=b'f' => Ok(b - b'a' + 10),
-        b'A'..=b'F' => Ok(b - b'A' + 10),
-        _ => Err(()),
-    });
+    let mut bite_iterator = hex.as_bytes().iter().map(|b| hex_digit(*b).ok_or(()));
     let mut bytes = Vec::new();
     let mut high = bite_iterator.next();
     let mut low = bite_iterator.next();
@@ -78,6 +73,16 @@ pub(crate) fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, ()> {
     Ok(bytes)
 }
 
+/// Value of a single hexadecimal digit, in either case.
+pub(crate) fn hex_digit(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
 pub(crate) mod aead {
     use crate::errors;
     use crypto::{
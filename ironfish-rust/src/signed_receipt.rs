@@ -0,0 +1,158 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Off-chain proof that the owner of a public address received a note.
+//!
+//! The recipient signs the note's commitment, owner, value and memo with
+//! their incoming viewing key. A public address is a diversifier point `g_d`
+//! together with the transmission key `pk_d = ivk * g_d`, so a Schnorr
+//! signature over the generator `g_d` can be checked by anyone holding
+//! the address, without revealing the view key.
+
+use super::{
+    errors,
+    keys::{IncomingViewKey, PublicAddress},
+    note::Note,
+    serializing::{point_to_bytes, read_scalar, scalar_to_bytes},
+    Sapling,
+};
+use blake2b_simd::Params as Blake2b;
+use rand::{thread_rng, Rng};
+use zcash_primitives::jubjub::{edwards, JubjubEngine, ToUniform, Unknown};
+
+const SIGNED_RECEIPT_PERSONALIZATION: &[u8; 16] = b"Iron Fish rcptsg";
+
+/// Size of the signed part of a receipt: note commitment, owner address,
+/// value and memo.
+const MESSAGE_SIZE: usize = 32 + 43 + 8 + 32;
+
+/// Size of a signed receipt: the message followed by the signature point and
+/// scalar.
+pub const SIGNED_RECEIPT_SIZE: usize = MESSAGE_SIZE + 32 + 32;
+
+/// Create a signed receipt for a note, proving that the holder of the
+/// incoming view key for the note's owner address received it.
+///
+/// Fails with `NoteError::KeyError` if the note isn't owned by the given key.
+pub fn sign_receipt<J: JubjubEngine + pairing::MultiMillerLoop>(
+    note: &Note<J>,
+    incoming_view_key: &IncomingViewKey<J>,
+) -> Result<Vec<u8>, errors::NoteError> {
+    let jubjub = &incoming_view_key.sapling.jubjub;
+    let owner = &note.owner;
+    if owner
+        .diversifier_point
+        .mul(incoming_view_key.view_key, jubjub)
+        != owner.transmission_key
+    {
+        return Err(errors::NoteError::KeyError);
+    }
+
+    let mut receipt = Vec::with_capacity(SIGNED_RECEIPT_SIZE);
+    receipt.extend_from_slice(&note.commitment());
+    receipt.extend_from_slice(&owner.public_address());
+    receipt.extend_from_slice(&note.value().to_le_bytes());
+    receipt.extend_from_slice(&note.memo().0);
+
+    let mut buffer = [0u8; 64];
+    thread_rng().fill(&mut buffer[..]);
+    let nonce = J::Fs::to_uniform(&buffer[..]);
+    let nonce_point = point_to_bytes(&owner.diversifier_point.mul(nonce, jubjub))?;
+
+    let challenge = challenge::<J>(&nonce_point, &receipt);
+    let response = nonce + challenge * incoming_view_key.view_key;
+
+    receipt.extend_from_slice(&nonce_point);
+    receipt.extend_from_slice(&scalar_to_bytes(&response));
+    Ok(receipt)
+}
+
+/// Check that a signed receipt was created by the owner of the given public
+/// address for a note paid to that address.
+///
+/// This only checks the signature. The caller still needs to check that the
+/// note commitment at the start of the receipt is actually on the chain.
+pub fn verify_signed_receipt<J: JubjubEngine + pairing::MultiMillerLoop>(
+    sapling: &Sapling<J>,
+    receipt: &[u8],
+    public_address: &PublicAddress<J>,
+) -> bool {
+    if receipt.len() != SIGNED_RECEIPT_SIZE {
+        return false;
+    }
+    let (message, signature) = receipt.split_at(MESSAGE_SIZE);
+    if message[32..75] != public_address.public_address()[..] {
+        return false;
+    }
+
+    let nonce_point = match edwards::Point::<J, Unknown>::read(&signature[..32], &sapling.jubjub) {
+        Ok(point) => point,
+        Err(_) => return false,
+    };
+    let response: J::Fs = match read_scalar(&signature[32..]) {
+        Ok(scalar) => scalar,
+        Err(_) => return false,
+    };
+    let challenge = challenge::<J>(&signature[..32], message);
+
+    let lhs: edwards::Point<J, Unknown> = public_address
+        .diversifier_point
+        .mul(response, &sapling.jubjub)
+        .into();
+    let key_part: edwards::Point<J, Unknown> = public_address
+        .transmission_key
+        .mul(challenge, &sapling.jubjub)
+        .into();
+    lhs == nonce_point.add(&key_part, &sapling.jubjub)
+}
+
+fn challenge<J: JubjubEngine + pairing::MultiMillerLoop>(
+    nonce_point: &[u8],
+    message: &[u8],
+) -> J::Fs {
+    let hash = Blake2b::new()
+        .hash_length(64)
+        .personal(SIGNED_RECEIPT_PERSONALIZATION)
+        .to_state()
+        .update(nonce_point)
+        .update(message)
+        .finalize();
+    J::Fs::to_uniform(hash.as_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{sign_receipt, verify_signed_receipt, SIGNED_RECEIPT_SIZE};
+    use crate::{
+        keys::SaplingKey,
+        note::{Memo, Note},
+        sapling_bls12,
+    };
+    use pairing::bls12_381::Bls12;
+
+    #[test]
+    fn test_signed_receipt() {
+        let sapling = &*sapling_bls12::SAPLING;
+        let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+        let other_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+        let address = key.generate_public_address();
+        let note = Note::new(sapling.clone(), address.clone(), 42, Memo::from("invoice"));
+
+        let receipt = sign_receipt(&note, key.incoming_view_key()).unwrap();
+        assert_eq!(receipt.len(), SIGNED_RECEIPT_SIZE);
+        assert!(verify_signed_receipt(sapling, &receipt, &address));
+
+        // A different address of the same key doesn't match the receipt
+        let other_address = key.generate_public_address();
+        assert!(!verify_signed_receipt(sapling, &receipt, &other_address));
+
+        // Tampering with the value invalidates the signature
+        let mut tampered = receipt.clone();
+        tampered[75] ^= 1;
+        assert!(!verify_signed_receipt(sapling, &tampered, &address));
+
+        assert!(!verify_signed_receipt(sapling, &receipt[1..], &address));
+        assert!(sign_receipt(&note, other_key.incoming_view_key()).is_err());
+    }
+}
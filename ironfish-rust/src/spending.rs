@@ -9,15 +9,16 @@ use super::{
     merkle_note_hash::MerkleNoteHash,
     note::Note,
     nullifiers::Nullifier,
+    proof_verifier::{CpuProofVerifier, ProofVerifier},
     serializing::read_scalar,
-    witness::WitnessTrait,
+    witness::{WitnessTrait, NOTE_TREE_DEPTH},
     Sapling,
 };
 use bellman::gadgets::multipack;
 use bellman::groth16;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use ff::Field;
-use rand::{rngs::OsRng, thread_rng, Rng};
+use rand::{rngs::OsRng, RngCore};
 
 use zcash_proofs::circuit::sapling::Spend;
 
@@ -27,6 +28,11 @@ use zcash_primitives::jubjub::{edwards, FixedGenerators, JubjubEngine, ToUniform
 use zcash_primitives::primitives::ValueCommitment;
 use zcash_primitives::redjubjub;
 
+/// Size in bytes of a serialized `SpendProof`: the groth16 proof (192), value
+/// commitment (32), randomized public key (32), root hash (32), tree size (4),
+/// nullifier (32) and authorizing signature (64).
+pub const SPEND_PROOF_SIZE: usize = 388;
+
 /// Parameters used when constructing proof that the spender owns a note with
 /// a given value.
 ///
@@ -81,20 +87,42 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SpendParams<J> {
     /// This is the only time this API thinks about the merkle tree. The witness
     /// contains the root-hash at the time the witness was created and the path
     /// to verify the location of that note in the tree.
+    ///
+    /// Notes with a value of zero can't be spent and fail with
+    /// `ZeroValueSpend`. A witness that doesn't lead from the note to its
+    /// root hash, or whose path isn't `NOTE_TREE_DEPTH` long, fails with
+    /// `InconsistentWitness` before any proving starts.
     pub fn new(
         sapling: Arc<Sapling<J>>,
         spender_key: SaplingKey<J>,
         note: &Note<J>,
         witness: &dyn WitnessTrait<J>,
     ) -> Result<SpendParams<J>, errors::SaplingProofError> {
+        Self::new_with_rng(sapling, spender_key, note, witness, &mut OsRng)
+    }
+
+    /// `new`, drawing the value commitment randomness, the public key
+    /// randomness and the proof randomness from the given rng.
+    pub(crate) fn new_with_rng<R: RngCore + ?Sized>(
+        sapling: Arc<Sapling<J>>,
+        spender_key: SaplingKey<J>,
+        note: &Note<J>,
+        witness: &dyn WitnessTrait<J>,
+        mut rng: &mut R,
+    ) -> Result<SpendParams<J>, errors::SaplingProofError> {
+        if note.value == 0 {
+            return Err(errors::SaplingProofError::ZeroValueSpend);
+        }
         // This is a sanity check; it would be caught in proving the circuit anyway,
         // but this gives us more information in the event of a failure
-        if !witness.verify(&MerkleNoteHash::new(note.commitment_point())) {
+        if witness.get_auth_path().len() != NOTE_TREE_DEPTH
+            || !witness.verify(&MerkleNoteHash::new(note.commitment_point()))
+        {
             return Err(errors::SaplingProofError::InconsistentWitness);
         }
 
         let mut buffer = [0u8; 64];
-        thread_rng().fill(&mut buffer[..]);
+        rng.fill_bytes(&mut buffer[..]);
 
         let value_commitment = ValueCommitment::<J> {
             value: note.value,
@@ -102,7 +130,7 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SpendParams<J> {
         };
 
         let mut buffer = [0u8; 64];
-        thread_rng().fill(&mut buffer[..]);
+        rng.fill_bytes(&mut buffer[..]);
         let public_key_randomness = J::Fs::to_uniform(&buffer[..]);
 
         let proof_generation_key = spender_key.sapling_proof_generation_key();
@@ -117,7 +145,14 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SpendParams<J> {
             anchor: Some(witness.root_hash()),
             ar: Some(public_key_randomness),
         };
-        let proof = groth16::create_random_proof(spend_circuit, &sapling.spend_params, &mut OsRng)?;
+        #[cfg(not(feature = "test-mocks"))]
+        let proof = groth16::create_random_proof(spend_circuit, &sapling.spend_params, &mut rng)?;
+        #[cfg(feature = "test-mocks")]
+        let proof = crate::test_mocks::create_placeholder_proof(
+            spend_circuit,
+            &sapling.spend_params,
+            &mut rng,
+        );
 
         let randomized_public_key =
             redjubjub::PublicKey(spender_key.authorizing_key.clone().into()).randomize(
@@ -149,28 +184,23 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SpendParams<J> {
         &self,
         signature_hash: &[u8; 32],
     ) -> Result<SpendProof<J>, errors::SaplingProofError> {
-        let private_key = redjubjub::PrivateKey::<J>(self.spender_key.spend_authorizing_key);
-        let randomized_private_key = private_key.randomize(self.public_key_randomness);
-        let randomized_public_key = redjubjub::PublicKey::from_private(
-            &randomized_private_key,
-            FixedGenerators::SpendingKeyGenerator,
-            &self.sapling.jubjub,
-        );
-        if randomized_public_key.0 != self.randomized_public_key.0 {
-            return Err(errors::SaplingProofError::SigningError);
-        }
-        let mut data_to_be_signed = [0; 64];
-        randomized_public_key
-            .0
-            .write(&mut data_to_be_signed[..32])?;
-        data_to_be_signed[32..].copy_from_slice(&signature_hash[..]);
+        self.post_with_rng(signature_hash, &mut OsRng)
+    }
 
-        let authorizing_signature = randomized_private_key.sign(
-            &data_to_be_signed,
-            &mut OsRng,
-            FixedGenerators::SpendingKeyGenerator,
+    /// `post`, drawing the signature randomness from the given rng.
+    pub(crate) fn post_with_rng<R: RngCore + ?Sized>(
+        &self,
+        signature_hash: &[u8; 32],
+        rng: &mut R,
+    ) -> Result<SpendProof<J>, errors::SaplingProofError> {
+        let (randomized_public_key, authorizing_signature) = sign_spend(
             &self.sapling.jubjub,
-        );
+            &self.spender_key,
+            self.public_key_randomness,
+            &self.randomized_public_key,
+            signature_hash,
+            rng,
+        )?;
 
         let spend_proof = SpendProof {
             proof: self.proof.clone(),
@@ -182,6 +212,8 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SpendParams<J> {
             authorizing_signature,
         };
 
+        // A placeholder proof from `test-mocks` would fail this check
+        #[cfg(not(feature = "test-mocks"))]
         spend_proof.verify_proof(&self.sapling)?;
 
         Ok(spend_proof)
@@ -207,6 +239,31 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SpendParams<J> {
         )
     }
 
+    /// Check the proof generated for this spend, before it is signed.
+    ///
+    /// `post` does this as well, but a coordinator assembling a transaction
+    /// from several contributors can use this to reject a bad proof early.
+    pub fn verify_proof(&self) -> Result<(), errors::SaplingProofError> {
+        let value_commitment = self.value_commitment();
+        if is_small_order(&self.sapling.jubjub, &value_commitment) {
+            return Err(errors::SaplingProofError::VerificationFailed);
+        }
+        let public_inputs = spend_public_inputs(
+            &self.randomized_public_key,
+            &value_commitment,
+            &self.root_hash,
+            &self.nullifier,
+        );
+        match groth16::verify_proof(
+            &self.sapling.spend_verifying_key,
+            &self.proof,
+            &public_inputs[..],
+        ) {
+            Ok(true) => Ok(()),
+            _ => Err(errors::SaplingProofError::VerificationFailed),
+        }
+    }
+
     /// Get the value_commitment from this proof as an edwards Point.
     ///
     /// This integrates the value and randomness into a single point, using
@@ -214,6 +271,179 @@ impl<'a, J: JubjubEngine + pairing::MultiMillerLoop> SpendParams<J> {
     pub(crate) fn value_commitment(&self) -> edwards::Point<J, Unknown> {
         self.value_commitment.cm(&self.sapling.jubjub).into()
     }
+
+    /// Write the spend for a draft of its transaction: the unsigned spend,
+    /// followed by the value of the note and the randomness of its value
+    /// commitment. The spender's key is not written.
+    pub(crate) fn write_draft<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.unsigned().write(&mut writer)?;
+        writer.write_u64::<LittleEndian>(self.value_commitment.value)?;
+        writer.write_all(self.value_commitment.randomness.to_repr().as_ref())?;
+        Ok(())
+    }
+
+    /// Load a spend written by `write_draft`, to be signed with
+    /// `spender_key` when the transaction is posted.
+    ///
+    /// Fails with `SigningError` if the spend wasn't proven with
+    /// `spender_key`, and with `VerificationFailed` if the value doesn't
+    /// match the value commitment.
+    pub(crate) fn read_draft<R: io::Read>(
+        sapling: Arc<Sapling<J>>,
+        spender_key: SaplingKey<J>,
+        mut reader: R,
+    ) -> Result<Self, errors::SaplingProofError> {
+        let unsigned = UnsignedSpend::read(&sapling.jubjub, &mut reader)?;
+        let value_commitment = ValueCommitment::<J> {
+            value: reader.read_u64::<LittleEndian>()?,
+            randomness: read_scalar(&mut reader)?,
+        };
+        let commitment: edwards::Point<J, Unknown> = value_commitment.cm(&sapling.jubjub).into();
+        if commitment != unsigned.value_commitment {
+            return Err(errors::SaplingProofError::VerificationFailed);
+        }
+        let randomized_public_key =
+            redjubjub::PublicKey(spender_key.authorizing_key.clone().into()).randomize(
+                unsigned.public_key_randomness,
+                FixedGenerators::SpendingKeyGenerator,
+                &sapling.jubjub,
+            );
+        if randomized_public_key.0 != unsigned.randomized_public_key.0 {
+            return Err(errors::SaplingProofError::SigningError);
+        }
+
+        Ok(SpendParams {
+            sapling,
+            spender_key,
+            public_key_randomness: unsigned.public_key_randomness,
+            proof: unsigned.proof,
+            value_commitment,
+            randomized_public_key,
+            root_hash: unsigned.root_hash,
+            tree_size: unsigned.tree_size,
+            nullifier: unsigned.nullifier,
+        })
+    }
+
+    /// The public values of this spend and the randomness needed to sign
+    /// it, without the spender's key.
+    pub(crate) fn unsigned(&self) -> UnsignedSpend<J> {
+        UnsignedSpend {
+            proof: self.proof.clone(),
+            value_commitment: self.value_commitment(),
+            randomized_public_key: redjubjub::PublicKey(self.randomized_public_key.0.clone()),
+            root_hash: self.root_hash,
+            tree_size: self.tree_size,
+            nullifier: self.nullifier,
+            public_key_randomness: self.public_key_randomness,
+        }
+    }
+}
+
+/// A spend that has been proven but not yet signed. Holds everything that
+/// ends up in the `SpendProof` except the authorizing signature, plus the
+/// public key randomness needed to create that signature, but not the key
+/// of the spender. It is the form of a spend that is handed to an offline
+/// signer.
+pub struct UnsignedSpend<J: JubjubEngine + pairing::MultiMillerLoop> {
+    pub(crate) proof: groth16::Proof<J>,
+    pub(crate) value_commitment: edwards::Point<J, Unknown>,
+    pub(crate) randomized_public_key: redjubjub::PublicKey<J>,
+    pub(crate) root_hash: J::Fr,
+    pub(crate) tree_size: u32,
+    pub(crate) nullifier: Nullifier,
+
+    /// Referred to as `ar` in the literature. Anyone who knows it can link
+    /// the randomized public key back to the spender's authorizing key, so
+    /// it should only be shared with the signer.
+    pub(crate) public_key_randomness: J::Fs,
+}
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> UnsignedSpend<J> {
+    /// Load an UnsignedSpend written by `write`.
+    pub fn read<R: io::Read>(
+        jubjub: &J::Params,
+        mut reader: R,
+    ) -> Result<Self, errors::SaplingProofError> {
+        let proof = groth16::Proof::read(&mut reader)?;
+        let value_commitment = edwards::Point::<J, Unknown>::read(&mut reader, &jubjub)?;
+        let randomized_public_key = redjubjub::PublicKey::<J>::read(&mut reader, &jubjub)?;
+        let root_hash = read_scalar(&mut reader)?;
+        let tree_size = reader.read_u32::<LittleEndian>()?;
+        let mut nullifier = [0; 32];
+        reader.read_exact(&mut nullifier)?;
+        let public_key_randomness = read_scalar(&mut reader)?;
+
+        Ok(UnsignedSpend {
+            proof,
+            value_commitment,
+            randomized_public_key,
+            root_hash,
+            tree_size,
+            nullifier,
+            public_key_randomness,
+        })
+    }
+
+    /// Store the fields of the spend, followed by the public key randomness.
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        self.serialize_signature_fields(&mut writer)?;
+        writer.write_all(self.public_key_randomness.to_repr().as_ref())?;
+        Ok(())
+    }
+
+    /// Sign the spend with the key of the spender, giving the SpendProof
+    /// that is posted in the transaction.
+    ///
+    /// Fails with `SigningError` if the key isn't the one the spend was
+    /// proven with, and verifies the proof before returning like
+    /// `SpendParams::post`.
+    pub fn sign(
+        &self,
+        sapling: &Sapling<J>,
+        spender_key: &SaplingKey<J>,
+        signature_hash: &[u8; 32],
+    ) -> Result<SpendProof<J>, errors::SaplingProofError> {
+        let (randomized_public_key, authorizing_signature) = sign_spend(
+            &sapling.jubjub,
+            spender_key,
+            self.public_key_randomness,
+            &self.randomized_public_key,
+            signature_hash,
+            &mut OsRng,
+        )?;
+
+        let spend_proof = SpendProof {
+            proof: self.proof.clone(),
+            value_commitment: self.value_commitment.clone(),
+            randomized_public_key,
+            root_hash: self.root_hash,
+            tree_size: self.tree_size,
+            nullifier: self.nullifier,
+            authorizing_signature,
+        };
+
+        // A placeholder proof from `test-mocks` would fail this check
+        #[cfg(not(feature = "test-mocks"))]
+        spend_proof.verify_proof(sapling)?;
+
+        Ok(spend_proof)
+    }
+
+    /// Serialize the fields that are needed in calculating a signature to
+    /// the provided writer (probably a Blake2B writer). These are the same
+    /// as those of the signed SpendProof.
+    pub(crate) fn serialize_signature_fields<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        serialize_signature_fields(
+            writer,
+            &self.proof,
+            &self.value_commitment,
+            &self.randomized_public_key,
+            &self.root_hash,
+            self.tree_size,
+            &self.nullifier,
+        )
+    }
 }
 /// The publicly visible value of a spent note. These get serialized to prove
 /// that the owner once had access to these values. It also publishes the
@@ -319,6 +549,29 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> SpendProof<J> {
         self.tree_size
     }
 
+    /// The value commitment (`cv` in the literature) of the spent note as
+    /// bytes.
+    pub fn value_commitment(&self) -> [u8; 32] {
+        let mut result = [0; 32];
+        self.value_commitment
+            .write(&mut result[..])
+            .expect("value commitment should be convertible to bytes");
+        result
+    }
+
+    /// The randomized public key (`rk` in the literature) that the
+    /// authorizing signature on this spend must verify against.
+    pub fn randomized_public_key(&self) -> &redjubjub::PublicKey<J> {
+        &self.randomized_public_key
+    }
+
+    /// Signature of the note owner authorizing this spend, made with the
+    /// key behind `randomized_public_key` over the transaction signature
+    /// hash.
+    pub fn authorizing_signature(&self) -> &redjubjub::Signature {
+        &self.authorizing_signature
+    }
+
     /// Verify that the signature on this proof is signing the provided input
     /// with the randomized_public_key on this proof.
     pub fn verify_signature(
@@ -354,30 +607,36 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> SpendProof<J> {
     /// This entails converting all the values to appropriate inputs to the
     /// bellman circuit and executing it.
     pub fn verify_proof(&self, sapling: &Sapling<J>) -> Result<(), errors::SaplingProofError> {
+        self.verify_value_commitment(sapling)?;
+        CpuProofVerifier.verify_spend_proof(sapling, self)
+    }
+
+    /// Reject value commitments of small order. This check is independent of
+    /// the proof, so it is done whichever `ProofVerifier` checks the proof.
+    pub(crate) fn verify_value_commitment(
+        &self,
+        sapling: &Sapling<J>,
+    ) -> Result<(), errors::SaplingProofError> {
         if is_small_order(&sapling.jubjub, &self.value_commitment) {
             return Err(errors::SaplingProofError::VerificationFailed);
         }
+        Ok(())
+    }
 
-        let mut public_input = [J::Fr::zero(); 7];
-        let (x, y) = self.randomized_public_key.0.to_xy();
-        public_input[0] = x;
-        public_input[1] = y;
-
-        let (x, y) = self.value_commitment.to_xy();
-        public_input[2] = x;
-        public_input[3] = y;
-
-        public_input[4] = self.root_hash;
-
-        let nullifier = multipack::bytes_to_bits_le(&self.nullifier);
-        let nullifier = multipack::compute_multipacking(&nullifier);
-        public_input[5] = nullifier[0];
-        public_input[6] = nullifier[1];
+    /// The groth16 proof that the spend is valid.
+    pub fn proof(&self) -> &groth16::Proof<J> {
+        &self.proof
+    }
 
-        match groth16::verify_proof(&sapling.spend_verifying_key, &self.proof, &public_input[..]) {
-            Ok(true) => Ok(()),
-            _ => Err(errors::SaplingProofError::VerificationFailed),
-        }
+    /// The public inputs the spend proof must be verified against, in the
+    /// order the spend circuit expects them.
+    pub fn public_inputs(&self) -> [J::Fr; 7] {
+        spend_public_inputs(
+            &self.randomized_public_key,
+            &self.value_commitment,
+            &self.root_hash,
+            &self.nullifier,
+        )
     }
 
     /// Serialize the fields that are needed in calculating a signature to
@@ -395,6 +654,71 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> SpendProof<J> {
     }
 }
 
+/// Public inputs of the spend circuit, in the order it expects them. Shared
+/// by SpendProof and SpendParams so proofs can be checked before and after
+/// the spend is signed.
+fn spend_public_inputs<J: JubjubEngine + pairing::MultiMillerLoop>(
+    randomized_public_key: &redjubjub::PublicKey<J>,
+    value_commitment: &edwards::Point<J, Unknown>,
+    root_hash: &J::Fr,
+    nullifier: &Nullifier,
+) -> [J::Fr; 7] {
+    let mut public_input = [J::Fr::zero(); 7];
+    let (x, y) = randomized_public_key.0.to_xy();
+    public_input[0] = x;
+    public_input[1] = y;
+
+    let (x, y) = value_commitment.to_xy();
+    public_input[2] = x;
+    public_input[3] = y;
+
+    public_input[4] = *root_hash;
+
+    let nullifier = multipack::bytes_to_bits_le(nullifier);
+    let nullifier = multipack::compute_multipacking(&nullifier);
+    public_input[5] = nullifier[0];
+    public_input[6] = nullifier[1];
+
+    public_input
+}
+
+/// Sign the signature hash of a transaction with the spend authorizing key
+/// of `spender_key`, randomized by `public_key_randomness`. Fails with
+/// `SigningError` if the randomized public key isn't the one the spend was
+/// proven with.
+fn sign_spend<J: JubjubEngine + pairing::MultiMillerLoop, R: RngCore + ?Sized>(
+    jubjub: &J::Params,
+    spender_key: &SaplingKey<J>,
+    public_key_randomness: J::Fs,
+    proven_public_key: &redjubjub::PublicKey<J>,
+    signature_hash: &[u8; 32],
+    mut rng: &mut R,
+) -> Result<(redjubjub::PublicKey<J>, redjubjub::Signature), errors::SaplingProofError> {
+    let private_key = redjubjub::PrivateKey::<J>(spender_key.spend_authorizing_key);
+    let randomized_private_key = private_key.randomize(public_key_randomness);
+    let randomized_public_key = redjubjub::PublicKey::from_private(
+        &randomized_private_key,
+        FixedGenerators::SpendingKeyGenerator,
+        jubjub,
+    );
+    if randomized_public_key.0 != proven_public_key.0 {
+        return Err(errors::SaplingProofError::SigningError);
+    }
+    let mut data_to_be_signed = [0; 64];
+    randomized_public_key
+        .0
+        .write(&mut data_to_be_signed[..32])?;
+    data_to_be_signed[32..].copy_from_slice(&signature_hash[..]);
+
+    let authorizing_signature = randomized_private_key.sign(
+        &data_to_be_signed,
+        &mut rng,
+        FixedGenerators::SpendingKeyGenerator,
+        jubjub,
+    );
+    Ok((randomized_public_key, authorizing_signature))
+}
+
 /// Given a writer (probably a Blake2b hasher), write byte representations
 /// of the parameters that are used in calculating the signature of a transaction.
 /// This function is called from both SpendProof and SpendParams because
@@ -423,17 +747,46 @@ mod test {
     extern crate bellman;
     extern crate pairing;
 
-    use super::{SpendParams, SpendProof};
+    use super::{SpendParams, SpendProof, SPEND_PROOF_SIZE};
     use crate::{
+        errors::SaplingProofError,
         keys::SaplingKey,
         note::{Memo, Note},
         sapling_bls12,
-        test_util::make_fake_witness,
+        test_util::{make_fake_witness, make_fake_witness_at_depth},
+        witness::{WitnessTrait, NOTE_TREE_DEPTH},
+        MerkleNoteHash,
     };
     use pairing::bls12_381::Bls12;
     use rand::prelude::*;
     use rand::{thread_rng, Rng};
 
+    #[test]
+    fn test_witness_depth_mismatch() {
+        let sapling = sapling_bls12::SAPLING.clone();
+        let key = SaplingKey::generate_key(sapling.clone());
+        let note = Note::new(
+            sapling.clone(),
+            key.generate_public_address(),
+            42,
+            Memo([0; 32]),
+        );
+
+        // The witness is valid for a smaller tree, but can't be proven
+        let witness = make_fake_witness_at_depth(sapling.clone(), &note, 4);
+        assert!(witness.verify(&MerkleNoteHash::new(note.commitment_point())));
+        assert!(matches!(
+            SpendParams::new(sapling.clone(), key.clone(), &note, &witness),
+            Err(SaplingProofError::InconsistentWitness)
+        ));
+
+        let witness = make_fake_witness_at_depth(sapling.clone(), &note, NOTE_TREE_DEPTH + 1);
+        assert!(matches!(
+            SpendParams::new(sapling.clone(), key, &note, &witness),
+            Err(SaplingProofError::InconsistentWitness)
+        ));
+    }
+
     #[test]
     fn test_spend_round_trip() {
         let sapling = sapling_bls12::SAPLING.clone();
@@ -480,6 +833,7 @@ mod test {
         proof
             .write(&mut serialized_proof)
             .expect("should be able to serialize proof");
+        assert_eq!(serialized_proof.len(), SPEND_PROOF_SIZE);
         let read_back_proof: SpendProof<Bls12> =
             SpendProof::read(&sapling.jubjub, &mut serialized_proof[..].as_ref())
                 .expect("should be able to deserialize valid proof");
@@ -497,6 +851,15 @@ mod test {
         );
         assert_eq!(proof.root_hash, read_back_proof.root_hash);
         assert_eq!(proof.nullifier, read_back_proof.nullifier);
+        // The value commitment follows the 192 byte groth16 proof
+        assert_eq!(proof.value_commitment()[..], serialized_proof[192..224]);
+        // The authorizing signature is the last 64 bytes
+        let mut signature = vec![];
+        read_back_proof
+            .authorizing_signature()
+            .write(&mut signature)
+            .unwrap();
+        assert_eq!(signature[..], serialized_proof[SPEND_PROOF_SIZE - 64..]);
         let mut serialized_again = vec![];
         read_back_proof
             .write(&mut serialized_again)
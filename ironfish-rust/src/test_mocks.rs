@@ -0,0 +1,80 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Placeholder proofs for tests. Only compiled with the `test-mocks`
+//! feature, which refuses to build without debug assertions.
+//!
+//! With the feature enabled, spends and receipts skip the groth16 prover and
+//! carry a fixed placeholder proof, which isn't checked when the transaction
+//! is posted. The verifiers of the crate still reject it; tests verify with
+//! `PlaceholderProofVerifier` instead, passed to `Transaction::verify_with`,
+//! which accepts the placeholder without checking it against the public
+//! inputs. Everything else, the signatures, the value balance and the small
+//! order checks, is still done for real. Transactions built this way prove
+//! nothing and must never leave a test.
+
+use super::{
+    errors::SaplingProofError,
+    proof_verifier::{CpuProofVerifier, ProofVerifier},
+    ReceiptProof, Sapling, SpendProof,
+};
+use bellman::{groth16, Circuit};
+use zcash_primitives::jubjub::JubjubEngine;
+
+/// Stand-in for `groth16::create_random_proof`, returning the placeholder
+/// proof for `params` without synthesizing the circuit.
+pub(crate) fn create_placeholder_proof<J, C, R>(
+    _circuit: C,
+    params: &groth16::Parameters<J>,
+    _rng: &mut R,
+) -> groth16::Proof<J>
+where
+    J: JubjubEngine + pairing::MultiMillerLoop,
+    C: Circuit<J::Fr>,
+{
+    // Points of the verifying key, so the proof can be written and read
+    // back like any other.
+    groth16::Proof {
+        a: params.vk.alpha_g1,
+        b: params.vk.beta_g2,
+        c: params.vk.delta_g1,
+    }
+}
+
+/// Whether `proof` is the placeholder that `create_placeholder_proof` makes
+/// for the parameters with verifying key `vk`.
+pub(crate) fn is_placeholder_proof<J: JubjubEngine + pairing::MultiMillerLoop>(
+    vk: &groth16::VerifyingKey<J>,
+    proof: &groth16::Proof<J>,
+) -> bool {
+    proof.a == vk.alpha_g1 && proof.b == vk.beta_g2 && proof.c == vk.delta_g1
+}
+
+/// Verifier for tests, accepting the placeholder proofs and checking any
+/// other proof like `CpuProofVerifier`.
+pub(crate) struct PlaceholderProofVerifier;
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> ProofVerifier<J> for PlaceholderProofVerifier {
+    fn verify_spend_proof(
+        &self,
+        sapling: &Sapling<J>,
+        spend: &SpendProof<J>,
+    ) -> Result<(), SaplingProofError> {
+        if is_placeholder_proof(&sapling.spend_params.vk, spend.proof()) {
+            return Ok(());
+        }
+        CpuProofVerifier.verify_spend_proof(sapling, spend)
+    }
+
+    fn verify_receipt_proof(
+        &self,
+        sapling: &Sapling<J>,
+        receipt: &ReceiptProof<J>,
+    ) -> Result<(), SaplingProofError> {
+        if is_placeholder_proof(&sapling.receipt_params.vk, receipt.proof()) {
+            return Ok(());
+        }
+        CpuProofVerifier.verify_receipt_proof(sapling, receipt)
+    }
+}
@@ -18,13 +18,23 @@ use zcash_proofs::circuit::sapling::TREE_DEPTH;
 pub(crate) fn make_fake_witness<J: JubjubEngine + pairing::MultiMillerLoop>(
     sapling: Arc<Sapling<J>>,
     note: &Note<J>,
+) -> Witness<J> {
+    make_fake_witness_at_depth(sapling, note, TREE_DEPTH)
+}
+
+/// Same as `make_fake_witness`, in a tree of the given depth.
+#[cfg(test)]
+pub(crate) fn make_fake_witness_at_depth<J: JubjubEngine + pairing::MultiMillerLoop>(
+    sapling: Arc<Sapling<J>>,
+    note: &Note<J>,
+    depth: usize,
 ) -> Witness<J> {
     let mut rng = thread_rng();
     let mut buffer = [0u8; 64];
     thread_rng().fill(&mut buffer[..]);
 
     let mut witness_auth_path = vec![];
-    for _ in 0..TREE_DEPTH {
+    for _ in 0..depth {
         witness_auth_path.push(match rng.gen() {
             false => WitnessNode::Left(J::Fr::from(rng.gen::<u64>())),
             true => WitnessNode::Right(J::Fr::from(rng.gen::<u64>())),
@@ -0,0 +1,87 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use super::ProposedTransaction;
+use crate::{
+    errors::TransactionError, keys::SaplingKey, receiving::ReceiptParams, spending::SpendParams,
+    Sapling,
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::{io, sync::Arc};
+use zcash_primitives::jubjub::JubjubEngine;
+
+/// Written at the start of every draft. Read as the header of a transaction,
+/// it gives a version no release has used, so a draft handed to
+/// `Transaction::read` fails with `UnsupportedVersion` rather than being
+/// misread.
+const DRAFT_MAGIC: &[u8; 8] = b"IFDRAFT1";
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> ProposedTransaction<J> {
+    /// Store the transaction as it stands, so a wallet can pick it up again
+    /// with `read_draft` after a restart.
+    ///
+    /// The proofs are kept, along with the randomness needed to sign them
+    /// and to compute the binding signature, but the spending key is not
+    /// written. Anyone holding a draft can link its spends to the spender
+    /// and learn the values of its notes, so it should be stored like the
+    /// wallet itself.
+    pub fn write_draft<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(DRAFT_MAGIC)?;
+        writer.write_i64::<LittleEndian>(self.transaction_fee)?;
+        writer.write_u32::<LittleEndian>(self.expiration_sequence)?;
+        writer.write_u64::<LittleEndian>(self.spends.len() as u64)?;
+        writer.write_u64::<LittleEndian>(self.receipts.len() as u64)?;
+        for spend in self.spends.iter() {
+            spend.write_draft(&mut writer)?;
+        }
+        for receipt in self.receipts.iter() {
+            receipt.write_draft(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Load a draft written by `write_draft`. `spender_key` must be the key
+    /// the spends were created with; it is needed to sign them when the
+    /// transaction is posted.
+    ///
+    /// Fails with a `SigningError` if the spends were created with another
+    /// key, and with `InvalidBalanceError` if the values don't add up to the
+    /// stored fee.
+    pub fn read_draft<R: io::Read>(
+        sapling: Arc<Sapling<J>>,
+        spender_key: &SaplingKey<J>,
+        mut reader: R,
+    ) -> Result<Self, TransactionError> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != DRAFT_MAGIC {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidData, "not a transaction draft").into(),
+            );
+        }
+        let transaction_fee = reader.read_i64::<LittleEndian>()?;
+        let expiration_sequence = reader.read_u32::<LittleEndian>()?;
+        let num_spends = reader.read_u64::<LittleEndian>()?;
+        let num_receipts = reader.read_u64::<LittleEndian>()?;
+
+        let mut transaction = ProposedTransaction::new(sapling.clone());
+        transaction.set_expiration_sequence(expiration_sequence);
+        for _ in 0..num_spends {
+            let spend = SpendParams::read_draft(sapling.clone(), spender_key.clone(), &mut reader)?;
+            let value = spend.value_commitment.value;
+            transaction.add_spend_proof(spend, value);
+        }
+        for _ in 0..num_receipts {
+            let receipt = ReceiptParams::read_draft(sapling.clone(), &mut reader)?;
+            transaction.add_receipt_proof(receipt, 0);
+        }
+        // The values of the receipts aren't stored, only their commitments,
+        // so the fee is taken from the draft and checked against the binding
+        // signature key instead of being recomputed.
+        transaction.transaction_fee = transaction_fee;
+        transaction.check_value_consistency()?;
+
+        Ok(transaction)
+    }
+}
@@ -3,35 +3,73 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use super::{
-    errors::{SaplingProofError, TransactionError},
+    errors::{SaplingProofError, TransactionError, VerificationError},
     keys::{PublicAddress, SaplingKey},
-    merkle_note::NOTE_ENCRYPTION_MINER_KEYS,
+    merkle_note::{self, NOTE_ENCRYPTION_MINER_KEYS},
     note::{Memo, Note},
-    receiving::{ReceiptParams, ReceiptProof},
-    spending::{SpendParams, SpendProof},
+    nullifiers::Nullifier,
+    proof_verifier::{CpuProofVerifier, ProofVerifier},
+    receiving::{ReceiptParams, ReceiptProof, RECEIPT_PROOF_SIZE},
+    serializing::scalar_to_bytes,
+    spending::{SpendParams, SpendProof, SPEND_PROOF_SIZE},
     witness::WitnessTrait,
     Sapling,
 };
 use blake2b_simd::Params as Blake2b;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use ff::Field;
-use rand::rngs::OsRng;
+use rand::{rngs::OsRng, RngCore, SeedableRng};
+use rand_chacha::ChaChaRng;
 
 use zcash_primitives::redjubjub::{PrivateKey, PublicKey, Signature};
 
-use std::{io, slice::Iter, sync::Arc};
+use std::{collections::HashSet, convert::TryFrom, io, slice::Iter, sync::Arc};
 use zcash_primitives::jubjub::{edwards, FixedGenerators, JubjubEngine, JubjubParams, Unknown};
 
+mod draft;
 mod simple;
 pub use simple::SimpleTransaction;
+mod stream;
 use std::ops::AddAssign;
 use std::ops::SubAssign;
+pub use stream::TransactionStream;
+mod unsigned;
+pub use unsigned::{sign_offline, UnsignedTransaction};
 
+#[cfg(feature = "serde")]
+mod serde_impl;
 #[cfg(test)]
 mod tests;
 
 const SIGNATURE_HASH_PERSONALIZATION: &[u8; 8] = b"Bnsighsh";
+const FINGERPRINT_PERSONALIZATION: &[u8; 8] = b"Bntxfngr";
 const TRANSACTION_SIGNATURE_VERSION: &[u8; 1] = &[0];
+const TRANSACTION_SIGNATURE_VERSION_EXPIRATION: &[u8; 1] = &[1];
+
+/// Transactions are versioned in the most significant byte of the spend
+/// count, which is far larger than any real number of spends. Version 0 is
+/// the original format; version 1 adds an expiration sequence after the fee.
+/// Transactions that don't expire are still written as version 0, so the
+/// serialization of existing transactions doesn't change.
+const TRANSACTION_VERSION_SHIFT: u32 = 56;
+const TRANSACTION_VERSION_ORIGINAL: u8 = 0;
+const TRANSACTION_VERSION_EXPIRATION: u8 = 1;
+
+/// Newest transaction format version this crate can read and write. Reading
+/// a transaction with a higher version fails with
+/// `TransactionError::UnsupportedVersion`.
+pub const TRANSACTION_VERSION: u8 = TRANSACTION_VERSION_EXPIRATION;
+
+/// Size in bytes of the fixed transaction header: the number of spends (8),
+/// the number of receipts (8) and the transaction fee (8).
+pub const TRANSACTION_HEADER_SIZE: usize = 24;
+
+/// Size in bytes of the expiration sequence in the header of transactions
+/// that expire.
+pub const EXPIRATION_SEQUENCE_SIZE: usize = 4;
+
+/// Size in bytes of the serialized binding signature.
+pub const BINDING_SIGNATURE_SIZE: usize = 64;
 
 /// A collection of spend and receipt proofs that can be signed and verified.
 /// In general, all the spent values should add up to all the receipt values.
@@ -66,6 +104,14 @@ pub struct ProposedTransaction<J: JubjubEngine + pairing::MultiMillerLoop> {
     /// The balance of all the spends minus all the receipts. The difference
     /// is the fee paid to the miner for mining the transaction.
     transaction_fee: i64,
+
+    /// Sequence of the last block this transaction can be mined in, or 0 if
+    /// it never expires.
+    expiration_sequence: u32,
+
+    /// Source of all randomness for transactions created `with_seed`. The OS
+    /// rng is used if this is None.
+    seeded_rng: Option<ChaChaRng>,
     //
     // NOTE: If adding fields here, you may need to add fields to
     // signature hash method, and also to Transaction.
@@ -80,21 +126,101 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ProposedTransaction<J> {
             spends: vec![],
             receipts: vec![],
             transaction_fee: 0,
+            expiration_sequence: 0,
+            seeded_rng: None,
+        }
+    }
+
+    /// Create a transaction that draws all of its randomness from a ChaCha
+    /// rng seeded with `seed`, rather than from the OS rng.
+    ///
+    /// Given the same seed, notes, witnesses and keys, and the same calls in
+    /// the same order, the posted transaction is identical byte for byte,
+    /// which makes golden file tests possible. This covers the proofs, value
+    /// commitments, ephemeral keys, signatures and the change note, but not:
+    ///  *  the notes passed in, which should be created deterministically
+    ///     too, for example with `Note::new_with_rng`
+    ///  *  the change address picked by `post` when none is given
+    ///
+    /// Never use this for real transactions: anyone who learns the seed can
+    /// recover the randomness that hides the values in the transaction.
+    pub fn with_seed(sapling: Arc<Sapling<J>>, seed: [u8; 32]) -> ProposedTransaction<J> {
+        ProposedTransaction {
+            seeded_rng: Some(ChaChaRng::from_seed(seed)),
+            ..ProposedTransaction::new(sapling)
+        }
+    }
+
+    /// Create a transaction with room for the given number of spends and
+    /// receipts, so they can be added without reallocating. Remember to count
+    /// the change receipt `post` may add.
+    pub fn with_capacity(
+        sapling: Arc<Sapling<J>>,
+        spends: usize,
+        receipts: usize,
+    ) -> ProposedTransaction<J> {
+        ProposedTransaction {
+            spends: Vec::with_capacity(spends),
+            receipts: Vec::with_capacity(receipts),
+            ..ProposedTransaction::new(sapling)
         }
     }
 
+    /// Return the transaction to the state `new` creates, dropping every
+    /// spend and receipt but keeping the memory allocated for them, so a
+    /// caller trying many candidate transactions doesn't reallocate for
+    /// each one. The seeded rng of a transaction created `with_seed` is
+    /// dropped too.
+    pub fn reset(&mut self) {
+        self.binding_signature_key = <J::Fs as Field>::zero();
+        self.binding_verification_key = edwards::Point::zero();
+        self.spends.clear();
+        self.receipts.clear();
+        self.transaction_fee = 0;
+        self.expiration_sequence = 0;
+        self.seeded_rng = None;
+    }
+
+    /// Set the sequence of the last block the transaction can be mined in.
+    /// 0, the default, means the transaction never expires.
+    pub fn set_expiration_sequence(&mut self, expiration_sequence: u32) {
+        self.expiration_sequence = expiration_sequence;
+    }
+
     /// Spend the note owned by spender_key at the given witness location.
+    ///
+    /// Fails with `DuplicateNullifier` if the note at that location is
+    /// already spent by this transaction, and with `ZeroValueSpend` if the
+    /// note has no value.
     pub fn spend(
         &mut self,
         spender_key: SaplingKey<J>,
         note: &Note<J>,
         witness: &dyn WitnessTrait<J>,
     ) -> Result<(), SaplingProofError> {
-        let proof = SpendParams::new(self.sapling.clone(), spender_key, note, witness)?;
+        // Check before creating the proof, so a duplicate fails fast
+        self.check_not_spent(&note.nullifier(&spender_key, merkle_note::position(witness)))?;
+        let mut os_rng = OsRng;
+        let rng = transaction_rng(&mut self.seeded_rng, &mut os_rng);
+        let proof =
+            SpendParams::new_with_rng(self.sapling.clone(), spender_key, note, witness, rng)?;
         self.add_spend_proof(proof, note.value());
         Ok(())
     }
 
+    /// Fail with `DuplicateNullifier` if a spend of this transaction already
+    /// has the given nullifier.
+    fn check_not_spent(&self, nullifier: &Nullifier) -> Result<(), SaplingProofError> {
+        if self
+            .spends
+            .iter()
+            .any(|spend| &spend.nullifier == nullifier)
+        {
+            return Err(SaplingProofError::DuplicateNullifier);
+        }
+        Ok(())
+    }
+
     /// Add a spend proof that was created externally.
     ///
     /// This allows for parallel immutable spends without having to take
@@ -114,14 +240,211 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ProposedTransaction<J> {
         spender_key: &SaplingKey<J>,
         note: &Note<J>,
     ) -> Result<(), SaplingProofError> {
-        let proof = ReceiptParams::new(self.sapling.clone(), spender_key, note)?;
+        let mut os_rng = OsRng;
+        let rng = transaction_rng(&mut self.seeded_rng, &mut os_rng);
+        let proof = ReceiptParams::new_with_rng(self.sapling.clone(), spender_key, note, rng)?;
+        self.add_receipt_proof(proof, note.value);
+        Ok(())
+    }
+
+    /// Add a receipt proof that was created externally, the counterpart of
+    /// `add_spend_proof`.
+    fn add_receipt_proof(&mut self, receipt: ReceiptParams<J>, note_value: u64) {
+        self.increment_binding_signature_key(&receipt.value_commitment_randomness, true);
+        self.increment_binding_verification_key(&receipt.merkle_note.value_commitment, true);
+
+        self.receipts.push(receipt);
+        self.transaction_fee -= note_value as i64;
+    }
+
+    /// Spend several notes owned by spender_key, generating their proofs in
+    /// parallel on the rayon thread pool.
+    ///
+    /// Proofs are created when spends and receipts are added rather than in
+    /// `post`, which only signs them, so this is where the parallelism pays
+    /// off. The spends are added in the given order. If any proof fails,
+    /// none of the spends are added.
+    ///
+    /// As with `spend`, fails with `DuplicateNullifier` if a note is already
+    /// spent by this transaction or appears twice in `spends`, before any
+    /// proof is created.
+    #[cfg(feature = "parallel")]
+    pub fn spend_parallel<W: WitnessTrait<J> + Sync>(
+        &mut self,
+        spender_key: &SaplingKey<J>,
+        spends: &[(Note<J>, W)],
+    ) -> Result<(), SaplingProofError>
+    where
+        Sapling<J>: Send + Sync,
+        SaplingKey<J>: Sync,
+        Note<J>: Sync,
+        SpendParams<J>: Send,
+    {
+        use rayon::prelude::*;
+
+        let mut nullifiers = HashSet::new();
+        for (note, witness) in spends.iter() {
+            let nullifier = note.nullifier(spender_key, merkle_note::position(witness));
+            self.check_not_spent(&nullifier)?;
+            if !nullifiers.insert(nullifier) {
+                return Err(SaplingProofError::DuplicateNullifier);
+            }
+        }
+
+        let seeds = self.proof_seeds(spends.len());
+        let sapling = &self.sapling;
+        let proofs = spends
+            .par_iter()
+            .zip(seeds.par_iter())
+            .map(|((note, witness), seed)| {
+                SpendParams::new_with_rng(
+                    sapling.clone(),
+                    spender_key.clone(),
+                    note,
+                    witness,
+                    &mut ChaChaRng::from_seed(*seed),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (proof, (note, _)) in proofs.into_iter().zip(spends.iter()) {
+            self.add_spend_proof(proof, note.value());
+        }
+        Ok(())
+    }
+
+    /// Create receipts for several notes, generating their proofs in
+    /// parallel on the rayon thread pool. Like `spend_parallel`, the receipts
+    /// are added in the given order, and none are added if any proof fails.
+    #[cfg(feature = "parallel")]
+    pub fn receive_parallel(
+        &mut self,
+        spender_key: &SaplingKey<J>,
+        notes: &[Note<J>],
+    ) -> Result<(), SaplingProofError>
+    where
+        Sapling<J>: Send + Sync,
+        SaplingKey<J>: Sync,
+        Note<J>: Sync,
+        ReceiptParams<J>: Send,
+    {
+        use rayon::prelude::*;
+
+        let seeds = self.proof_seeds(notes.len());
+        let sapling = &self.sapling;
+        let proofs = notes
+            .par_iter()
+            .zip(seeds.par_iter())
+            .map(|(note, seed)| {
+                ReceiptParams::new_with_rng(
+                    sapling.clone(),
+                    spender_key,
+                    note,
+                    &mut ChaChaRng::from_seed(*seed),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for (proof, note) in proofs.into_iter().zip(notes.iter()) {
+            self.add_receipt_proof(proof, note.value());
+        }
+        Ok(())
+    }
+
+    /// A seed for the rng of each of `count` proofs created in parallel,
+    /// drawn in order from the transaction's rng, so a transaction created
+    /// `with_seed` gets the same proofs whatever thread creates them.
+    #[cfg(feature = "parallel")]
+    fn proof_seeds(&mut self, count: usize) -> Vec<[u8; 32]> {
+        let mut os_rng = OsRng;
+        let rng = transaction_rng(&mut self.seeded_rng, &mut os_rng);
+        (0..count)
+            .map(|_| {
+                let mut seed = [0; 32];
+                rng.fill_bytes(&mut seed);
+                seed
+            })
+            .collect()
+    }
 
-        self.increment_binding_signature_key(&proof.value_commitment_randomness, true);
-        self.increment_binding_verification_key(&proof.merkle_note.value_commitment, true);
+    /// Estimate how many more outputs can be added to this transaction
+    /// before its fee rate drops below `target_rate`, if it pays `fee_budget`
+    /// in fees.
+    ///
+    /// The estimate assumes:
+    ///  *  the fee rate is measured as fee per byte of the serialized
+    ///     transaction
+    ///  *  every output adds exactly one receipt of `RECEIPT_PROOF_SIZE` bytes,
+    ///     so a change output, if one is needed, must be counted by the caller
+    ///  *  the spends and outputs already added stay in the transaction
+    ///
+    /// Returns 0 if the spends and outputs already added use up the budget, or
+    /// if `target_rate` is not a positive number.
+    pub fn outputs_for_fee_rate(&self, target_rate: f64, fee_budget: u64) -> usize {
+        if target_rate.is_nan() || target_rate <= 0.0 {
+            return 0;
+        }
+        let max_size = (fee_budget as f64 / target_rate).floor() as usize;
+        let current_size = header_size(version_for(self.expiration_sequence))
+            + self.spends.len() * SPEND_PROOF_SIZE
+            + self.receipts.len() * RECEIPT_PROOF_SIZE
+            + BINDING_SIGNATURE_SIZE;
+        max_size.saturating_sub(current_size) / RECEIPT_PROOF_SIZE
+    }
 
-        self.receipts.push(proof);
-        self.transaction_fee -= note.value as i64;
+    /// Sum of the values of the spends minus the sum of the values of the
+    /// receipts added so far. This is the fee the transaction would pay if
+    /// it were posted without change, and is known without generating any
+    /// more proofs.
+    pub fn transaction_fee(&self) -> i64 {
+        self.transaction_fee
+    }
 
+    /// Note commitments of the receipts added so far, in the order they were
+    /// added.
+    pub fn receipt_commitments(&self) -> Vec<[u8; 32]> {
+        self.receipts
+            .iter()
+            .map(|receipt| receipt.merkle_note.note_commitment())
+            .collect()
+    }
+
+    /// Randomness of the value commitment (`rcv`) of each spend added so
+    /// far, in the order they were added.
+    ///
+    /// Notes don't carry this randomness; it is drawn when the proof is
+    /// created and is lost once the transaction is posted, so this is the
+    /// only place to get it. It is secret: anyone who has it can recover
+    /// the value from the posted value commitment.
+    pub fn spend_value_commitment_randomness(&self) -> Vec<[u8; 32]> {
+        self.spends
+            .iter()
+            .map(|spend| scalar_to_bytes(&spend.value_commitment.randomness))
+            .collect()
+    }
+
+    /// Randomness of the value commitment (`rcv`) of each receipt added so
+    /// far, in the order they were added. Secret, like
+    /// `spend_value_commitment_randomness`.
+    pub fn receipt_value_commitment_randomness(&self) -> Vec<[u8; 32]> {
+        self.receipts
+            .iter()
+            .map(|receipt| scalar_to_bytes(&receipt.value_commitment_randomness))
+            .collect()
+    }
+
+    /// Check the proofs generated for the spends and receipts added so far,
+    /// without posting the transaction.
+    ///
+    /// Proofs are created as soon as a spend or receipt is added, so they
+    /// can be checked before the transaction is signed; this lets whoever
+    /// assembles a transaction reject a bad proof before anything is
+    /// finalized.
+    pub fn verify_proofs(&self) -> Result<(), TransactionError> {
+        for spend in self.spends.iter() {
+            spend.verify_proof()?;
+        }
+        for receipt in self.receipts.iter() {
+            receipt.verify_proof()?;
+        }
         Ok(())
     }
 
@@ -141,28 +464,162 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ProposedTransaction<J> {
         change_goes_to: Option<PublicAddress<J>>,
         intended_transaction_fee: u64,
     ) -> Result<Transaction<J>, TransactionError> {
-        let change_amount = self.transaction_fee - intended_transaction_fee as i64;
+        self.post_with_progress(
+            spender_key,
+            change_goes_to,
+            intended_transaction_fee,
+            |_, _| {},
+        )
+    }
+
+    /// Post the transaction like `post`, calling `progress(completed, total)`
+    /// after each step of posting finishes, for showing a progress bar.
+    ///
+    /// The steps are the proof of the change note, if there is change, the
+    /// binding signature, and the signature of each spend. The proofs of the
+    /// spends and receipts themselves are created by `spend` and `receive`,
+    /// so progress through those is up to the caller.
+    pub fn post_with_progress<F: FnMut(usize, usize)>(
+        &mut self,
+        spender_key: &SaplingKey<J>,
+        change_goes_to: Option<PublicAddress<J>>,
+        intended_transaction_fee: u64,
+        mut progress: F,
+    ) -> Result<Transaction<J>, TransactionError> {
+        let change_amount =
+            self.add_change(spender_key, change_goes_to, intended_transaction_fee)?;
+
+        let mut completed = 0;
+        let total = (change_amount > 0) as usize + 1 + self.spends.len();
+        if change_amount > 0 {
+            completed += 1;
+            progress(completed, total);
+        }
+        self._partial_post(&mut || {
+            completed += 1;
+            progress(completed, total);
+        })
+    }
 
-        if change_amount < 0 {
+    /// Post the transaction like `post`, splitting the change across several
+    /// notes with explicit values instead of a single change note.
+    ///
+    /// The values in `changes` must account for everything not spent on
+    /// outputs or the fee:
+    ///
+    /// self.transaction_fee - intended_transaction_fee - sum(changes) = 0
+    ///
+    /// Otherwise nothing is added to the transaction and
+    /// `InvalidBalanceError` is returned. The change notes are added as
+    /// receipts in the order given, once the proofs of all of them have been
+    /// created, so a failing proof leaves the transaction unchanged too.
+    pub fn post_with_change_outputs(
+        &mut self,
+        spender_key: &SaplingKey<J>,
+        changes: &[(PublicAddress<J>, u64)],
+        intended_transaction_fee: u64,
+    ) -> Result<Transaction<J>, TransactionError> {
+        let total = changes
+            .iter()
+            .try_fold(intended_transaction_fee, |total, (_, value)| {
+                total.checked_add(*value)
+            })
+            .and_then(|total| i64::try_from(total).ok())
+            .ok_or(TransactionError::InvalidBalanceError)?;
+        if total != self.transaction_fee {
             return Err(TransactionError::InvalidBalanceError);
         }
+
+        let mut os_rng = OsRng;
+        let mut proofs = Vec::with_capacity(changes.len());
+        for (address, value) in changes.iter() {
+            let change_note = Note::new_with_rng(
+                self.sapling.clone(),
+                address.clone(),
+                *value,
+                Memo([0; 32]),
+                transaction_rng(&mut self.seeded_rng, &mut os_rng),
+            );
+            let proof = ReceiptParams::new_with_rng(
+                self.sapling.clone(),
+                spender_key,
+                &change_note,
+                transaction_rng(&mut self.seeded_rng, &mut os_rng),
+            )?;
+            proofs.push((proof, *value));
+        }
+        for (proof, value) in proofs {
+            self.add_receipt_proof(proof, value);
+        }
+        self._partial_post(&mut || {})
+    }
+
+    /// Value of the change note `post` would add when posting with the given
+    /// fee: whatever the spends leave after the outputs and the fee. Fails
+    /// with `InvalidBalanceError` if they don't cover the fee.
+    pub fn change_amount(&self, intended_transaction_fee: u64) -> Result<u64, TransactionError> {
+        i64::try_from(intended_transaction_fee)
+            .ok()
+            .and_then(|fee| self.transaction_fee.checked_sub(fee))
+            .filter(|change| *change >= 0)
+            .map(|change| change as u64)
+            .ok_or(TransactionError::InvalidBalanceError)
+    }
+
+    /// Add the change note `post` would add, creating its proof, and return
+    /// its value. Nothing is added if there is no change.
+    ///
+    /// This is the only proof `post` creates, so doing it separately lets a
+    /// caller spread the work of posting over several calls. Posting
+    /// afterwards with the same fee adds no further change.
+    pub fn add_change(
+        &mut self,
+        spender_key: &SaplingKey<J>,
+        change_goes_to: Option<PublicAddress<J>>,
+        intended_transaction_fee: u64,
+    ) -> Result<u64, TransactionError> {
+        // Check the balance before creating the proof for the change note,
+        // so an unaffordable fee fails fast
+        let change_amount = self.change_amount(intended_transaction_fee)?;
         if change_amount > 0 {
-            // TODO: The public address generated from the spender_key if
-            // change_goes_to is None should probably be associated with a
-            // known diversifier (eg: that used on other notes?)
-            // But we haven't worked out why determinacy in public addresses
-            // would be useful yet.
-            let change_address =
-                change_goes_to.unwrap_or_else(|| spender_key.generate_public_address());
-            let change_note = Note::new(
+            let mut os_rng = OsRng;
+            let change_note = Note::new_with_rng(
                 self.sapling.clone(),
-                change_address,
-                change_amount as u64, // we checked it was positive
+                change_address(spender_key, change_goes_to),
+                change_amount,
                 Memo([0; 32]),
+                transaction_rng(&mut self.seeded_rng, &mut os_rng),
             );
             self.receive(&spender_key, &change_note)?;
         }
-        self._partial_post()
+        Ok(change_amount)
+    }
+
+    /// The change note `post` would add when posting with the given fee and
+    /// `Some(change_goes_to)`, without creating its proof, or None if there is
+    /// no change. Fails with `InvalidBalanceError` if the spends don't cover
+    /// the outputs and the fee.
+    ///
+    /// The address is required because the spender's default address gets a
+    /// fresh diversifier on every call; pass the same address to `post` or
+    /// `add_change` to get this owner. Only the value and the owner of the
+    /// note are meaningful; its randomness is drawn again when the change is
+    /// actually added.
+    pub fn preview_change(
+        &self,
+        change_goes_to: PublicAddress<J>,
+        intended_transaction_fee: u64,
+    ) -> Result<Option<Note<J>>, TransactionError> {
+        let change_amount = self.change_amount(intended_transaction_fee)?;
+        if change_amount == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Note::new(
+            self.sapling.clone(),
+            change_goes_to,
+            change_amount,
+            Memo([0; 32]),
+        )))
     }
 
     /// Special case for posting a miners fee transaction. Miner fee transactions
@@ -180,23 +637,34 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ProposedTransaction<J> {
             .expect("bounds checked above")
             .merkle_note
             .note_encryption_keys = *NOTE_ENCRYPTION_MINER_KEYS;
-        self._partial_post()
+        self._partial_post(&mut || {})
     }
     /// Super special case for generating an illegal transaction for the genesis block.
     /// Don't bother using this anywhere else, it won't pass verification.
     #[deprecated(note = "Use only in genesis block generation")]
     pub fn post_genesis_transaction(&self) -> Result<Transaction<J>, TransactionError> {
-        self._partial_post()
+        self._partial_post(&mut || {})
     }
 
-    // post transaction without much validation.
-    fn _partial_post(&self) -> Result<Transaction<J>, TransactionError> {
+    /// Sign the transaction, calling `step_done` after the binding signature
+    /// and after each spend signature.
+    fn _partial_post(
+        &self,
+        step_done: &mut dyn FnMut(),
+    ) -> Result<Transaction<J>, TransactionError> {
         self.check_value_consistency()?;
+        // Spends added with `add_spend_proof` haven't been checked yet
+        check_unique_nullifiers(self.spends.iter().map(|spend| &spend.nullifier))?;
+        let mut os_rng = OsRng;
+        let mut seeded_rng = self.seeded_rng.clone();
+        let rng = transaction_rng(&mut seeded_rng, &mut os_rng);
         let data_to_sign = self.transaction_signature_hash();
-        let binding_signature = self.binding_signature()?;
+        let binding_signature = self.binding_signature(rng)?;
+        step_done();
         let mut spend_proofs = vec![];
         for spend in &self.spends {
-            spend_proofs.push(spend.post(&data_to_sign)?);
+            spend_proofs.push(spend.post_with_rng(&data_to_sign, rng)?);
+            step_done();
         }
         let mut receipt_proofs = vec![];
         for receipt in &self.receipts {
@@ -204,7 +672,9 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ProposedTransaction<J> {
         }
         Ok(Transaction {
             sapling: self.sapling.clone(),
+            version: version_for(self.expiration_sequence),
             transaction_fee: self.transaction_fee,
+            expiration_sequence: self.expiration_sequence,
             spends: spend_proofs,
             receipts: receipt_proofs,
             binding_signature,
@@ -214,18 +684,19 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ProposedTransaction<J> {
     /// Calculate a hash of the transaction data. This hash is what gets signed
     /// by the private keys to verify that the transaction actually happened.
     ///
-    /// This is called during final posting of the transaction
-    ///
-    fn transaction_signature_hash(&self) -> [u8; 32] {
+    /// This is called during final posting of the transaction, and can be
+    /// called earlier to hand the hash to an external signer. It covers the
+    /// spends, receipts, fee and expiration added so far, so adding a spend
+    /// or receipt, or setting the expiration, invalidates it. So does `post`
+    /// if it adds a change note, and `post_miners_fee`, which changes the
+    /// encryption keys of the receipt.
+    pub fn transaction_signature_hash(&self) -> [u8; 32] {
         let mut hasher = Blake2b::new()
             .hash_length(32)
             .personal(SIGNATURE_HASH_PERSONALIZATION)
             .to_state();
 
-        hasher.update(TRANSACTION_SIGNATURE_VERSION);
-        hasher
-            .write_i64::<LittleEndian>(self.transaction_fee)
-            .unwrap();
+        write_signature_header(&mut hasher, self.transaction_fee, self.expiration_sequence);
         for spend in self.spends.iter() {
             spend.serialize_signature_fields(&mut hasher).unwrap();
         }
@@ -250,50 +721,27 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> ProposedTransaction<J> {
     /// binding_signature below. I find the separation of concerns easier
     /// to read, but it's an easy win if we see a performance bottleneck here.
     fn check_value_consistency(&self) -> Result<(), TransactionError> {
-        let jubjub = &self.sapling.jubjub;
-        let private_key = PrivateKey::<J>(self.binding_signature_key);
-        let public_key = PublicKey::from_private(
-            &private_key,
-            FixedGenerators::ValueCommitmentRandomness,
-            jubjub,
-        );
-        let mut value_balance_point = value_balance_to_point(self.transaction_fee as i64, jubjub)?;
-
-        value_balance_point = value_balance_point.negate();
-        let mut calculated_public_key = self.binding_verification_key.clone();
-        calculated_public_key = calculated_public_key.add(&value_balance_point, jubjub);
-
-        if calculated_public_key != public_key.0 {
-            Err(TransactionError::InvalidBalanceError)
-        } else {
-            Ok(())
-        }
+        check_value_consistency(
+            &self.sapling.jubjub,
+            self.binding_signature_key,
+            &self.binding_verification_key,
+            self.transaction_fee,
+        )
     }
 
     /// The binding signature ties up all the randomness generated with the
     /// transaction and uses it as a private key to sign all the values
     /// that were calculated as part of the transaction. This function
     /// performs the calculation and sets the value on this struct.
-    fn binding_signature(&self) -> Result<Signature, TransactionError> {
-        let mut data_to_be_signed = [0u8; 64];
-        let private_key = PrivateKey::<J>(self.binding_signature_key);
-        let public_key = PublicKey::from_private(
-            &private_key,
-            FixedGenerators::ValueCommitmentRandomness,
-            &self.sapling.jubjub,
-        );
-
-        public_key
-            .0
-            .write(&mut data_to_be_signed[..32])
-            .expect("Should be able to copy key");
-        (&mut data_to_be_signed[32..]).copy_from_slice(&self.transaction_signature_hash());
-
-        Ok(private_key.sign(
-            &data_to_be_signed,
-            &mut OsRng,
-            FixedGenerators::ValueCommitmentRandomness,
+    fn binding_signature<R: RngCore + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<Signature, TransactionError> {
+        Ok(binding_signature(
             &self.sapling.jubjub,
+            self.binding_signature_key,
+            &self.transaction_signature_hash(),
+            rng,
         ))
     }
 
@@ -335,9 +783,19 @@ pub struct Transaction<J: JubjubEngine + pairing::MultiMillerLoop> {
     /// reference to the sapling object associated with this transaction
     sapling: Arc<Sapling<J>>,
 
+    /// Format version the transaction was read or posted with. The version
+    /// isn't covered by the signature hash, so `read` only accepts the one
+    /// `version_for` the expiration sequence; any other would let the same
+    /// signed transaction be written with different bytes.
+    version: u8,
+
     /// The balance of total spends - outputs, which is the amount that the miner gets to keep
     transaction_fee: i64,
 
+    /// Sequence of the last block this transaction can be mined in, or 0 if
+    /// it never expires.
+    expiration_sequence: u32,
+
     /// List of spends, or input notes, that have been destroyed.
     spends: Vec<SpendProof<J>>,
 
@@ -353,26 +811,79 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> Transaction<J> {
     /// Load a Transaction from a Read implementation (e.g: socket, file)
     /// This is the main entry-point when reconstructing a serialized transaction
     /// for verifying.
+    ///
+    /// Exactly one transaction, with its single binding signature, is read;
+    /// transactions can't be merged by concatenating their bytes. Anything
+    /// after the transaction is left in the reader, for example to be read
+    /// as the next transaction by `TransactionStream`.
+    ///
+    /// Only the canonical encoding written by `write` is accepted: a
+    /// transaction that doesn't expire must use the original format
+    /// version, and every spend and receipt must write back to the exact
+    /// bytes it was read from. Anything else fails with an `InvalidData`
+    /// io error, so a transaction can't be relayed under different bytes.
     pub fn read<R: io::Read>(
         sapling: Arc<Sapling<J>>,
         mut reader: R,
     ) -> Result<Self, TransactionError> {
-        let num_spends = reader.read_u64::<LittleEndian>()?;
+        let versioned_num_spends = reader.read_u64::<LittleEndian>()?;
+        let version = (versioned_num_spends >> TRANSACTION_VERSION_SHIFT) as u8;
+        let num_spends = versioned_num_spends & ((1 << TRANSACTION_VERSION_SHIFT) - 1);
+        if version > TRANSACTION_VERSION {
+            return Err(TransactionError::UnsupportedVersion(version));
+        }
         let num_receipts = reader.read_u64::<LittleEndian>()?;
         let transaction_fee = reader.read_i64::<LittleEndian>()?;
+        let expiration_sequence = if version >= TRANSACTION_VERSION_EXPIRATION {
+            reader.read_u32::<LittleEndian>()?
+        } else {
+            0
+        };
+        if version != version_for(expiration_sequence) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "transactions that don't expire must use the original format version",
+            )
+            .into());
+        }
         let mut spends = vec![];
         let mut receipts = vec![];
-        for _ in 0..num_spends {
-            spends.push(SpendProof::read(&sapling.jubjub, &mut reader)?);
+        for index in 0..num_spends {
+            let bytes = read_item(&mut reader, SPEND_PROOF_SIZE, "spend", index, num_spends)?;
+            let spend = SpendProof::read(&sapling.jubjub, &bytes[..])
+                .map_err(|e| invalid_item("spend", index, num_spends, e))?;
+            check_canonical(&bytes, |writer| spend.write(writer), "spend", index)?;
+            spends.push(spend);
         }
-        for _ in 0..num_receipts {
-            receipts.push(ReceiptProof::read(sapling.clone(), &mut reader)?);
+        for index in 0..num_receipts {
+            let bytes = read_item(
+                &mut reader,
+                RECEIPT_PROOF_SIZE,
+                "receipt",
+                index,
+                num_receipts,
+            )?;
+            let receipt = ReceiptProof::read(sapling.clone(), &bytes[..])
+                .map_err(|e| invalid_item("receipt", index, num_receipts, e))?;
+            check_canonical(&bytes, |writer| receipt.write(writer), "receipt", index)?;
+            receipts.push(receipt);
         }
-        let binding_signature = Signature::read(&mut reader)?;
+        let binding_signature = Signature::read(&mut reader).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "transaction ends before its binding signature",
+                )
+            } else {
+                e
+            }
+        })?;
 
         Ok(Transaction {
             sapling,
+            version,
             transaction_fee,
+            expiration_sequence,
             spends,
             receipts,
             binding_signature,
@@ -381,10 +892,21 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> Transaction<J> {
 
     /// Store the bytes of this transaction in the given writer. This is used
     /// to serialize transactions to file or network
+    ///
+    /// The encoding is canonical: the header, then the spends and receipts
+    /// in order, then the binding signature, each in a fixed size with no
+    /// padding, and points in their compressed form. Equal transactions
+    /// always write the same bytes, and `read` accepts no other encoding of
+    /// them, so the bytes can be hashed to identify a transaction.
     pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
-        writer.write_u64::<LittleEndian>(self.spends.len() as u64)?;
+        writer.write_u64::<LittleEndian>(
+            ((self.version as u64) << TRANSACTION_VERSION_SHIFT) | self.spends.len() as u64,
+        )?;
         writer.write_u64::<LittleEndian>(self.receipts.len() as u64)?;
         writer.write_i64::<LittleEndian>(self.transaction_fee)?;
+        if self.version >= TRANSACTION_VERSION_EXPIRATION {
+            writer.write_u32::<LittleEndian>(self.expiration_sequence)?;
+        }
         for spend in self.spends.iter() {
             spend.write(&mut writer)?;
         }
@@ -403,37 +925,163 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> Transaction<J> {
     ///  *  The entire transaction was signed with a binding signature
     ///     containing those proofs (and only those proofs)
     ///
+    /// The sign of the fee isn't checked: a negative fee mints currency,
+    /// which only a block's miners fee transaction may do. Check that one
+    /// with `verify_miners_fee` instead.
     pub fn verify(&self) -> Result<(), TransactionError> {
-        // Context to accumulate a signature of all the spends and outputs and
-        // guarantee they are part of this transaction, unmodified.
-        let mut binding_verification_key = edwards::Point::zero();
+        self.verify_with(&CpuProofVerifier)
+    }
+
+    /// Validate the transaction like `verify`, but check the zero knowledge
+    /// proofs with the given verifier, for example to use a hardware
+    /// accelerated backend. The signatures are always checked here.
+    pub fn verify_with<V: ProofVerifier<J>>(&self, verifier: &V) -> Result<(), TransactionError> {
+        self.verify_against(&self.sapling, verifier)
+    }
+
+    /// Validate the transaction like `verify`, but against the proof
+    /// parameters of `sapling` rather than those the transaction was read or
+    /// created with, for example those of another network. To check against
+    /// just the verifying keys of a parameter set, pass
+    /// `proof_verifier::VerifyingKeys` to `verify_with` instead.
+    pub fn verify_with_params(&self, sapling: &Sapling<J>) -> Result<(), TransactionError> {
+        self.verify_against(sapling, &CpuProofVerifier)
+    }
+
+    fn verify_against<V: ProofVerifier<J>>(
+        &self,
+        sapling: &Sapling<J>,
+        verifier: &V,
+    ) -> Result<(), TransactionError> {
+        check_unique_nullifiers(self.spends.iter().map(|spend| &spend.nullifier))?;
 
         for spend in self.spends.iter() {
-            spend.verify_proof(&self.sapling)?;
-            let mut tmp = spend.value_commitment.clone();
-            tmp = tmp.add(&binding_verification_key, &self.sapling.jubjub);
-            binding_verification_key = tmp;
+            spend.verify_value_commitment(sapling)?;
+            verifier.verify_spend_proof(sapling, spend)?;
         }
 
         for receipt in self.receipts.iter() {
-            receipt.verify_proof(&self.sapling)?;
-            let mut tmp = receipt.merkle_note.value_commitment.clone();
-            tmp = tmp.negate();
-            tmp = tmp.add(&binding_verification_key, &self.sapling.jubjub);
-            binding_verification_key = tmp;
+            receipt.verify_points(sapling)?;
+            verifier.verify_receipt_proof(sapling, receipt)?;
         }
 
+        // Context to accumulate a signature of all the spends and outputs and
+        // guarantee they are part of this transaction, unmodified.
+        let binding_verification_key = self.binding_verification_key();
+
         let hash_to_verify_signature = self.transaction_signature_hash();
 
         for spend in self.spends.iter() {
-            spend.verify_signature(&self.sapling.jubjub, &hash_to_verify_signature)?;
+            spend.verify_signature(&sapling.jubjub, &hash_to_verify_signature)?;
         }
 
-        self.verify_binding_signature(&self.sapling, &binding_verification_key)?;
+        self.verify_binding_signature(sapling, &binding_verification_key)?;
 
         Ok(())
     }
 
+    /// Validate a block's miners fee transaction: it must be shaped like the
+    /// ones `ProposedTransaction::post_miners_fee` creates, with no spends
+    /// and a single output marked as a miner's note, and mint exactly
+    /// `expected_reward`, the block subsidy plus the fees of the block's
+    /// other transactions. It must also pass `verify`.
+    ///
+    /// Fails with `InvalidBalanceError` if the shape or the amount is wrong.
+    pub fn verify_miners_fee(&self, expected_reward: u64) -> Result<(), TransactionError> {
+        let is_miners_fee = self.spends.is_empty()
+            && self.receipts.len() == 1
+            && self.receipts[0].merkle_note.note_encryption_keys[..]
+                == NOTE_ENCRYPTION_MINER_KEYS[..];
+        if !is_miners_fee || -(self.transaction_fee as i128) != expected_reward as i128 {
+            return Err(TransactionError::InvalidBalanceError);
+        }
+        self.verify()
+    }
+
+    /// Validate the transaction like `verify`, reporting which check failed
+    /// instead of a generic `VerificationFailed`.
+    ///
+    /// The checks run in the same order as in `verify`, so the error is for
+    /// the first problem `verify` would have hit.
+    pub fn verify_detailed(&self) -> Result<(), VerificationError> {
+        check_unique_nullifiers(self.spends.iter().map(|spend| &spend.nullifier))
+            .map_err(|_| VerificationError::DuplicateNullifier)?;
+
+        for (index, spend) in self.spends.iter().enumerate() {
+            spend
+                .verify_proof(&self.sapling)
+                .map_err(|_| VerificationError::SpendProofInvalid(index))?;
+        }
+
+        for (index, receipt) in self.receipts.iter().enumerate() {
+            receipt
+                .verify_proof(&self.sapling)
+                .map_err(|_| VerificationError::ReceiptProofInvalid(index))?;
+        }
+
+        let hash_to_verify_signature = self.transaction_signature_hash();
+        for (index, spend) in self.spends.iter().enumerate() {
+            spend
+                .verify_signature(&self.sapling.jubjub, &hash_to_verify_signature)
+                .map_err(|_| VerificationError::SpendSignatureInvalid(index))?;
+        }
+
+        // The only fee that can't be committed to, checked here so it isn't
+        // reported as a bad signature
+        if self.transaction_fee == i64::MIN {
+            return Err(VerificationError::FeeMismatch);
+        }
+        self.verify_value_balance()
+            .map_err(|_| VerificationError::BindingSignatureInvalid)
+    }
+
+    /// Check that the transaction can still be mined in the block with the
+    /// given sequence. Transactions expire after the block with their
+    /// expiration sequence; an expiration sequence of 0 never expires.
+    ///
+    /// This is not part of `verify`, because whether a transaction has
+    /// expired depends on the chain it is checked against.
+    pub fn verify_expiration(&self, current_sequence: u32) -> Result<(), TransactionError> {
+        if self.expiration_sequence != 0 && current_sequence > self.expiration_sequence {
+            Err(TransactionError::ExpiredError)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Number of steps `verify_step` splits the verification of this
+    /// transaction into: one for every spend, one for every receipt, and a
+    /// final one for the binding signature.
+    pub fn verification_steps(&self) -> usize {
+        self.spends.len() + self.receipts.len() + 1
+    }
+
+    /// Perform a single step of `verify`, checking at most one proof.
+    ///
+    /// Steps `0..spends.len()` check a spend proof and its authorizing
+    /// signature, the following `receipts.len()` steps check a receipt proof,
+    /// and the last step checks the binding signature and that no note is
+    /// spent twice. Running every step up
+    /// to `verification_steps()` checks exactly what `verify` checks, in any
+    /// order and spread over as many calls as the caller likes.
+    pub fn verify_step(&self, step: usize) -> Result<(), TransactionError> {
+        let spends = self.spends.len();
+        let receipts = self.receipts.len();
+        if step < spends {
+            let spend = &self.spends[step];
+            spend.verify_proof(&self.sapling)?;
+            spend.verify_signature(&self.sapling.jubjub, &self.transaction_signature_hash())?;
+        } else if step < spends + receipts {
+            self.receipts[step - spends].verify_proof(&self.sapling)?;
+        } else if step == spends + receipts {
+            check_unique_nullifiers(self.spends.iter().map(|spend| &spend.nullifier))?;
+            self.verify_value_balance()?;
+        } else {
+            return Err(TransactionError::IllegalValueError);
+        }
+        Ok(())
+    }
+
     /// Get an iterator over the spends in this transaction. Each spend
     /// is by reference
     pub fn iter_spends(&self) -> Iter<SpendProof<J>> {
@@ -460,6 +1108,30 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> Transaction<J> {
         self.transaction_fee
     }
 
+    /// Sequence of the last block this transaction can be mined in, or 0 if
+    /// it never expires.
+    pub fn expiration_sequence(&self) -> u32 {
+        self.expiration_sequence
+    }
+
+    /// Format version of this transaction. Posted transactions use the
+    /// oldest version that can hold them, so only transactions that expire
+    /// are written with the expiration version.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Number of bytes `write` will produce for this transaction.
+    ///
+    /// Spends and receipts have a fixed serialized size, so this is computed
+    /// from the counts alone without serializing anything.
+    pub fn serialized_size(&self) -> usize {
+        header_size(self.version)
+            + self.spends.len() * SPEND_PROOF_SIZE
+            + self.receipts.len() * RECEIPT_PROOF_SIZE
+            + BINDING_SIGNATURE_SIZE
+    }
+
     /// Get the transaction signature for this transaction.
     pub fn binding_signature(&self) -> &Signature {
         &self.binding_signature
@@ -476,10 +1148,7 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> Transaction<J> {
             .hash_length(32)
             .personal(SIGNATURE_HASH_PERSONALIZATION)
             .to_state();
-        hasher.update(TRANSACTION_SIGNATURE_VERSION);
-        hasher
-            .write_i64::<LittleEndian>(self.transaction_fee)
-            .unwrap();
+        write_signature_header(&mut hasher, self.transaction_fee, self.expiration_sequence);
         for spend in self.spends.iter() {
             spend.serialize_signature_fields(&mut hasher).unwrap();
         }
@@ -492,6 +1161,77 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> Transaction<J> {
         hash_result
     }
 
+    /// Hash of the whole serialized transaction, signatures included, for
+    /// use as a key when deduplicating transactions.
+    ///
+    /// Unlike `transaction_signature_hash`, which covers only what the
+    /// signatures sign, this changes with the signatures: the same unsigned
+    /// transaction signed twice has one signature hash but two fingerprints.
+    /// It only depends on the serialized bytes, so it is the same after a
+    /// round trip through `write` and `read`.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut hasher = Blake2b::new()
+            .hash_length(32)
+            .personal(FINGERPRINT_PERSONALIZATION)
+            .to_state();
+        self.write(&mut hasher).unwrap();
+
+        let mut hash_result = [0; 32];
+        hash_result[..].clone_from_slice(&hasher.finalize().as_ref()[..]);
+        hash_result
+    }
+
+    /// Check that this transaction conserves value, without verifying any of
+    /// the proofs or spend signatures.
+    ///
+    /// The value commitments of the spends minus those of the receipts, minus
+    /// a commitment to the fee, is not the identity point: every value
+    /// commitment is blinded with its own randomness, so what remains is a
+    /// commitment to zero value under the sum of those randomnesses. The
+    /// binding signature proves that the poster knew that sum, which they
+    /// can only know if the values balance. Recomputing the key from the
+    /// commitments and checking the binding signature against it is therefore
+    /// the value balance check.
+    pub fn verify_value_balance(&self) -> Result<(), TransactionError> {
+        self.verify_binding_signature(&self.sapling, &self.binding_verification_key())
+    }
+
+    /// Serialized sum of the spend value commitments minus the sum of the
+    /// receipt value commitments.
+    ///
+    /// This is a commitment to the transaction fee, blinded by randomness
+    /// only the poster knows, so it reveals nothing about the individual
+    /// values. `verify_value_balance` checks that it balances against the
+    /// cleartext fee.
+    pub fn value_commitment_sum(&self) -> [u8; 32] {
+        let mut result = [0; 32];
+        self.binding_verification_key()
+            .write(&mut result[..])
+            .expect("value commitment sum should be convertible to bytes");
+        result
+    }
+
+    /// Sum of the spend value commitments minus the sum of the receipt value
+    /// commitments. The binding signature must verify against this point
+    /// once the fee is taken out of it.
+    fn binding_verification_key(&self) -> edwards::Point<J, Unknown> {
+        let mut binding_verification_key = edwards::Point::zero();
+
+        for spend in self.spends.iter() {
+            binding_verification_key =
+                binding_verification_key.add(&spend.value_commitment, &self.sapling.jubjub);
+        }
+
+        for receipt in self.receipts.iter() {
+            binding_verification_key = binding_verification_key.add(
+                &receipt.merkle_note.value_commitment.negate(),
+                &self.sapling.jubjub,
+            );
+        }
+
+        binding_verification_key
+    }
+
     /// Confirm that this transaction was signed by the values it contains.
     /// Called from the public verify function.
     fn verify_binding_signature(
@@ -499,6 +1239,40 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> Transaction<J> {
         sapling: &Sapling<J>,
         binding_verification_key: &edwards::Point<J, Unknown>,
     ) -> Result<(), TransactionError> {
+        let (public_key, data_to_verify_signature) =
+            self.binding_signature_key_and_message(sapling, binding_verification_key)?;
+
+        if !public_key.verify(
+            &data_to_verify_signature,
+            &self.binding_signature,
+            FixedGenerators::ValueCommitmentRandomness,
+            &sapling.jubjub,
+        ) {
+            Err(TransactionError::VerificationFailed)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The exact bytes the binding signature signs: the binding verification
+    /// key with the fee taken out of it (32 bytes), followed by the
+    /// transaction signature hash (32 bytes).
+    ///
+    /// The key is the sum of the spend value commitments, minus the sum of
+    /// the receipt value commitments, minus `fee * ValueCommitmentValue`
+    /// generator; the signature is a RedJubjub signature over this message
+    /// with the `ValueCommitmentRandomness` generator.
+    pub fn binding_signature_message(&self) -> Result<[u8; 64], TransactionError> {
+        let (_, message) = self
+            .binding_signature_key_and_message(&self.sapling, &self.binding_verification_key())?;
+        Ok(message)
+    }
+
+    fn binding_signature_key_and_message(
+        &self,
+        sapling: &Sapling<J>,
+        binding_verification_key: &edwards::Point<J, Unknown>,
+    ) -> Result<(PublicKey<J>, [u8; 64]), TransactionError> {
         let mut value_balance_point =
             value_balance_to_point(self.transaction_fee, &sapling.jubjub)?;
         value_balance_point = value_balance_point.negate();
@@ -514,17 +1288,286 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> Transaction<J> {
             .expect("Should be able to copy key");
         (&mut data_to_verify_signature[32..]).copy_from_slice(&self.transaction_signature_hash());
 
-        if !public_key.verify(
-            &data_to_verify_signature,
-            &self.binding_signature,
-            FixedGenerators::ValueCommitmentRandomness,
-            &sapling.jubjub,
-        ) {
-            Err(TransactionError::VerificationFailed)
+        Ok((public_key, data_to_verify_signature))
+    }
+}
+
+/// Check that the binding signature key is the discrete log of the binding
+/// verification key with the fee taken out of it, which is only the case if
+/// the values of the spends, receipts and fee balance.
+fn check_value_consistency<J: JubjubEngine + pairing::MultiMillerLoop>(
+    jubjub: &J::Params,
+    binding_signature_key: J::Fs,
+    binding_verification_key: &edwards::Point<J, Unknown>,
+    transaction_fee: i64,
+) -> Result<(), TransactionError> {
+    let private_key = PrivateKey::<J>(binding_signature_key);
+    let public_key = PublicKey::from_private(
+        &private_key,
+        FixedGenerators::ValueCommitmentRandomness,
+        jubjub,
+    );
+    let mut value_balance_point = value_balance_to_point(transaction_fee, jubjub)?;
+
+    value_balance_point = value_balance_point.negate();
+    let mut calculated_public_key = binding_verification_key.clone();
+    calculated_public_key = calculated_public_key.add(&value_balance_point, jubjub);
+
+    if calculated_public_key != public_key.0 {
+        Err(TransactionError::InvalidBalanceError)
+    } else {
+        Ok(())
+    }
+}
+
+/// Sign the transaction signature hash with the binding signature key, which
+/// proves that the poster knew the randomness of every value commitment.
+fn binding_signature<J: JubjubEngine + pairing::MultiMillerLoop, R: RngCore + ?Sized>(
+    jubjub: &J::Params,
+    binding_signature_key: J::Fs,
+    transaction_signature_hash: &[u8; 32],
+    mut rng: &mut R,
+) -> Signature {
+    let mut data_to_be_signed = [0u8; 64];
+    let private_key = PrivateKey::<J>(binding_signature_key);
+    let public_key = PublicKey::from_private(
+        &private_key,
+        FixedGenerators::ValueCommitmentRandomness,
+        jubjub,
+    );
+
+    public_key
+        .0
+        .write(&mut data_to_be_signed[..32])
+        .expect("Should be able to copy key");
+    (&mut data_to_be_signed[32..]).copy_from_slice(transaction_signature_hash);
+
+    private_key.sign(
+        &data_to_be_signed,
+        &mut rng,
+        FixedGenerators::ValueCommitmentRandomness,
+        jubjub,
+    )
+}
+
+/// Fail with `DuplicateNullifier` if any nullifier appears more than once,
+/// which means the same note is spent twice in one transaction.
+fn check_unique_nullifiers<'a>(
+    nullifiers: impl Iterator<Item = &'a Nullifier>,
+) -> Result<(), TransactionError> {
+    let mut seen = HashSet::new();
+    for nullifier in nullifiers {
+        if !seen.insert(nullifier) {
+            return Err(TransactionError::DuplicateNullifier);
+        }
+    }
+    Ok(())
+}
+
+/// The rng of a transaction created `with_seed`, or else the OS rng.
+fn transaction_rng<'a>(
+    seeded_rng: &'a mut Option<ChaChaRng>,
+    os_rng: &'a mut OsRng,
+) -> &'a mut dyn RngCore {
+    match seeded_rng {
+        Some(rng) => rng,
+        None => os_rng,
+    }
+}
+
+/// Format version a transaction with the given expiration sequence is posted
+/// with.
+fn version_for(expiration_sequence: u32) -> u8 {
+    if expiration_sequence == 0 {
+        TRANSACTION_VERSION_ORIGINAL
+    } else {
+        TRANSACTION_VERSION_EXPIRATION
+    }
+}
+
+/// Size of the serialized header of a transaction with the given format
+/// version.
+fn header_size(version: u8) -> usize {
+    if version >= TRANSACTION_VERSION_EXPIRATION {
+        TRANSACTION_HEADER_SIZE + EXPIRATION_SEQUENCE_SIZE
+    } else {
+        TRANSACTION_HEADER_SIZE
+    }
+}
+
+/// Read the `size` bytes of the spend or receipt at `index`, out of the
+/// `count` the transaction header declares. Fails with `UnexpectedEof`,
+/// naming the item, if the bytes run out first.
+fn read_item<R: io::Read>(
+    mut reader: R,
+    size: usize,
+    kind: &str,
+    index: u64,
+    count: u64,
+) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; size];
+    reader.read_exact(&mut bytes).map_err(|e| {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "transaction declares {} {}s, but ends in {} {}",
+                    count, kind, kind, index
+                ),
+            )
         } else {
-            Ok(())
+            e
         }
+    })?;
+    Ok(bytes)
+}
+
+/// Fail unless an item decoded from `bytes` writes back to exactly those
+/// bytes. Some encodings, like a point with a sign bit its x coordinate
+/// can't have, decode to the same value as the canonical one.
+fn check_canonical<F: FnOnce(&mut Vec<u8>) -> io::Result<()>>(
+    bytes: &[u8],
+    write: F,
+    kind: &str,
+    index: u64,
+) -> io::Result<()> {
+    let mut written = Vec::with_capacity(bytes.len());
+    write(&mut written)?;
+    if written != bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} {} is not canonically encoded", kind, index),
+        ));
     }
+    Ok(())
+}
+
+/// Error for a spend or receipt whose bytes were all there, but couldn't be
+/// decoded.
+fn invalid_item(kind: &str, index: u64, count: u64, e: SaplingProofError) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} {} of {} is invalid: {:?}", kind, index, count, e),
+    )
+}
+
+/// Write the fields of the transaction itself into the signature hash.
+///
+/// Transactions that expire are hashed with a different version byte and
+/// the expiration sequence, so the expiration can't be changed or stripped
+/// without invalidating the signatures. Transactions that don't expire hash
+/// exactly as they did before expiration was introduced.
+fn write_signature_header<W: io::Write>(
+    hasher: &mut W,
+    transaction_fee: i64,
+    expiration_sequence: u32,
+) {
+    if expiration_sequence == 0 {
+        hasher.write_all(TRANSACTION_SIGNATURE_VERSION).unwrap();
+        hasher.write_i64::<LittleEndian>(transaction_fee).unwrap();
+    } else {
+        hasher
+            .write_all(TRANSACTION_SIGNATURE_VERSION_EXPIRATION)
+            .unwrap();
+        hasher.write_i64::<LittleEndian>(transaction_fee).unwrap();
+        hasher
+            .write_u32::<LittleEndian>(expiration_sequence)
+            .unwrap();
+    }
+}
+
+/// Verify a batch of transactions on the rayon thread pool.
+///
+/// Each transaction is verified independently, exactly as by
+/// `Transaction::verify`, and the results are returned in the same order
+/// as the input.
+#[cfg(feature = "parallel")]
+pub fn verify_transactions_parallel<J: JubjubEngine + pairing::MultiMillerLoop>(
+    transactions: &[Transaction<J>],
+) -> Vec<Result<(), TransactionError>>
+where
+    Transaction<J>: Sync,
+{
+    use rayon::prelude::*;
+
+    transactions
+        .par_iter()
+        .map(|transaction| transaction.verify())
+        .collect()
+}
+
+/// Owner of the change note of a transaction: `change_goes_to`, or the
+/// default address of the spender.
+fn change_address<J: JubjubEngine + pairing::MultiMillerLoop>(
+    spender_key: &SaplingKey<J>,
+    change_goes_to: Option<PublicAddress<J>>,
+) -> PublicAddress<J> {
+    // TODO: The public address generated from the spender_key if
+    // change_goes_to is None should probably be associated with a
+    // known diversifier (eg: that used on other notes?)
+    // But we haven't worked out why determinacy in public addresses
+    // would be useful yet.
+    change_goes_to.unwrap_or_else(|| spender_key.generate_public_address())
+}
+
+/// Verify the transactions of a block in order with
+/// `Transaction::verify_detailed`, and check that no note is spent by two
+/// of them. Stops at the first transaction that fails, returning its index
+/// along with the reason; a note spent again is reported as a
+/// `DuplicateNullifier` of the later transaction.
+///
+/// The fees are not checked, so a block of transactions with negative fees
+/// passes. Check the block's miners fee transaction with
+/// `Transaction::verify_miners_fee`, against the subsidy plus the fees of
+/// the other transactions, so the block as a whole mints no more than the
+/// subsidy.
+///
+/// Use `verify_transactions_parallel` instead to learn which of a batch of
+/// independent transactions are valid.
+pub fn verify_block<'a, J, I>(transactions: I) -> Result<(), (usize, VerificationError)>
+where
+    J: JubjubEngine + pairing::MultiMillerLoop + 'a,
+    I: IntoIterator<Item = &'a Transaction<J>>,
+{
+    let mut spent = HashSet::new();
+    for (index, transaction) in transactions.into_iter().enumerate() {
+        transaction
+            .verify_detailed()
+            .map_err(|error| (index, error))?;
+        for spend in transaction.spends.iter() {
+            if !spent.insert(spend.nullifier) {
+                return Err((index, VerificationError::DuplicateNullifier));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check that the miners fee transaction of a block balances the fees
+/// collected by the other transactions in that block.
+///
+/// A miners fee transaction has a negative fee, since it generates currency.
+/// For a block to be valid, the amount it generates must be exactly the sum
+/// of the fees on the other transactions plus the block subsidy:
+///
+/// -coinbase.fee == sum(others.fee) + subsidy
+pub fn verify_block_fee_balance<J: JubjubEngine + pairing::MultiMillerLoop>(
+    coinbase: &Transaction<J>,
+    others: &[Transaction<J>],
+    subsidy: u64,
+) -> bool {
+    let fees: Vec<i64> = others.iter().map(|t| t.transaction_fee()).collect();
+    block_fees_balance(coinbase.transaction_fee(), &fees, subsidy)
+}
+
+/// Same check as `verify_block_fee_balance`, working directly on the fees
+/// of the transactions instead of the transactions themselves.
+///
+/// The sums are calculated in 128 bits so a block full of large fees can't
+/// overflow into a passing check.
+pub fn block_fees_balance(coinbase_fee: i64, fees: &[i64], subsidy: u64) -> bool {
+    let collected: i128 = fees.iter().map(|fee| *fee as i128).sum::<i128>() + subsidy as i128;
+    -(coinbase_fee as i128) == collected
 }
 
 // Convert the integer value to a point on the Jubjub curve, accounting for
@@ -0,0 +1,79 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! serde support for posted transactions, behind the `serde` feature.
+//!
+//! A transaction is serialized as exactly the bytes produced by
+//! `Transaction::write`: as a byte string in binary formats such as bincode,
+//! and as a lowercase hex string in human readable formats such as JSON.
+
+use std::fmt;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+use zcash_primitives::jubjub::JubjubEngine;
+
+use super::Transaction;
+use crate::sapling_bls12::{Bls12, SAPLING};
+use crate::serializing::{bytes_to_hex, hex_to_bytes};
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> Serialize for Transaction<J> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::with_capacity(self.serialized_size());
+        self.write(&mut bytes).map_err(ser::Error::custom)?;
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&bytes_to_hex(&bytes))
+        } else {
+            serializer.serialize_bytes(&bytes)
+        }
+    }
+}
+
+/// Transactions can only be deserialized on the BLS12 curve, because
+/// reading them requires the shared sapling parameters in `SAPLING`.
+impl<'de> Deserialize<'de> for Transaction<Bls12> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = if deserializer.is_human_readable() {
+            let hex = String::deserialize(deserializer)?;
+            hex_to_bytes(&hex)
+                .map_err(|_| de::Error::custom("transaction is not a valid hex string"))?
+        } else {
+            deserializer.deserialize_byte_buf(TransactionBytesVisitor)?
+        };
+
+        let mut reader = &bytes[..];
+        let transaction = Transaction::read(SAPLING.clone(), &mut reader)
+            .map_err(|e| de::Error::custom(format!("invalid transaction: {:?}", e)))?;
+        if !reader.is_empty() {
+            return Err(de::Error::custom("trailing bytes after transaction"));
+        }
+        Ok(transaction)
+    }
+}
+
+struct TransactionBytesVisitor;
+
+impl<'de> Visitor<'de> for TransactionBytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the serialized bytes of a transaction")
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(value.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(value)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
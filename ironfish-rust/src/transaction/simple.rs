@@ -57,6 +57,15 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> SimpleTransaction<J> {
         self.transaction.receive(&self.spender_key, note)
     }
 
+    /// The sum of the values spent minus the sum of the values received so
+    /// far. Posting sends anything above the intended fee back to the
+    /// spender as change, and fails if this is below the intended fee.
+    pub fn transaction_fee(&self) -> i64 {
+        self.transaction.transaction_fee()
+    }
+
+    /// Post the transaction, failing with `InvalidBalanceError` if the
+    /// spends don't cover the receipts and the intended fee.
     pub fn post(&mut self) -> Result<Transaction<J>, TransactionError> {
         self.transaction
             .post(&self.spender_key, None, self.intended_transaction_fee)
@@ -0,0 +1,78 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::{errors::TransactionError, transaction::Transaction, Sapling};
+use std::io::{self, Read};
+use std::sync::Arc;
+use zcash_primitives::jubjub::JubjubEngine;
+
+/// Iterator over the transactions in a reader holding any number of
+/// serialized transactions back to back, such as a sync stream.
+///
+/// Each transaction is read straight from the reader, so the stream never
+/// needs to be split up or buffered in advance. Iteration ends cleanly when
+/// the reader is exhausted at a transaction boundary. If the reader ends in
+/// the middle of a transaction, or a transaction can't be read, the error is
+/// yielded and iteration stops, since the position of the next transaction
+/// is unknown.
+pub struct TransactionStream<J: JubjubEngine + pairing::MultiMillerLoop, R: Read> {
+    sapling: Arc<Sapling<J>>,
+    reader: R,
+    done: bool,
+}
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop, R: Read> TransactionStream<J, R> {
+    pub fn new(sapling: Arc<Sapling<J>>, reader: R) -> Self {
+        TransactionStream {
+            sapling,
+            reader,
+            done: false,
+        }
+    }
+
+    /// Give back the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read the first byte of the next transaction, or None at the end of
+    /// the stream.
+    fn read_first_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8; 1];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop, R: Read> Iterator for TransactionStream<J, R> {
+    type Item = Result<Transaction<J>, TransactionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = match self.read_first_byte() {
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Ok(Some(first_byte)) => {
+                let reader = (&[first_byte][..]).chain(&mut self.reader);
+                Transaction::read(self.sapling.clone(), reader)
+            }
+            Err(e) => Err(e.into()),
+        };
+        if result.is_err() {
+            self.done = true;
+        }
+        Some(result)
+    }
+}
@@ -3,17 +3,32 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 #[cfg(test)]
-use super::{ProposedTransaction, SimpleTransaction, Transaction};
+use super::{
+    block_fees_balance, sign_offline, verify_block, ProposedTransaction, SimpleTransaction,
+    Transaction, TransactionStream, UnsignedTransaction, BINDING_SIGNATURE_SIZE,
+    EXPIRATION_SEQUENCE_SIZE, TRANSACTION_HEADER_SIZE, TRANSACTION_VERSION,
+};
 use crate::{
+    errors::{SaplingProofError, TransactionError, VerificationError},
     keys::SaplingKey,
     merkle_note::NOTE_ENCRYPTION_MINER_KEYS,
     note::{Memo, Note},
+    proof_verifier::{CpuProofVerifier, ProofVerifier, VerifyingKeys},
+    receiving::{ReceiptProof, RECEIPT_PROOF_SIZE},
     sapling_bls12,
+    serializing::bytes_to_scalar,
+    spending::{SpendParams, SpendProof, SPEND_PROOF_SIZE},
     test_util::make_fake_witness,
+    Sapling,
 };
 use pairing::bls12_381::Bls12;
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use std::sync::Arc;
 
-use zcash_primitives::redjubjub::Signature;
+use zcash_primitives::jubjub::{edwards, FixedGenerators, Unknown};
+use zcash_primitives::primitives::ValueCommitment;
+use zcash_primitives::redjubjub::{PublicKey, Signature};
 
 #[test]
 fn test_transaction() {
@@ -40,7 +55,7 @@ fn test_transaction() {
         Memo([0; 32]),
     );
     let witness = make_fake_witness(sapling.clone(), &in_note);
-    let _witness2 = make_fake_witness(sapling.clone(), &in_note2);
+    let witness2 = make_fake_witness(sapling.clone(), &in_note2);
     transaction
         .spend(spender_key.clone(), &in_note, &witness)
         .expect("should be able to prove spend");
@@ -56,19 +71,30 @@ fn test_transaction() {
         .check_value_consistency()
         .expect("should be consistent after receipt");
 
-    // This fails because witness and witness2 have different root hashes, and constructing
-    // an auth_path with consistent hashes is non-trivial without a real merkle tree
-    // implementation. Multiple spends should be tested at the integration level instead.
-    //
-    // If you comment the sanity check at the beginning of Transaction.spend, it should pass
-    //
-    // transaction
-    //     .spend(&spender_key, &in_note2, &witness2)
-    //     .expect("should be able to prove second spend");
+    // witness and witness2 have different root hashes; each spend carries
+    // its own anchor, so they can still be spent in the same transaction.
+    transaction
+        .spend(spender_key.clone(), &in_note2, &witness2)
+        .expect("should be able to prove second spend");
+    assert_eq!(transaction.spends.len(), 2);
+
+    // Fees larger than the value spent are rejected without adding change
+    for fee in &[transaction.transaction_fee() as u64 + 1, u64::MAX] {
+        assert!(matches!(
+            transaction.post(&spender_key, None, *fee),
+            Err(TransactionError::InvalidBalanceError)
+        ));
+        assert_eq!(transaction.receipts.len(), 1);
+    }
 
+    let mut steps = vec![];
     let public_transaction = transaction
-        .post(&spender_key, None, 1)
+        .post_with_progress(&spender_key, None, 1, |completed, total| {
+            steps.push((completed, total))
+        })
         .expect("should be able to post transaction");
+    // The change note, the binding signature and the two spend signatures
+    assert_eq!(steps, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
     public_transaction
         .verify()
         .expect("Should be able to verify transaction");
@@ -82,6 +108,10 @@ fn test_transaction() {
     public_transaction
         .write(&mut serialized_transaction)
         .expect("should be able to serialize transaction");
+    assert_eq!(
+        serialized_transaction.len(),
+        public_transaction.serialized_size()
+    );
     let read_back_transaction: Transaction<Bls12> =
         Transaction::read(sapling.clone(), &mut serialized_transaction[..].as_ref())
             .expect("should be able to deserialize valid transaction");
@@ -104,6 +134,51 @@ fn test_transaction() {
     assert_eq!(serialized_transaction, serialized_again);
 }
 
+#[test]
+fn test_spends_with_different_anchors() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let note1 = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        10,
+        Memo([0; 32]),
+    );
+    let note2 = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        20,
+        Memo([0; 32]),
+    );
+
+    // The second note was witnessed against a later state of the tree
+    let witness1 = make_fake_witness(sapling.clone(), &note1);
+    let mut witness2 = make_fake_witness(sapling.clone(), &note2);
+    witness2.tree_size = witness1.tree_size + 5;
+    assert_ne!(witness1.root_hash, witness2.root_hash);
+
+    transaction
+        .spend(spender_key.clone(), &note1, &witness1)
+        .expect("should be able to prove first spend");
+    transaction
+        .spend(spender_key.clone(), &note2, &witness2)
+        .expect("should be able to prove second spend");
+
+    let public_transaction = transaction
+        .post(&spender_key, None, 1)
+        .expect("should be able to post transaction");
+    public_transaction
+        .verify()
+        .expect("should be able to verify transaction");
+
+    let spends = public_transaction.spends();
+    assert_eq!(spends[0].root_hash(), witness1.root_hash);
+    assert_eq!(spends[0].tree_size(), witness1.tree_size as u32);
+    assert_eq!(spends[1].root_hash(), witness2.root_hash);
+    assert_eq!(spends[1].tree_size(), witness2.tree_size as u32);
+}
+
 #[test]
 fn test_simple_transaction() {
     let sapling = sapling_bls12::SAPLING.clone();
@@ -124,6 +199,7 @@ fn test_simple_transaction() {
     transaction
         .receive(&out_note)
         .expect("Should be able to receive note");
+    assert_eq!(transaction.transaction_fee(), 1);
 
     let public_transaction = transaction
         .post()
@@ -134,6 +210,30 @@ fn test_simple_transaction() {
         .expect("should be able to verify transaction")
 }
 
+#[test]
+fn test_simple_transaction_overspend() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key = SaplingKey::generate_key(sapling.clone());
+    let spender_address = spender_key.generate_public_address();
+
+    let mut transaction = SimpleTransaction::new(sapling.clone(), spender_key, 2);
+    let in_note = Note::new(sapling.clone(), spender_address.clone(), 42, Memo([0; 32]));
+    let out_note = Note::new(sapling.clone(), spender_address, 41, Memo([0; 32]));
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    transaction
+        .spend(&in_note, &witness)
+        .expect("should be able to spend note");
+    transaction
+        .receive(&out_note)
+        .expect("Should be able to receive note");
+
+    // Only 1 is left over for an intended fee of 2
+    assert!(matches!(
+        transaction.post(),
+        Err(TransactionError::InvalidBalanceError)
+    ));
+}
+
 #[test]
 fn test_miners_fee() {
     let sapling = &*sapling_bls12::SAPLING;
@@ -164,25 +264,1643 @@ fn test_miners_fee() {
 }
 
 #[test]
-fn test_transaction_signature() {
+fn test_block_fees_balance() {
+    assert!(block_fees_balance(0, &[], 0));
+    assert!(block_fees_balance(-10, &[], 10));
+    assert!(block_fees_balance(-17, &[3, 4], 10));
+    assert!(!block_fees_balance(-16, &[3, 4], 10));
+    assert!(!block_fees_balance(-18, &[3, 4], 10));
+    assert!(!block_fees_balance(17, &[3, 4], 10));
+
+    // sums that would overflow an i64 must not wrap around
+    assert!(!block_fees_balance(i64::MIN, &[i64::MAX, i64::MAX, 2], 0));
+    assert!(block_fees_balance(-i64::MAX, &[i64::MAX], 0));
+}
+
+#[cfg(feature = "test-mocks")]
+#[test]
+fn test_placeholder_proofs() {
+    use crate::test_mocks::PlaceholderProofVerifier;
+
     let sapling = sapling_bls12::SAPLING.clone();
-    let spender_key = SaplingKey::generate_key(sapling.clone());
-    let receiver_key = SaplingKey::generate_key(sapling.clone());
-    let spender_address = spender_key.generate_public_address();
-    let receiver_address = receiver_key.generate_public_address();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let out_note = Note::new(
+        sapling.clone(),
+        receiver_key.generate_public_address(),
+        40,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
 
-    let mut transaction = SimpleTransaction::new(sapling.clone(), spender_key, 0);
-    let in_note = Note::new(sapling.clone(), spender_address.clone(), 42, Memo([0; 32]));
-    let out_note = Note::new(sapling.clone(), receiver_address.clone(), 41, Memo([0; 32]));
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .unwrap();
+    transaction.receive(&spender_key, &out_note).unwrap();
+    let posted = transaction.post(&spender_key, None, 2).unwrap();
+
+    assert!(crate::test_mocks::is_placeholder_proof(
+        &sapling.spend_params.vk,
+        &posted.spends[0].proof
+    ));
+    assert!(crate::test_mocks::is_placeholder_proof(
+        &sapling.receipt_params.vk,
+        &posted.receipts[0].proof
+    ));
+    assert!(posted.verify_with(&PlaceholderProofVerifier).is_ok());
+
+    // the placeholders prove nothing, so the real verifier rejects them
+    assert!(posted.verify().is_err());
+
+    // the signatures are still checked
+    let mut bytes = vec![];
+    posted.write(&mut bytes).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    let corrupted = Transaction::read(sapling, &mut bytes[..].as_ref());
+    assert!(corrupted.map_or(true, |transaction| transaction
+        .verify_with(&PlaceholderProofVerifier)
+        .is_err()));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_verify_transactions_parallel() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+
+    let mut transactions = vec![];
+    for value in 1..4 {
+        let mut transaction = ProposedTransaction::new(sapling.clone());
+        let out_note = Note::new(
+            sapling.clone(),
+            receiver_key.generate_public_address(),
+            value,
+            Memo([0; 32]),
+        );
+        transaction
+            .receive(&receiver_key, &out_note)
+            .expect("It's a valid note");
+        transactions.push(transaction.post_miners_fee().unwrap());
+    }
+    // Invalidate the binding signature of the middle transaction
+    transactions[1].transaction_fee = 0;
+
+    let results = super::verify_transactions_parallel(&transactions);
+    assert_eq!(results.len(), 3);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+    assert!(results[2].is_ok());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_spend_and_receive_parallel() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+
+    let spends: Vec<_> = (1..4)
+        .map(|value| {
+            let note = Note::new(
+                sapling.clone(),
+                spender_key.generate_public_address(),
+                value * 10,
+                Memo([0; 32]),
+            );
+            let witness = make_fake_witness(sapling.clone(), &note);
+            (note, witness)
+        })
+        .collect();
+    let notes: Vec<_> = (1..4)
+        .map(|value| {
+            Note::new(
+                sapling.clone(),
+                receiver_key.generate_public_address(),
+                value,
+                Memo([0; 32]),
+            )
+        })
+        .collect();
+
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend_parallel(&spender_key, &spends)
+        .expect("should be able to prove spends");
+    transaction
+        .receive_parallel(&spender_key, &notes)
+        .expect("should be able to prove receipts");
+    assert_eq!(transaction.spends.len(), 3);
+    assert_eq!(transaction.receipts.len(), 3);
+    assert_eq!(transaction.transaction_fee(), 54);
+
+    let public_transaction = transaction
+        .post(&spender_key, None, 54)
+        .expect("should be able to post transaction");
+    public_transaction
+        .verify()
+        .expect("should be able to verify transaction");
+
+    // Notes already spent, or repeated in the batch, are rejected up front
+    assert!(matches!(
+        transaction.spend_parallel(&spender_key, &spends[..1]),
+        Err(SaplingProofError::DuplicateNullifier)
+    ));
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    let repeated = vec![spends[0].clone(), spends[1].clone(), spends[0].clone()];
+    assert!(matches!(
+        transaction.spend_parallel(&spender_key, &repeated),
+        Err(SaplingProofError::DuplicateNullifier)
+    ));
+    assert!(transaction.spends.is_empty());
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_parallel_with_seed_is_deterministic() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::new(sapling.clone(), [7; 32]).unwrap();
+    let mut note_rng = ChaChaRng::from_seed([1; 32]);
+    let spends: Vec<_> = (1..3)
+        .map(|value| {
+            let note = Note::new_with_rng(
+                sapling.clone(),
+                spender_key.generate_public_address(),
+                value * 10,
+                Memo([0; 32]),
+                &mut note_rng,
+            );
+            let witness = make_fake_witness(sapling.clone(), &note);
+            (note, witness)
+        })
+        .collect();
+    let notes: Vec<_> = (1..3)
+        .map(|value| {
+            Note::new_with_rng(
+                sapling.clone(),
+                spender_key.generate_public_address(),
+                value,
+                Memo([0; 32]),
+                &mut note_rng,
+            )
+        })
+        .collect();
+
+    let post_seeded = |seed: [u8; 32]| {
+        let mut transaction = ProposedTransaction::with_seed(sapling.clone(), seed);
+        transaction
+            .spend_parallel(&spender_key, &spends)
+            .expect("should be able to prove spends");
+        transaction
+            .receive_parallel(&spender_key, &notes)
+            .expect("should be able to prove receipts");
+        let posted = transaction
+            .post(&spender_key, None, 27)
+            .expect("should be able to post transaction");
+        let mut serialized = vec![];
+        posted.write(&mut serialized).unwrap();
+        serialized
+    };
+
+    assert_eq!(post_seeded([3; 32]), post_seeded([3; 32]));
+    assert_ne!(post_seeded([3; 32]), post_seeded([4; 32]));
+}
+
+#[test]
+fn test_verify_value_balance() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let out_note = Note::new(
+        sapling.clone(),
+        receiver_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    transaction
+        .receive(&receiver_key, &out_note)
+        .expect("It's a valid note");
+    let mut posted_transaction = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+    assert!(posted_transaction.verify_value_balance().is_ok());
+
+    posted_transaction.transaction_fee = -43;
+    assert!(posted_transaction.verify_value_balance().is_err());
+}
+
+/// Verifier that accepts spend proofs and rejects receipt proofs
+struct RejectReceipts;
+
+impl ProofVerifier<Bls12> for RejectReceipts {
+    fn verify_spend_proof(
+        &self,
+        _sapling: &Sapling<Bls12>,
+        _spend: &SpendProof<Bls12>,
+    ) -> Result<(), SaplingProofError> {
+        Ok(())
+    }
+
+    fn verify_receipt_proof(
+        &self,
+        _sapling: &Sapling<Bls12>,
+        _receipt: &ReceiptProof<Bls12>,
+    ) -> Result<(), SaplingProofError> {
+        Err(SaplingProofError::VerificationFailed)
+    }
+}
+
+#[test]
+fn test_verify_with_other_keys() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let out_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        40,
+        Memo([0; 32]),
+    );
     let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .unwrap();
+    transaction.receive(&spender_key, &out_note).unwrap();
+    let posted = transaction.post(&spender_key, None, 2).unwrap();
+
+    assert!(posted.verify_with_params(&sapling).is_ok());
+
+    let mut serialized_keys = vec![];
+    sapling
+        .verifying_keys()
+        .write(&mut serialized_keys)
+        .unwrap();
+    let keys = VerifyingKeys::read(&serialized_keys[..]).unwrap();
+    assert!(posted.verify_with(&keys).is_ok());
+
+    // keys of a different parameter set reject the proofs
+    let swapped_keys = VerifyingKeys::new(
+        sapling.receipt_params.vk.clone(),
+        sapling.spend_params.vk.clone(),
+    );
+    assert!(posted.verify_with(&swapped_keys).is_err());
+
+    assert!(VerifyingKeys::<Bls12>::read(&serialized_keys[..100]).is_err());
+}
 
+#[test]
+fn test_verify_step() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
     transaction
-        .spend(&in_note, &witness)
-        .expect("should be able to spend note");
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to prove spend");
+    let public_transaction = transaction
+        .post(&spender_key, None, 2)
+        .expect("should be able to post transaction");
+
+    // One spend, one change receipt and the binding signature
+    assert_eq!(public_transaction.verification_steps(), 3);
+    for step in (0..public_transaction.verification_steps()).rev() {
+        public_transaction
+            .verify_step(step)
+            .expect("every step should verify");
+    }
+    assert!(public_transaction
+        .verify_step(public_transaction.verification_steps())
+        .is_err());
+}
+
+#[test]
+fn test_verify_with() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let out_note = Note::new(
+        sapling.clone(),
+        receiver_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    transaction
+        .receive(&receiver_key, &out_note)
+        .expect("It's a valid note");
+    let posted_transaction = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+
+    assert!(posted_transaction.verify_with(&CpuProofVerifier).is_ok());
+    assert!(posted_transaction.verify_with(&RejectReceipts).is_err());
+}
 
+#[test]
+fn test_verify_proofs_before_posting() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let out_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        40,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
     transaction
-        .receive(&out_note)
-        .expect("Should be able to receive note");
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to prove spend");
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to prove receipt");
+    transaction
+        .verify_proofs()
+        .expect("generated proofs should verify");
+    assert_eq!(
+        transaction.receipt_commitments(),
+        vec![transaction.receipts[0].merkle_note.note_commitment()]
+    );
+
+    // A proof for a different anchor doesn't match the public inputs
+    transaction.spends[0].root_hash = witness.root_hash + witness.root_hash;
+    assert!(transaction.verify_proofs().is_err());
+}
+
+#[test]
+fn test_value_commitment_randomness() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let out_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        40,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to prove spend");
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to prove receipt");
+    let spend_randomness = transaction.spend_value_commitment_randomness();
+    let receipt_randomness = transaction.receipt_value_commitment_randomness();
+    assert_eq!(spend_randomness.len(), 1);
+    assert_eq!(receipt_randomness.len(), 1);
+
+    let posted = transaction
+        .post(&spender_key, None, 2)
+        .expect("should be able to post transaction");
+    let commitment = |value, randomness: &[u8; 32]| -> edwards::Point<Bls12, Unknown> {
+        ValueCommitment::<Bls12> {
+            value,
+            randomness: bytes_to_scalar(randomness),
+        }
+        .cm(&sapling.jubjub)
+        .into()
+    };
+    assert!(commitment(42, &spend_randomness[0]) == posted.spends()[0].value_commitment);
+    assert!(
+        commitment(40, &receipt_randomness[0]) == posted.receipts()[0].merkle_note.value_commitment
+    );
+}
+
+#[test]
+fn test_with_capacity() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let transaction: ProposedTransaction<Bls12> =
+        ProposedTransaction::with_capacity(sapling.clone(), 3, 5);
+    assert!(transaction.spends.is_empty());
+    assert!(transaction.spends.capacity() >= 3);
+    assert!(transaction.receipts.is_empty());
+    assert!(transaction.receipts.capacity() >= 5);
+    assert_eq!(transaction.transaction_fee, 0);
+}
+
+#[test]
+fn test_reset() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let out_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        40,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction.set_expiration_sequence(10);
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to spend note");
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to receive note");
+
+    transaction.reset();
+    assert!(transaction.spends.is_empty());
+    assert!(transaction.spends.capacity() >= 1);
+    assert!(transaction.receipts.is_empty());
+    assert!(transaction.receipts.capacity() >= 1);
+    assert_eq!(transaction.transaction_fee, 0);
+    assert_eq!(transaction.expiration_sequence, 0);
+    assert_eq!(
+        transaction.transaction_signature_hash(),
+        ProposedTransaction::new(sapling.clone()).transaction_signature_hash()
+    );
+
+    // The note can be spent again, and the transaction posts like a new one
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to spend note again");
+    let posted = transaction
+        .post(&spender_key, None, 2)
+        .expect("should be able to post transaction");
+    posted
+        .verify()
+        .expect("should be able to verify transaction");
+    assert_eq!(posted.transaction_fee(), 2);
+    assert_eq!(posted.expiration_sequence(), 0);
+    assert_eq!(posted.receipts().len(), 1);
+}
+
+#[test]
+fn test_sapling_parameters_are_shared() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    assert!(Arc::ptr_eq(&sapling, &sapling_bls12::SAPLING));
+
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let out_note = Note::new(
+        sapling.clone(),
+        receiver_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .receive(&receiver_key, &out_note)
+        .expect("It's a valid note");
+    let public_transaction = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+    assert!(Arc::ptr_eq(&public_transaction.sapling, &sapling));
+
+    let mut serialized = vec![];
+    public_transaction.write(&mut serialized).unwrap();
+    let read_back = Transaction::read(sapling.clone(), &mut serialized[..].as_ref()).unwrap();
+    assert!(Arc::ptr_eq(&read_back.sapling, &sapling));
+}
+
+#[test]
+fn test_proposed_transaction_fee() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    assert_eq!(transaction.transaction_fee(), 0);
+
+    for value in [42, 8].iter() {
+        let out_note = Note::new(
+            sapling.clone(),
+            receiver_key.generate_public_address(),
+            *value,
+            Memo([0; 32]),
+        );
+        transaction
+            .receive(&receiver_key, &out_note)
+            .expect("It's a valid note");
+    }
+    assert_eq!(transaction.transaction_fee(), -50);
+}
+
+#[test]
+fn test_post_with_change_outputs() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to prove spend");
+
+    let changes = vec![
+        (spender_key.generate_public_address(), 10),
+        (spender_key.generate_public_address(), 20),
+    ];
+    // 42 - 30 leaves 12 for the fee, not 11
+    assert!(matches!(
+        transaction.post_with_change_outputs(&spender_key, &changes, 11),
+        Err(TransactionError::InvalidBalanceError)
+    ));
+    assert!(matches!(
+        transaction.post_with_change_outputs(
+            &spender_key,
+            &[(spender_key.generate_public_address(), u64::MAX)],
+            12
+        ),
+        Err(TransactionError::InvalidBalanceError)
+    ));
+    assert_eq!(transaction.receipts.len(), 0);
+
+    let posted = transaction
+        .post_with_change_outputs(&spender_key, &changes, 12)
+        .expect("should be able to post transaction");
+    assert_eq!(posted.receipts().len(), 2);
+    assert_eq!(posted.transaction_fee(), 12);
+    posted
+        .verify()
+        .expect("should be able to verify transaction");
+}
+
+#[test]
+fn test_add_change() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to prove spend");
+
+    assert_eq!(transaction.change_amount(2).unwrap(), 40);
+    assert!(matches!(
+        transaction.change_amount(43),
+        Err(TransactionError::InvalidBalanceError)
+    ));
+    assert!(matches!(
+        transaction.change_amount(u64::MAX),
+        Err(TransactionError::InvalidBalanceError)
+    ));
+
+    let change_address = spender_key.generate_public_address();
+    let preview = transaction
+        .preview_change(change_address.clone(), 2)
+        .unwrap()
+        .expect("there should be change");
+    assert_eq!(preview.value(), 40);
+    assert_eq!(preview.owner(), change_address);
+    assert!(transaction
+        .preview_change(change_address.clone(), 42)
+        .unwrap()
+        .is_none());
+    assert!(matches!(
+        transaction.preview_change(change_address.clone(), 43),
+        Err(TransactionError::InvalidBalanceError)
+    ));
+    assert!(transaction.receipts.is_empty());
+
+    assert_eq!(
+        transaction
+            .add_change(&spender_key, Some(change_address.clone()), 2)
+            .unwrap(),
+        40
+    );
+    assert_eq!(transaction.receipts.len(), 1);
+    assert_eq!(transaction.change_amount(2).unwrap(), 0);
+    assert!(transaction
+        .preview_change(change_address, 2)
+        .unwrap()
+        .is_none());
+
+    // Posting with the same fee doesn't add the change a second time
+    let posted = transaction
+        .post(&spender_key, None, 2)
+        .expect("should be able to post transaction");
+    assert_eq!(posted.receipts().len(), 1);
+    assert_eq!(posted.transaction_fee(), 2);
+    let change_note = posted.receipts()[0]
+        .merkle_note()
+        .decrypt_note_for_owner(spender_key.incoming_view_key())
+        .expect("spender should be able to decrypt the change");
+    assert_eq!(change_note.owner(), preview.owner());
+    assert_eq!(change_note.value(), preview.value());
+    posted
+        .verify()
+        .expect("should be able to verify transaction");
+}
+
+#[test]
+fn test_sign_offline() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let out_note = Note::new(
+        sapling.clone(),
+        receiver_key.generate_public_address(),
+        30,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction.set_expiration_sequence(10);
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to spend note");
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to receive note");
+    transaction
+        .add_change(&spender_key, None, 2)
+        .expect("should be able to add change");
+
+    let mut bytes = vec![];
+    transaction
+        .write_unsigned(&mut bytes)
+        .expect("should be able to write unsigned transaction");
+    let unsigned = UnsignedTransaction::read(sapling.clone(), &bytes[..])
+        .expect("should be able to read unsigned transaction");
+    assert_eq!(
+        unsigned.transaction_signature_hash(),
+        transaction.transaction_signature_hash()
+    );
+    assert_eq!(unsigned.transaction_fee(), 2);
+
+    let signed = sign_offline(sapling.clone(), &bytes, &spender_key)
+        .expect("should be able to sign transaction");
+    signed
+        .verify()
+        .expect("should be able to verify transaction");
+    assert_eq!(signed.transaction_fee(), 2);
+    assert_eq!(signed.expiration_sequence(), 10);
+    assert_eq!(signed.receipts().len(), 2);
+
+    assert!(matches!(
+        sign_offline(sapling.clone(), &bytes, &receiver_key),
+        Err(TransactionError::SigningError)
+    ));
+
+    let mut corrupted = bytes.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 1;
+    assert!(matches!(
+        sign_offline(sapling.clone(), &corrupted, &spender_key),
+        Err(TransactionError::IoError(_))
+    ));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(
+        sign_offline(sapling.clone(), &trailing, &spender_key),
+        Err(TransactionError::IoError(_))
+    ));
+
+    // Outputs worth more than the spends can't be signed
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to receive note");
+    assert!(matches!(
+        transaction.write_unsigned(&mut vec![]),
+        Err(TransactionError::InvalidBalanceError)
+    ));
+}
+
+#[test]
+fn test_draft() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let out_note = Note::new(
+        sapling.clone(),
+        receiver_key.generate_public_address(),
+        30,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction.set_expiration_sequence(10);
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to spend note");
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to receive note");
+
+    let mut bytes = vec![];
+    transaction
+        .write_draft(&mut bytes)
+        .expect("should be able to write draft");
+    let mut draft = ProposedTransaction::read_draft(sapling.clone(), &spender_key, &bytes[..])
+        .expect("should be able to read draft");
+    assert_eq!(
+        draft.transaction_signature_hash(),
+        transaction.transaction_signature_hash()
+    );
+
+    let posted = draft
+        .post(&spender_key, None, 2)
+        .expect("should be able to post draft");
+    posted
+        .verify()
+        .expect("should be able to verify transaction");
+    assert_eq!(posted.transaction_fee(), 2);
+    assert_eq!(posted.expiration_sequence(), 10);
+    assert_eq!(posted.spends().len(), 1);
+    assert_eq!(posted.receipts().len(), 2);
+
+    assert!(matches!(
+        ProposedTransaction::read_draft(sapling.clone(), &receiver_key, &bytes[..]),
+        Err(TransactionError::SigningError)
+    ));
+    assert!(matches!(
+        ProposedTransaction::read_draft(sapling.clone(), &spender_key, &bytes[..bytes.len() - 1]),
+        Err(TransactionError::IoError(_))
+    ));
+    assert!(matches!(
+        Transaction::read(sapling.clone(), &bytes[..]),
+        Err(TransactionError::UnsupportedVersion(_))
+    ));
+}
+
+#[test]
+fn test_outputs_for_fee_rate() {
+    let sapling = &*sapling_bls12::SAPLING;
+    let transaction: ProposedTransaction<Bls12> = ProposedTransaction::new(sapling.clone());
+    let fixed_size = TRANSACTION_HEADER_SIZE + BINDING_SIGNATURE_SIZE;
+
+    assert_eq!(
+        transaction.outputs_for_fee_rate(1.0, (fixed_size + 2 * RECEIPT_PROOF_SIZE) as u64),
+        2
+    );
+    assert_eq!(
+        transaction.outputs_for_fee_rate(2.0, (fixed_size + 2 * RECEIPT_PROOF_SIZE) as u64),
+        0
+    );
+    assert_eq!(
+        transaction.outputs_for_fee_rate(0.5, (fixed_size + 2 * RECEIPT_PROOF_SIZE) as u64),
+        4
+    );
+    assert_eq!(
+        transaction.outputs_for_fee_rate(1.0, fixed_size as u64 - 1),
+        0
+    );
+    assert_eq!(transaction.outputs_for_fee_rate(0.0, 1_000_000), 0);
+    assert_eq!(transaction.outputs_for_fee_rate(f64::NAN, 1_000_000), 0);
+}
+
+#[test]
+fn test_binding_signature_message() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let out_note = Note::new(
+        sapling.clone(),
+        receiver_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .receive(&receiver_key, &out_note)
+        .expect("It's a valid note");
+    let public_transaction = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+
+    let message = public_transaction
+        .binding_signature_message()
+        .expect("should be able to compute message");
+    assert_eq!(
+        message[32..],
+        public_transaction.transaction_signature_hash()[..]
+    );
+
+    // The key half is the binding verification key with the fee taken out,
+    // which for a balanced transaction is a commitment to zero value
+    let key = PublicKey::<Bls12>::read(&message[..32], &sapling.jubjub)
+        .expect("message should start with a valid point");
+    assert!(key.verify(
+        &message,
+        public_transaction.binding_signature(),
+        FixedGenerators::ValueCommitmentRandomness,
+        &sapling.jubjub
+    ));
+}
+
+#[test]
+fn test_expiration_sequence() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let out_note = Note::new(
+        sapling.clone(),
+        receiver_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .receive(&receiver_key, &out_note)
+        .expect("It's a valid note");
+    let unexpiring = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+    assert_eq!(unexpiring.expiration_sequence(), 0);
+    assert_eq!(unexpiring.version(), 0);
+    assert!(unexpiring.verify_expiration(u32::MAX).is_ok());
+    let mut unexpiring_bytes = vec![];
+    unexpiring.write(&mut unexpiring_bytes).unwrap();
+    // Transactions without an expiration keep the original format
+    assert_eq!(unexpiring_bytes[..8], 0u64.to_le_bytes());
+
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction.set_expiration_sequence(10);
+    transaction
+        .receive(&receiver_key, &out_note)
+        .expect("It's a valid note");
+    let public_transaction = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+    assert!(public_transaction.verify().is_ok());
+    assert!(public_transaction.verify_expiration(9).is_ok());
+    assert!(public_transaction.verify_expiration(10).is_ok());
+    assert!(public_transaction.verify_expiration(11).is_err());
+
+    let mut serialized = vec![];
+    public_transaction.write(&mut serialized).unwrap();
+    assert_eq!(
+        serialized.len(),
+        unexpiring_bytes.len() + EXPIRATION_SEQUENCE_SIZE
+    );
+    assert_eq!(serialized.len(), public_transaction.serialized_size());
+
+    let read_back = Transaction::read(sapling.clone(), &mut serialized[..].as_ref())
+        .expect("should be able to read an expiring transaction");
+    assert_eq!(read_back.expiration_sequence(), 10);
+    assert_eq!(read_back.version(), 1);
+    assert!(read_back.verify().is_ok());
+    let mut written = vec![];
+    read_back.write(&mut written).unwrap();
+    assert_eq!(written, serialized);
+
+    // The expiration is covered by the signatures
+    let offset = TRANSACTION_HEADER_SIZE;
+    serialized[offset..offset + EXPIRATION_SEQUENCE_SIZE].copy_from_slice(&20u32.to_le_bytes());
+    let tampered = Transaction::read(sapling.clone(), &mut serialized[..].as_ref())
+        .expect("should still be able to read the transaction");
+    assert_eq!(tampered.expiration_sequence(), 20);
+    assert!(tampered.verify().is_err());
+
+    // A v1 transaction that doesn't expire signs the same as the v0 one, so
+    // it is rejected to keep one encoding per transaction
+    serialized[offset..offset + EXPIRATION_SEQUENCE_SIZE].copy_from_slice(&0u32.to_le_bytes());
+    match Transaction::read(sapling.clone(), &mut serialized[..].as_ref()) {
+        Err(TransactionError::IoError(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::InvalidData)
+        }
+        _ => panic!("a v1 transaction without expiration isn't canonical"),
+    }
+
+    // Future versions, whose layout isn't known yet, are rejected
+    assert_eq!(TRANSACTION_VERSION, 1);
+    serialized[7] = 2;
+    assert!(matches!(
+        Transaction::read(sapling, &mut serialized[..].as_ref()),
+        Err(TransactionError::UnsupportedVersion(2))
+    ));
+}
+
+#[test]
+fn test_with_seed_is_deterministic() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::new(sapling.clone(), [7; 32]).unwrap();
+
+    let post_seeded = |seed: [u8; 32]| {
+        let mut note_rng = ChaChaRng::from_seed([1; 32]);
+        let out_note = Note::new_with_rng(
+            sapling.clone(),
+            receiver_key.generate_public_address(),
+            42,
+            Memo([0; 32]),
+            &mut note_rng,
+        );
+        let mut transaction = ProposedTransaction::with_seed(sapling.clone(), seed);
+        transaction
+            .receive(&receiver_key, &out_note)
+            .expect("It's a valid note");
+        let posted = transaction
+            .post_miners_fee()
+            .expect("it is a valid miner's fee");
+        assert!(posted.verify().is_ok());
+        let mut serialized = vec![];
+        posted.write(&mut serialized).unwrap();
+        serialized
+    };
+
+    assert_eq!(post_seeded([3; 32]), post_seeded([3; 32]));
+    assert_ne!(post_seeded([3; 32]), post_seeded([4; 32]));
+}
+
+#[test]
+fn test_fingerprint() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to spend note");
+    let posted = transaction
+        .post(&spender_key, None, 42)
+        .expect("should be able to post transaction");
+
+    let mut serialized = vec![];
+    posted.write(&mut serialized).unwrap();
+    let read_back =
+        Transaction::read(sapling.clone(), &serialized[..]).expect("should be able to read");
+    assert_eq!(read_back.fingerprint(), posted.fingerprint());
+    assert_ne!(posted.fingerprint(), posted.transaction_signature_hash());
+
+    // Signing the same content again gives new signatures
+    let posted_again = transaction
+        .post(&spender_key, None, 42)
+        .expect("should be able to post transaction");
+    assert_eq!(
+        posted_again.transaction_signature_hash(),
+        posted.transaction_signature_hash()
+    );
+    assert_ne!(posted_again.fingerprint(), posted.fingerprint());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let out_note = Note::new(
+        sapling.clone(),
+        receiver_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let mut transaction = ProposedTransaction::new(sapling);
+    transaction
+        .receive(&receiver_key, &out_note)
+        .expect("It's a valid note");
+    let posted = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+    let mut serialized = vec![];
+    posted.write(&mut serialized).unwrap();
+
+    let json = serde_json::to_string(&posted).unwrap();
+    let from_json: Transaction<Bls12> = serde_json::from_str(&json).unwrap();
+    assert!(from_json.verify().is_ok());
+    let mut reserialized = vec![];
+    from_json.write(&mut reserialized).unwrap();
+    assert_eq!(reserialized, serialized);
+
+    let binary = bincode::serialize(&posted).unwrap();
+    // A u64 length prefix followed by the bytes from `write`
+    assert_eq!(binary[8..], serialized[..]);
+    let from_binary: Transaction<Bls12> = bincode::deserialize(&binary).unwrap();
+    assert!(from_binary.verify().is_ok());
+
+    assert!(serde_json::from_str::<Transaction<Bls12>>(r#""00""#).is_err());
+}
+
+#[test]
+fn test_transaction_stream() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let mut stream_bytes = vec![];
+    for value in 1..=3 {
+        let out_note = Note::new(
+            sapling.clone(),
+            receiver_key.generate_public_address(),
+            value,
+            Memo([0; 32]),
+        );
+        let mut transaction = ProposedTransaction::new(sapling.clone());
+        transaction
+            .receive(&receiver_key, &out_note)
+            .expect("It's a valid note");
+        transaction
+            .post_miners_fee()
+            .expect("it is a valid miner's fee")
+            .write(&mut stream_bytes)
+            .unwrap();
+    }
+
+    let transactions: Vec<Transaction<Bls12>> =
+        TransactionStream::new(sapling.clone(), &stream_bytes[..])
+            .collect::<Result<_, _>>()
+            .expect("should read every transaction in the stream");
+    assert_eq!(transactions.len(), 3);
+    for (transaction, fee) in transactions.iter().zip(&[-1, -2, -3]) {
+        assert_eq!(transaction.transaction_fee(), *fee);
+        assert!(transaction.verify().is_ok());
+    }
+
+    assert_eq!(TransactionStream::new(sapling.clone(), &[][..]).count(), 0);
+
+    // A truncated last transaction is an error, and ends the stream
+    let truncated = &stream_bytes[..stream_bytes.len() - 10];
+    let results: Vec<_> = TransactionStream::new(sapling, truncated).collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[1].is_ok());
+    assert!(results[2].is_err());
+}
+
+#[test]
+fn test_read_truncated_transaction() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let out_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        40,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .unwrap();
+    transaction.receive(&spender_key, &out_note).unwrap();
+    let posted = transaction.post(&spender_key, None, 2).unwrap();
+    let mut bytes = vec![];
+    posted.write(&mut bytes).unwrap();
+
+    // every cut, including ones in the middle of a point or proof, is a
+    // clean end of file error
+    for length in (0..bytes.len()).step_by(7) {
+        match Transaction::read(sapling.clone(), &bytes[..length]) {
+            Err(TransactionError::IoError(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof, "{}", length)
+            }
+            _ => panic!("reading {} of {} bytes should fail", length, bytes.len()),
+        }
+    }
+
+    // a header declaring more spends than the bytes hold
+    let mut extra_spends = bytes.clone();
+    extra_spends[0] = 3;
+    match Transaction::read(sapling.clone(), &extra_spends[..]) {
+        Err(TransactionError::IoError(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+            assert!(e.to_string().contains("declares 3 spends"));
+        }
+        _ => panic!("three spends can't be read"),
+    }
+
+    // all the bytes of the receipt are there, but its value commitment
+    // isn't a point
+    let mut corrupted = bytes.clone();
+    let value_commitment = TRANSACTION_HEADER_SIZE + SPEND_PROOF_SIZE + 192;
+    for byte in corrupted[value_commitment..value_commitment + 32].iter_mut() {
+        *byte = 0xff;
+    }
+    match Transaction::read(sapling, &corrupted[..]) {
+        Err(TransactionError::IoError(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+            assert!(e.to_string().contains("receipt 0 of 1"));
+        }
+        _ => panic!("the receipt is invalid"),
+    }
+}
+
+#[test]
+fn test_canonical_serialization() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+
+    // (values spent, values received, expiration sequence)
+    let shapes: Vec<(Vec<u64>, Vec<u64>, u32)> = vec![
+        (vec![], vec![7], 0),
+        (vec![10], vec![3], 0),
+        (vec![10], vec![3, 4], 9),
+        (vec![5, 6], vec![10], 1),
+        (vec![8, 8], vec![1, 2, 3], 0),
+    ];
+    for (spend_values, receipt_values, expiration) in shapes {
+        let mut transaction = ProposedTransaction::new(sapling.clone());
+        transaction.set_expiration_sequence(expiration);
+        for value in spend_values.iter() {
+            let note = Note::new(
+                sapling.clone(),
+                spender_key.generate_public_address(),
+                *value,
+                Memo([0; 32]),
+            );
+            let witness = make_fake_witness(sapling.clone(), &note);
+            transaction
+                .spend(spender_key.clone(), &note, &witness)
+                .unwrap();
+        }
+        for value in receipt_values.iter() {
+            let note = Note::new(
+                sapling.clone(),
+                receiver_key.generate_public_address(),
+                *value,
+                Memo([0; 32]),
+            );
+            transaction.receive(&spender_key, &note).unwrap();
+        }
+        let posted = if spend_values.is_empty() {
+            transaction.post_miners_fee().unwrap()
+        } else {
+            transaction.post(&spender_key, None, 1).unwrap()
+        };
+
+        let mut bytes = vec![];
+        posted.write(&mut bytes).unwrap();
+        let mut again = vec![];
+        posted.write(&mut again).unwrap();
+        assert_eq!(bytes, again);
+
+        let read_back = Transaction::read(sapling.clone(), &bytes[..]).unwrap();
+        let mut rewritten = vec![];
+        read_back.write(&mut rewritten).unwrap();
+        assert_eq!(bytes, rewritten);
+        assert_eq!(
+            read_back.transaction_signature_hash(),
+            posted.transaction_signature_hash()
+        );
+        assert!(read_back.verify().is_ok());
+    }
+}
+
+#[test]
+fn test_read_rejects_non_canonical_encodings() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let note = Note::new(
+        sapling.clone(),
+        key.generate_public_address(),
+        5,
+        Memo([0; 32]),
+    );
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction.receive(&key, &note).unwrap();
+    let mut bytes = vec![];
+    transaction
+        .post_miners_fee()
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+
+    // the expiration version with an expiration of 0 describes the same
+    // transaction as the original version
+    let mut expiration_version = bytes[..TRANSACTION_HEADER_SIZE].to_vec();
+    expiration_version[7] = TRANSACTION_VERSION;
+    expiration_version.extend_from_slice(&[0; EXPIRATION_SEQUENCE_SIZE]);
+    expiration_version.extend_from_slice(&bytes[TRANSACTION_HEADER_SIZE..]);
+    match Transaction::read(sapling.clone(), &expiration_version[..]) {
+        Err(TransactionError::IoError(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::InvalidData)
+        }
+        _ => panic!("the expiration version needs an expiration"),
+    }
+
+    // the identity point with the sign bit of its x coordinate set
+    let mut sign_bit = bytes.clone();
+    let value_commitment = TRANSACTION_HEADER_SIZE + 192;
+    let mut identity = [0; 32];
+    identity[0] = 1;
+    identity[31] = 0x80;
+    sign_bit[value_commitment..value_commitment + 32].copy_from_slice(&identity);
+    match Transaction::read(sapling, &sign_bit[..]) {
+        Err(TransactionError::IoError(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::InvalidData)
+        }
+        _ => panic!("the value commitment isn't canonical"),
+    }
+}
+
+#[test]
+fn test_concatenated_transactions_are_not_merged() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let receiver_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let posted: Vec<Vec<u8>> = (1..=2)
+        .map(|value| {
+            let out_note = Note::new(
+                sapling.clone(),
+                receiver_key.generate_public_address(),
+                value,
+                Memo([0; 32]),
+            );
+            let mut transaction = ProposedTransaction::new(sapling.clone());
+            transaction
+                .receive(&receiver_key, &out_note)
+                .expect("It's a valid note");
+            let mut bytes = vec![];
+            transaction
+                .post_miners_fee()
+                .expect("it is a valid miner's fee")
+                .write(&mut bytes)
+                .unwrap();
+            bytes
+        })
+        .collect();
+
+    // Reading concatenated bytes gives back the first transaction only, and
+    // leaves the second one in the reader
+    let concatenated = [&posted[0][..], &posted[1][..]].concat();
+    let mut reader = &concatenated[..];
+    let first = Transaction::read(sapling.clone(), &mut reader).unwrap();
+    assert_eq!(first.receipts().len(), 1);
+    assert_eq!(first.transaction_fee(), -1);
+    assert_eq!(reader, &posted[1][..]);
+
+    // Splicing the receipt of the second transaction into the first doesn't
+    // make a valid transaction out of two binding signatures' worth of data
+    let receipt_end = posted[0].len() - BINDING_SIGNATURE_SIZE;
+    let mut spliced = posted[0][..receipt_end].to_vec();
+    spliced[8..16].copy_from_slice(&2u64.to_le_bytes());
+    spliced[16..24].copy_from_slice(&(-3i64).to_le_bytes());
+    spliced.extend_from_slice(&posted[1][TRANSACTION_HEADER_SIZE..receipt_end]);
+    spliced.extend_from_slice(&posted[0][receipt_end..]);
+    let spliced = Transaction::read(sapling, &mut &spliced[..])
+        .expect("should be able to read the spliced transaction");
+    assert_eq!(spliced.receipts().len(), 2);
+    assert!(spliced.verify().is_err());
+}
+
+#[test]
+fn test_zero_value_notes() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let zero_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        0,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &zero_note);
+
+    // Zero value notes can pad the outputs
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .receive(&spender_key, &zero_note)
+        .expect("should be able to receive zero value note");
+    transaction
+        .post_miners_fee()
+        .expect("should be able to post transaction")
+        .verify()
+        .expect("should be able to verify transaction");
+
+    // but not be spent
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    assert!(matches!(
+        transaction.spend(spender_key.clone(), &zero_note, &witness),
+        Err(SaplingProofError::ZeroValueSpend)
+    ));
+    assert!(transaction.spends.is_empty());
+}
+
+#[test]
+fn test_duplicate_nullifiers_are_rejected() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to spend note");
+    assert!(matches!(
+        transaction.spend(spender_key.clone(), &in_note, &witness),
+        Err(SaplingProofError::DuplicateNullifier)
+    ));
+    assert_eq!(transaction.spends.len(), 1);
+
+    // Spends added directly are caught when posting
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    for _ in 0..2 {
+        let proof = SpendParams::new(sapling.clone(), spender_key.clone(), &in_note, &witness)
+            .expect("should be able to create spend proof");
+        transaction.add_spend_proof(proof, in_note.value());
+    }
+    assert!(matches!(
+        transaction.post(&spender_key, None, 84),
+        Err(TransactionError::DuplicateNullifier)
+    ));
+
+    // And a posted transaction that spends a note twice doesn't verify
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to spend note");
+    let mut posted = transaction
+        .post(&spender_key, None, 42)
+        .expect("should be able to post transaction");
+    assert!(posted.verify().is_ok());
+    let duplicate = posted.spends[0].clone();
+    posted.spends.push(duplicate);
+    assert!(matches!(
+        posted.verify(),
+        Err(TransactionError::DuplicateNullifier)
+    ));
+    assert!(matches!(
+        posted.verify_step(posted.verification_steps() - 1),
+        Err(TransactionError::DuplicateNullifier)
+    ));
+}
+
+#[test]
+fn test_verify_detailed() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let out_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        40,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to spend note");
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to receive note");
+    let posted = transaction
+        .post(&spender_key, None, 2)
+        .expect("should be able to post transaction");
+    assert_eq!(posted.verify_detailed(), Ok(()));
+
+    let read_signature = |signature: &Signature| {
+        let mut bytes = vec![];
+        signature.write(&mut bytes).unwrap();
+        Signature::read(&bytes[..]).unwrap()
+    };
+
+    let mut tampered = posted.clone();
+    tampered.spends[0].value_commitment = posted.receipts[0].merkle_note.value_commitment.clone();
+    assert_eq!(
+        tampered.verify_detailed(),
+        Err(VerificationError::SpendProofInvalid(0))
+    );
+
+    let mut tampered = posted.clone();
+    tampered.receipts[0].merkle_note.value_commitment = posted.spends[0].value_commitment.clone();
+    assert_eq!(
+        tampered.verify_detailed(),
+        Err(VerificationError::ReceiptProofInvalid(0))
+    );
+
+    let mut tampered = posted.clone();
+    tampered.spends[0].authorizing_signature = read_signature(&posted.binding_signature);
+    assert_eq!(
+        tampered.verify_detailed(),
+        Err(VerificationError::SpendSignatureInvalid(0))
+    );
+
+    let mut tampered = posted.clone();
+    tampered.binding_signature = read_signature(&posted.spends[0].authorizing_signature);
+    assert_eq!(
+        tampered.verify_detailed(),
+        Err(VerificationError::BindingSignatureInvalid)
+    );
+
+    let mut tampered = posted.clone();
+    tampered.spends.push(posted.spends[0].clone());
+    assert_eq!(
+        tampered.verify_detailed(),
+        Err(VerificationError::DuplicateNullifier)
+    );
+
+    // Without spends there are no spend signatures over the fee to fail first
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to receive note");
+    let mut miners_fee = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+    miners_fee.transaction_fee = i64::MIN;
+    assert_eq!(
+        miners_fee.verify_detailed(),
+        Err(VerificationError::FeeMismatch)
+    );
+    miners_fee.transaction_fee = i64::MIN + 1;
+    assert_eq!(
+        miners_fee.verify_detailed(),
+        Err(VerificationError::BindingSignatureInvalid)
+    );
+    assert_eq!(VerificationError::FeeMismatch.to_string(), "FeeMismatch");
+}
+
+#[test]
+fn test_verify_miners_fee() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let out_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        40,
+        Memo([0; 32]),
+    );
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to receive note");
+    #[allow(deprecated)]
+    let minted = transaction
+        .post_genesis_transaction()
+        .expect("should be able to post transaction");
+    let miners_fee = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+
+    miners_fee
+        .verify_miners_fee(40)
+        .expect("should be able to verify miner's fee");
+    assert!(matches!(
+        miners_fee.verify_miners_fee(39),
+        Err(TransactionError::InvalidBalanceError)
+    ));
+
+    // Minting is valid on its own, but only the miners fee may do it
+    minted
+        .verify()
+        .expect("should be able to verify transaction");
+    assert!(matches!(
+        minted.verify_miners_fee(40),
+        Err(TransactionError::InvalidBalanceError)
+    ));
+
+    let mut tampered = miners_fee.clone();
+    tampered.transaction_fee = -41;
+    assert!(matches!(
+        tampered.verify_miners_fee(41),
+        Err(TransactionError::VerificationFailed)
+    ));
+}
+
+#[test]
+fn test_verify_block() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+    let out_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        40,
+        Memo([0; 32]),
+    );
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .receive(&spender_key, &out_note)
+        .expect("should be able to receive note");
+    let posted = transaction
+        .post_miners_fee()
+        .expect("it is a valid miner's fee");
+
+    let mut tampered = posted.clone();
+    tampered.transaction_fee += 1;
+
+    let in_note = Note::new(
+        sapling.clone(),
+        spender_key.generate_public_address(),
+        42,
+        Memo([0; 32]),
+    );
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+    let mut transaction = ProposedTransaction::new(sapling.clone());
+    transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to prove spend");
+    let spending = transaction
+        .post(&spender_key, None, 1)
+        .expect("should be able to post transaction");
+
+    assert_eq!(verify_block(&[posted.clone(), spending.clone()]), Ok(()));
+    assert_eq!(verify_block(Vec::<&Transaction<Bls12>>::new()), Ok(()));
+    assert_eq!(
+        verify_block(&[posted.clone(), tampered, posted.clone()]),
+        Err((1, VerificationError::BindingSignatureInvalid))
+    );
+    // Each transaction is valid on its own, but the note is spent twice
+    assert_eq!(
+        verify_block(&[spending.clone(), posted, spending]),
+        Err((2, VerificationError::DuplicateNullifier))
+    );
+}
+
+#[test]
+fn test_transaction_signature() {
+    let sapling = sapling_bls12::SAPLING.clone();
+    let spender_key = SaplingKey::generate_key(sapling.clone());
+    let receiver_key = SaplingKey::generate_key(sapling.clone());
+    let spender_address = spender_key.generate_public_address();
+    let receiver_address = receiver_key.generate_public_address();
+
+    let mut transaction = SimpleTransaction::new(sapling.clone(), spender_key.clone(), 0);
+    let in_note = Note::new(sapling.clone(), spender_address.clone(), 42, Memo([0; 32]));
+    let out_note = Note::new(sapling.clone(), receiver_address.clone(), 41, Memo([0; 32]));
+    let witness = make_fake_witness(sapling.clone(), &in_note);
+
+    transaction
+        .spend(&in_note, &witness)
+        .expect("should be able to spend note");
+
+    transaction
+        .receive(&out_note)
+        .expect("Should be able to receive note");
+
+    let mut proposed_transaction = ProposedTransaction::new(sapling.clone());
+    proposed_transaction
+        .spend(spender_key.clone(), &in_note, &witness)
+        .expect("should be able to spend note");
+    let unsigned_hash = proposed_transaction.transaction_signature_hash();
+    proposed_transaction
+        .receive(&spender_key, &out_note)
+        .expect("Should be able to receive note");
+    let signature_hash = proposed_transaction.transaction_signature_hash();
+    // Adding a receipt invalidates the hash
+    assert_ne!(unsigned_hash, signature_hash);
+    // No change is needed, so posting signs the same hash
+    let posted = proposed_transaction
+        .post(&spender_key, None, 1)
+        .expect("should be able to post transaction");
+    assert_eq!(posted.transaction_signature_hash(), signature_hash);
 
     let public_transaction = transaction
         .post()
@@ -0,0 +1,266 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use super::{
+    binding_signature, check_unique_nullifiers, check_value_consistency, version_for,
+    write_signature_header, ProposedTransaction, Transaction, SIGNATURE_HASH_PERSONALIZATION,
+    TRANSACTION_VERSION, TRANSACTION_VERSION_EXPIRATION, TRANSACTION_VERSION_SHIFT,
+};
+use crate::{
+    errors::TransactionError, keys::SaplingKey, receiving::ReceiptProof, serializing::read_scalar,
+    spending::UnsignedSpend, Sapling,
+};
+use blake2b_simd::Params as Blake2b;
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::PrimeField;
+use rand::rngs::OsRng;
+use std::{io, sync::Arc};
+use zcash_primitives::jubjub::{edwards, JubjubEngine, Unknown};
+
+/// A transaction whose proofs have all been created, but that hasn't been
+/// signed. Used to build a transaction on an online machine and sign it on
+/// an offline one that holds the spending key.
+///
+/// It holds the spends without their authorizing signatures, the receipts,
+/// the fee, the expiration sequence, and the binding signature key. It does
+/// not hold the spending key, but the binding signature key and the public
+/// key randomness of each spend reveal the values of the notes and link the
+/// spends to the spender, so it should only travel between machines of the
+/// same owner.
+///
+/// Written by `ProposedTransaction::write_unsigned`, and turned into a
+/// `Transaction` by `sign`.
+pub struct UnsignedTransaction<J: JubjubEngine + pairing::MultiMillerLoop> {
+    sapling: Arc<Sapling<J>>,
+    transaction_fee: i64,
+    expiration_sequence: u32,
+    spends: Vec<UnsignedSpend<J>>,
+    receipts: Vec<ReceiptProof<J>>,
+    binding_signature_key: J::Fs,
+}
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> ProposedTransaction<J> {
+    /// The transaction as it stands, to be signed elsewhere. Everything not
+    /// spent on outputs goes to the fee, so add any change first with
+    /// `add_change`.
+    ///
+    /// Fails with `InvalidBalanceError` if the outputs are worth more than
+    /// the spends, and with `DuplicateNullifier` if a note is spent twice.
+    pub fn unsigned(&self) -> Result<UnsignedTransaction<J>, TransactionError> {
+        self.check_value_consistency()?;
+        check_unique_nullifiers(self.spends.iter().map(|spend| &spend.nullifier))?;
+        if self.transaction_fee < 0 {
+            return Err(TransactionError::InvalidBalanceError);
+        }
+        Ok(UnsignedTransaction {
+            sapling: self.sapling.clone(),
+            transaction_fee: self.transaction_fee,
+            expiration_sequence: self.expiration_sequence,
+            spends: self.spends.iter().map(|spend| spend.unsigned()).collect(),
+            receipts: self
+                .receipts
+                .iter()
+                .map(|receipt| ReceiptProof {
+                    proof: receipt.proof.clone(),
+                    merkle_note: receipt.merkle_note.clone(),
+                })
+                .collect(),
+            binding_signature_key: self.binding_signature_key,
+        })
+    }
+
+    /// Write the unsigned form of the transaction, as returned by `unsigned`,
+    /// for `sign_offline` to complete on the machine that holds the spending
+    /// key.
+    pub fn write_unsigned<W: io::Write>(&self, writer: W) -> Result<(), TransactionError> {
+        self.unsigned()?.write(writer)?;
+        Ok(())
+    }
+}
+
+impl<J: JubjubEngine + pairing::MultiMillerLoop> UnsignedTransaction<J> {
+    /// Load an unsigned transaction written by `write`.
+    ///
+    /// The signature hash written at the end is checked against the one
+    /// computed from the transaction, so a transaction that was corrupted on
+    /// the way fails with an `IoError` here rather than being signed.
+    pub fn read<R: io::Read>(
+        sapling: Arc<Sapling<J>>,
+        mut reader: R,
+    ) -> Result<Self, TransactionError> {
+        let versioned_num_spends = reader.read_u64::<LittleEndian>()?;
+        let version = (versioned_num_spends >> TRANSACTION_VERSION_SHIFT) as u8;
+        let num_spends = versioned_num_spends & ((1 << TRANSACTION_VERSION_SHIFT) - 1);
+        if version > TRANSACTION_VERSION {
+            return Err(TransactionError::UnsupportedVersion(version));
+        }
+        let num_receipts = reader.read_u64::<LittleEndian>()?;
+        let transaction_fee = reader.read_i64::<LittleEndian>()?;
+        let expiration_sequence = if version >= TRANSACTION_VERSION_EXPIRATION {
+            reader.read_u32::<LittleEndian>()?
+        } else {
+            0
+        };
+        let binding_signature_key = read_scalar(&mut reader).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "invalid binding signature key")
+        })?;
+        let mut spends = vec![];
+        let mut receipts = vec![];
+        for _ in 0..num_spends {
+            spends.push(UnsignedSpend::read(&sapling.jubjub, &mut reader)?);
+        }
+        for _ in 0..num_receipts {
+            receipts.push(ReceiptProof::read(sapling.clone(), &mut reader)?);
+        }
+        let mut signature_hash = [0; 32];
+        reader.read_exact(&mut signature_hash)?;
+
+        let transaction = UnsignedTransaction {
+            sapling,
+            transaction_fee,
+            expiration_sequence,
+            spends,
+            receipts,
+            binding_signature_key,
+        };
+        if transaction.transaction_signature_hash() != signature_hash {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "signature hash doesn't match the transaction",
+            )
+            .into());
+        }
+        Ok(transaction)
+    }
+
+    /// Store the bytes of the unsigned transaction: the header of a
+    /// transaction, the binding signature key, the spends with their public
+    /// key randomness, the receipts, and the signature hash.
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let version = version_for(self.expiration_sequence);
+        writer.write_u64::<LittleEndian>(
+            ((version as u64) << TRANSACTION_VERSION_SHIFT) | self.spends.len() as u64,
+        )?;
+        writer.write_u64::<LittleEndian>(self.receipts.len() as u64)?;
+        writer.write_i64::<LittleEndian>(self.transaction_fee)?;
+        if version >= TRANSACTION_VERSION_EXPIRATION {
+            writer.write_u32::<LittleEndian>(self.expiration_sequence)?;
+        }
+        writer.write_all(self.binding_signature_key.to_repr().as_ref())?;
+        for spend in self.spends.iter() {
+            spend.write(&mut writer)?;
+        }
+        for receipt in self.receipts.iter() {
+            receipt.write(&mut writer)?;
+        }
+        writer.write_all(&self.transaction_signature_hash())?;
+
+        Ok(())
+    }
+
+    /// Sign every spend with `spender_key` and add the binding signature,
+    /// giving the transaction that is ready to publish.
+    ///
+    /// Fails with `InvalidBalanceError` if the values don't balance against
+    /// the binding signature key, and with `SigningError` if the spends
+    /// weren't proven with `spender_key`. The proofs are verified before
+    /// returning, like `ProposedTransaction::post` does.
+    pub fn sign(&self, spender_key: &SaplingKey<J>) -> Result<Transaction<J>, TransactionError> {
+        check_unique_nullifiers(self.spends.iter().map(|spend| &spend.nullifier))?;
+        check_value_consistency(
+            &self.sapling.jubjub,
+            self.binding_signature_key,
+            &self.binding_verification_key(),
+            self.transaction_fee,
+        )?;
+
+        let signature_hash = self.transaction_signature_hash();
+        let binding_signature = binding_signature(
+            &self.sapling.jubjub,
+            self.binding_signature_key,
+            &signature_hash,
+            &mut OsRng,
+        );
+        let mut spends = vec![];
+        for spend in self.spends.iter() {
+            spends.push(spend.sign(&self.sapling, spender_key, &signature_hash)?);
+        }
+        // A placeholder proof from `test-mocks` would fail this check
+        #[cfg(not(feature = "test-mocks"))]
+        for receipt in self.receipts.iter() {
+            receipt.verify_proof(&self.sapling)?;
+        }
+
+        Ok(Transaction {
+            sapling: self.sapling.clone(),
+            version: version_for(self.expiration_sequence),
+            transaction_fee: self.transaction_fee,
+            expiration_sequence: self.expiration_sequence,
+            spends,
+            receipts: self.receipts.clone(),
+            binding_signature,
+        })
+    }
+
+    /// The hash the spends and the binding signature sign. It is the same
+    /// as that of the signed transaction.
+    pub fn transaction_signature_hash(&self) -> [u8; 32] {
+        let mut hasher = Blake2b::new()
+            .hash_length(32)
+            .personal(SIGNATURE_HASH_PERSONALIZATION)
+            .to_state();
+        write_signature_header(&mut hasher, self.transaction_fee, self.expiration_sequence);
+        for spend in self.spends.iter() {
+            spend.serialize_signature_fields(&mut hasher).unwrap();
+        }
+        for receipt in self.receipts.iter() {
+            receipt.serialize_signature_fields(&mut hasher).unwrap();
+        }
+
+        let mut hash_result = [0; 32];
+        hash_result[..].clone_from_slice(&hasher.finalize().as_ref()[..]);
+        hash_result
+    }
+
+    /// The fee the transaction pays the miner.
+    pub fn transaction_fee(&self) -> i64 {
+        self.transaction_fee
+    }
+
+    /// Sum of the spend value commitments minus the sum of the receipt value
+    /// commitments.
+    fn binding_verification_key(&self) -> edwards::Point<J, Unknown> {
+        let mut binding_verification_key = edwards::Point::zero();
+        for spend in self.spends.iter() {
+            binding_verification_key =
+                binding_verification_key.add(&spend.value_commitment, &self.sapling.jubjub);
+        }
+        for receipt in self.receipts.iter() {
+            binding_verification_key = binding_verification_key.add(
+                &receipt.merkle_note.value_commitment.negate(),
+                &self.sapling.jubjub,
+            );
+        }
+        binding_verification_key
+    }
+}
+
+/// Read an unsigned transaction written by
+/// `ProposedTransaction::write_unsigned` and sign it with `spender_key`. The
+/// bytes must hold exactly one unsigned transaction.
+pub fn sign_offline<J: JubjubEngine + pairing::MultiMillerLoop>(
+    sapling: Arc<Sapling<J>>,
+    mut bytes: &[u8],
+    spender_key: &SaplingKey<J>,
+) -> Result<Transaction<J>, TransactionError> {
+    let unsigned = UnsignedTransaction::read(sapling, &mut bytes)?;
+    if !bytes.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected bytes after the unsigned transaction",
+        )
+        .into());
+    }
+    unsigned.sign(spender_key)
+}
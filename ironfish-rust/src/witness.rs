@@ -2,12 +2,22 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use super::{MerkleNoteHash, Sapling};
+use super::{serializing::read_scalar, MerkleNoteHash, Sapling};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::PrimeField;
+use std::convert::TryFrom;
 use std::fmt::{self, Debug};
+use std::io;
 use std::sync::Arc;
 
 use zcash_primitives::jubjub::JubjubEngine;
 
+/// Depth of the note commitment tree, and so the length of the
+/// authentication path of every witness used to spend a note. It is fixed
+/// by the spend circuit the SAPLING parameters were generated for, so
+/// `Witness` and `verify` work at any depth, but spending needs this one.
+pub const NOTE_TREE_DEPTH: usize = zcash_proofs::circuit::sapling::TREE_DEPTH;
+
 /// Witness to a specific node in an authentication path.
 ///
 /// The Left/Right is the Hash of THIS node, but the MerkleHash at node.0 is
@@ -43,6 +53,112 @@ pub struct Witness<J: JubjubEngine + pairing::MultiMillerLoop> {
     pub auth_path: Vec<WitnessNode<J::Fr>>,
 }
 
+impl<J: JubjubEngine + pairing::MultiMillerLoop> Witness<J> {
+    /// Load a witness from its compact serialized form:
+    ///
+    /// * root hash (32 bytes)
+    /// * tree size (u32, little endian)
+    /// * number of nodes in the authentication path (u8)
+    /// * for each node, its side (0 for Left, 1 for Right) followed by the
+    ///   hash of its sibling (32 bytes)
+    pub fn read<R: io::Read>(hasher: Arc<Sapling<J>>, mut reader: R) -> io::Result<Self> {
+        let root_hash = read_hash::<J, _>(&mut reader)?;
+        let tree_size = reader.read_u32::<LittleEndian>()? as usize;
+        let path_length = reader.read_u8()?;
+        let mut auth_path = Vec::with_capacity(path_length as usize);
+        for _ in 0..path_length {
+            let side = reader.read_u8()?;
+            let hash = read_hash::<J, _>(&mut reader)?;
+            auth_path.push(match side {
+                0 => WitnessNode::Left(hash),
+                1 => WitnessNode::Right(hash),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Invalid witness node side",
+                    ))
+                }
+            });
+        }
+        Ok(Witness {
+            hasher,
+            tree_size,
+            root_hash,
+            auth_path,
+        })
+    }
+
+    /// Build the witness of the leaf at `leaf_position` from the hashes of its
+    /// siblings, ordered from the leaf up to the root.
+    ///
+    /// The side of each node follows from the bits of the position, lowest
+    /// bit first: a 0 bit means the node is a left child. The tree size is
+    /// taken to be the smallest one holding the leaf. Fails if the position
+    /// doesn't fit in a tree with that many levels.
+    pub fn from_auth_path(
+        hasher: Arc<Sapling<J>>,
+        root_hash: J::Fr,
+        leaf_position: u64,
+        siblings: Vec<J::Fr>,
+    ) -> io::Result<Self> {
+        let fits = siblings.len() >= 64 || leaf_position >> siblings.len() == 0;
+        let tree_size = leaf_position
+            .checked_add(1)
+            .filter(|size| fits && *size <= u32::MAX as u64)
+            .map(|size| size as usize)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Leaf position is outside the tree",
+                )
+            })?;
+        let auth_path = siblings
+            .into_iter()
+            .enumerate()
+            .map(|(depth, hash)| {
+                if (leaf_position >> depth) & 1 == 0 {
+                    WitnessNode::Left(hash)
+                } else {
+                    WitnessNode::Right(hash)
+                }
+            })
+            .collect();
+        Ok(Witness {
+            hasher,
+            tree_size,
+            root_hash,
+            auth_path,
+        })
+    }
+
+    /// Store the witness in the compact form read by `Witness::read`.
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "Witness is too large");
+        let tree_size = u32::try_from(self.tree_size).map_err(|_| too_large())?;
+        let path_length = u8::try_from(self.auth_path.len()).map_err(|_| too_large())?;
+
+        writer.write_all(self.root_hash.to_repr().as_ref())?;
+        writer.write_u32::<LittleEndian>(tree_size)?;
+        writer.write_u8(path_length)?;
+        for node in self.auth_path.iter() {
+            let (side, hash) = match node {
+                WitnessNode::Left(hash) => (0, hash),
+                WitnessNode::Right(hash) => (1, hash),
+            };
+            writer.write_u8(side)?;
+            writer.write_all(hash.to_repr().as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+fn read_hash<J: JubjubEngine + pairing::MultiMillerLoop, R: io::Read>(
+    reader: R,
+) -> io::Result<J::Fr> {
+    read_scalar(reader)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Unable to convert hash"))
+}
+
 /// Implement partial equality, ignoring the Sapling Arc
 impl<J: JubjubEngine + pairing::MultiMillerLoop> PartialEq for Witness<J> {
     fn eq(&self, other: &Witness<J>) -> bool {
@@ -97,3 +213,95 @@ impl<J: JubjubEngine + pairing::MultiMillerLoop> fmt::Debug for Witness<J> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Witness, WitnessNode, WitnessTrait};
+    use crate::{
+        keys::SaplingKey,
+        note::{Memo, Note},
+        sapling_bls12,
+        test_util::make_fake_witness,
+        MerkleNoteHash,
+    };
+    use pairing::bls12_381::Bls12;
+
+    #[test]
+    fn test_witness_round_trip() {
+        let sapling = &*sapling_bls12::SAPLING;
+        let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+        let note = Note::new(
+            sapling.clone(),
+            key.generate_public_address(),
+            42,
+            Memo([0; 32]),
+        );
+        let witness = make_fake_witness(sapling.clone(), &note);
+
+        let mut serialized = vec![];
+        witness.write(&mut serialized).unwrap();
+        assert_eq!(serialized.len(), 32 + 4 + 1 + 33 * witness.auth_path.len());
+
+        let read_back: Witness<Bls12> = Witness::read(sapling.clone(), &serialized[..]).unwrap();
+        assert_eq!(read_back, witness);
+        assert!(read_back.verify(&MerkleNoteHash::new(note.commitment_point())));
+
+        serialized[37] = 2;
+        assert!(Witness::<Bls12>::read(sapling.clone(), &serialized[..]).is_err());
+        assert!(Witness::<Bls12>::read(sapling.clone(), &serialized[..40]).is_err());
+    }
+
+    #[test]
+    fn test_witness_from_auth_path() {
+        let sapling = &*sapling_bls12::SAPLING;
+        let key: SaplingKey<Bls12> = SaplingKey::generate_key(sapling.clone());
+        let note = Note::new(
+            sapling.clone(),
+            key.generate_public_address(),
+            42,
+            Memo([0; 32]),
+        );
+        let witness = make_fake_witness(sapling.clone(), &note);
+
+        // Recover the position from the sides of the fake witness
+        let mut leaf_position = 0;
+        let mut siblings = vec![];
+        for (depth, node) in witness.auth_path.iter().enumerate() {
+            siblings.push(match node {
+                WitnessNode::Left(hash) => *hash,
+                WitnessNode::Right(hash) => {
+                    leaf_position |= 1 << depth;
+                    *hash
+                }
+            });
+        }
+
+        let rebuilt: Witness<Bls12> = Witness::from_auth_path(
+            sapling.clone(),
+            witness.root_hash,
+            leaf_position,
+            siblings.clone(),
+        )
+        .unwrap();
+        assert_eq!(rebuilt.auth_path, witness.auth_path);
+        assert_eq!(rebuilt.tree_size as u64, leaf_position + 1);
+        assert!(rebuilt.verify(&MerkleNoteHash::new(note.commitment_point())));
+
+        let wrong_position: Witness<Bls12> = Witness::from_auth_path(
+            sapling.clone(),
+            witness.root_hash,
+            leaf_position ^ 1,
+            siblings.clone(),
+        )
+        .unwrap();
+        assert!(!wrong_position.verify(&MerkleNoteHash::new(note.commitment_point())));
+
+        assert!(Witness::<Bls12>::from_auth_path(
+            sapling.clone(),
+            witness.root_hash,
+            4,
+            siblings[..2].to_vec()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,51 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Compares `verifyTransactions` with `verifyTransactionsParallel` on a
+//! block of sample transactions. Run natively the thread pool is rayon's
+//! default one, which shows the speedup to expect from a worker pool of the
+//! same size.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use ironfish_wasm::wasm_structs::{
+    sample_posted_transaction, verify_transactions, verify_transactions_parallel,
+};
+
+const BLOCK_SIZE: usize = 32;
+
+/// Concatenated transactions and their lengths, as passed to both
+/// verifiers.
+fn make_block() -> (Vec<u8>, Vec<u32>) {
+    let mut batch = vec![];
+    let mut lengths = vec![];
+    for _ in 0..BLOCK_SIZE {
+        let bytes = sample_posted_transaction(1, 2).serialize().unwrap();
+        lengths.push(bytes.len() as u32);
+        batch.extend_from_slice(&bytes);
+    }
+    (batch, lengths)
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let (batch, lengths) = make_block();
+    let all_valid = vec![0xff; BLOCK_SIZE / 8];
+
+    let mut group = c.benchmark_group("verify block of 32 transactions");
+    group.sample_size(10);
+    group.bench_function("verifyTransactions", |b| {
+        b.iter(|| assert_eq!(verify_transactions(&batch, &lengths).unwrap(), all_valid))
+    });
+    group.bench_function("verifyTransactionsParallel", |b| {
+        b.iter(|| {
+            assert_eq!(
+                verify_transactions_parallel(&batch, &lengths).unwrap(),
+                all_valid
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify);
+criterion_main!(benches);
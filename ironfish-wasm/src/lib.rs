@@ -12,6 +12,7 @@ pub mod wasm_structs;
 
 use std::str;
 use wasm_bindgen::prelude::*;
+use zeroize::Zeroize;
 
 #[wasm_bindgen]
 pub struct Key {
@@ -42,6 +43,52 @@ impl Key {
     pub fn public_address(&self) -> String {
         self.public_address.clone()
     }
+
+    /// Basic sanity checks on the spending key, meant to catch keys that were
+    /// obviously not generated from a good source of randomness (all zeros,
+    /// a repeating pattern, a well known test value). Returns a description
+    /// of the problem, or undefined if none of the checks tripped.
+    ///
+    /// Passing these checks says nothing about whether a key is secure.
+    #[wasm_bindgen(js_name = "entropyWarning")]
+    pub fn entropy_warning(&self) -> Option<String> {
+        let hasher = sapling_bls12::SAPLING.clone();
+        let sapling_key = sapling_bls12::Key::from_hex(hasher, &self.spending_key).ok()?;
+        spending_key_entropy_warning(&sapling_key.spending_key())
+    }
+
+    /// Short, stable identifier for this account, derived from its view keys.
+    /// Watch-only accounts get the same value from
+    /// `accountFingerprintFromViewKeys`.
+    #[wasm_bindgen(js_name = "accountFingerprint")]
+    pub fn account_fingerprint(&self) -> Vec<u8> {
+        view_keys_fingerprint(&self.incoming_view_key, &self.outgoing_view_key).unwrap()
+    }
+
+    /// Incoming view key of the subaccount at `account_index`, as hex.
+    ///
+    /// Each subaccount has its own view key and addresses, so deposits to
+    /// different subaccounts can be told apart, but every subaccount key can
+    /// be recreated from this account's spending key.
+    #[wasm_bindgen(js_name = "subAccountViewKey")]
+    pub fn sub_account_view_key(&self, account_index: u32) -> Result<String, JsValue> {
+        Ok(sub_account_view_key(&self.spending_key, account_index)?)
+    }
+}
+
+impl Key {
+    /// Overwrite the secret key strings so they don't linger in linear memory.
+    fn wipe(&mut self) {
+        self.spending_key.zeroize();
+        self.incoming_view_key.zeroize();
+        self.outgoing_view_key.zeroize();
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        self.wipe();
+    }
 }
 
 #[wasm_bindgen(js_name = "generateKey")]
@@ -72,6 +119,83 @@ pub fn create_new_public_key_to_js(private_key: &str) -> Key {
     }
 }
 
+/// Compute the account fingerprint from the hex encoded view keys of an
+/// account. Matches `Key.accountFingerprint` for the full account.
+#[wasm_bindgen(js_name = "accountFingerprintFromViewKeys")]
+pub fn account_fingerprint_from_view_keys(
+    incoming_view_key: &str,
+    outgoing_view_key: &str,
+) -> Result<Vec<u8>, JsValue> {
+    Ok(view_keys_fingerprint(incoming_view_key, outgoing_view_key)?)
+}
+
+/// Smallest number of distinct byte values we expect in a random 32 byte
+/// key. A uniformly random key has fewer than this with negligible
+/// probability.
+const MIN_DISTINCT_KEY_BYTES: usize = 12;
+
+fn spending_key_entropy_warning(spending_key: &[u8; 32]) -> Option<String> {
+    if spending_key.iter().all(|b| *b == 0) {
+        return Some("Spending key is all zeros".into());
+    }
+
+    let sequential = spending_key
+        .windows(2)
+        .all(|w| w[1] == w[0].wrapping_add(1) || w[1] == w[0].wrapping_sub(1));
+    if sequential {
+        return Some("Spending key is a sequence of consecutive bytes".into());
+    }
+
+    for period in 1..=spending_key.len() / 2 {
+        if spending_key
+            .iter()
+            .zip(spending_key[period..].iter())
+            .all(|(a, b)| a == b)
+        {
+            return Some(format!(
+                "Spending key repeats a pattern of {} bytes",
+                period
+            ));
+        }
+    }
+
+    let mut seen = [false; 256];
+    for b in spending_key.iter() {
+        seen[*b as usize] = true;
+    }
+    let distinct = seen.iter().filter(|s| **s).count();
+    if distinct < MIN_DISTINCT_KEY_BYTES {
+        return Some(format!(
+            "Spending key only uses {} distinct byte values",
+            distinct
+        ));
+    }
+
+    None
+}
+
+fn sub_account_view_key(
+    spending_key: &str,
+    account_index: u32,
+) -> Result<String, wasm_structs::WasmSaplingError> {
+    let hasher = sapling_bls12::SAPLING.clone();
+    let sapling_key = sapling_bls12::Key::from_hex(hasher, spending_key)?;
+    let child_key = sapling_key.derive_child(account_index)?;
+    Ok(child_key.incoming_view_key().hex_key())
+}
+
+fn view_keys_fingerprint(
+    incoming_view_key: &str,
+    outgoing_view_key: &str,
+) -> Result<Vec<u8>, wasm_structs::WasmSaplingError> {
+    let hasher = sapling_bls12::SAPLING.clone();
+    let view_keys = sapling_bls12::ViewKeys {
+        incoming: sapling_bls12::IncomingViewKey::from_hex(hasher.clone(), incoming_view_key)?,
+        outgoing: sapling_bls12::OutgoingViewKey::from_hex(hasher, outgoing_view_key)?,
+    };
+    Ok(view_keys.fingerprint().to_vec())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -87,4 +211,69 @@ mod tests {
 
         assert_ne!(key1.public_address(), key2.public_address());
     }
+
+    #[test]
+    fn test_account_fingerprint() {
+        let key = create_key_to_js();
+        let other_key = create_key_to_js();
+
+        let fingerprint = key.account_fingerprint();
+        assert_eq!(
+            view_keys_fingerprint(&key.incoming_view_key(), &key.outgoing_view_key()).unwrap(),
+            fingerprint
+        );
+        assert_ne!(other_key.account_fingerprint(), fingerprint);
+        assert!(view_keys_fingerprint("invalid", &key.outgoing_view_key()).is_err());
+    }
+
+    #[test]
+    fn test_sub_account_view_key() {
+        let key = create_key_to_js();
+
+        let view_key = key.sub_account_view_key(3).unwrap();
+        assert_eq!(view_key.len(), 64);
+        assert_eq!(key.sub_account_view_key(3).unwrap(), view_key);
+        assert_ne!(key.sub_account_view_key(4).unwrap(), view_key);
+        assert_ne!(key.incoming_view_key(), view_key);
+        assert!(sub_account_view_key("invalid", 3).is_err());
+    }
+
+    #[test]
+    fn test_spending_key_entropy_warning() {
+        assert!(spending_key_entropy_warning(&[0; 32]).is_some());
+        assert!(spending_key_entropy_warning(&[0xff; 32]).is_some());
+
+        let mut sequential = [0; 32];
+        for (i, b) in sequential.iter_mut().enumerate() {
+            *b = i as u8 + 1;
+        }
+        assert!(spending_key_entropy_warning(&sequential).is_some());
+
+        let mut repeating = [0; 32];
+        for (i, b) in repeating.iter_mut().enumerate() {
+            *b = [0xde, 0xad, 0xbe, 0xef][i % 4];
+        }
+        assert!(spending_key_entropy_warning(&repeating).is_some());
+
+        let mut few_values = [0; 32];
+        for (i, b) in few_values.iter_mut().enumerate() {
+            *b = ((i * 7) % 5) as u8;
+        }
+        assert!(spending_key_entropy_warning(&few_values).is_some());
+
+        let key = create_key_to_js();
+        assert_eq!(key.entropy_warning(), None);
+    }
+
+    #[test]
+    fn test_key_wipe() {
+        let mut key = create_key_to_js();
+        assert!(!key.spending_key().is_empty());
+
+        key.wipe();
+        assert!(key.spending_key.is_empty());
+        assert!(key.incoming_view_key.is_empty());
+        assert!(key.outgoing_view_key.is_empty());
+        assert!(!key.public_address.is_empty());
+    }
 }
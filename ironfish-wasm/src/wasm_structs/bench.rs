@@ -0,0 +1,131 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Fixed transactions for benchmarking verification across crate versions.
+//! Only compiled with the `bench` feature, and for tests, which use them as
+//! fixtures.
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::sapling_bls12::{
+    Bls12, Fr, Key, MerkleNoteHash, Note, ProposedTransaction, PublicAddress, SAPLING,
+};
+use ironfish_rust::witness::{Witness, WitnessNode, NOTE_TREE_DEPTH};
+
+use super::WasmTransactionPosted;
+
+const SAMPLE_SPENDING_KEY: [u8; 32] = [42; 32];
+
+const SAMPLE_TRANSACTION_SEED: [u8; 32] = [7; 32];
+
+/// Build and post a transaction with the given number of spends and
+/// outputs, for benchmarking.
+///
+/// The keys, notes, witnesses and values are all derived from fixed seeds,
+/// and the transaction draws its proofs, value commitment randomness and
+/// ephemeral keys from a fixed seed too, so repeated calls with the same
+/// shape produce the same bytes.
+///
+/// A transaction without spends can only be posted as a miners fee, which
+/// must have exactly one output.
+#[wasm_bindgen(js_name = "samplePostedTransaction")]
+pub fn sample_posted_transaction(spends: usize, outputs: usize) -> WasmTransactionPosted {
+    let sapling = SAPLING.clone();
+    let key = Key::new(sapling.clone(), SAMPLE_SPENDING_KEY).unwrap();
+    let address = sample_address(&key);
+
+    let mut transaction = ProposedTransaction::with_seed(sapling.clone(), SAMPLE_TRANSACTION_SEED);
+    // Make every spend large enough to cover all outputs on its own, so any
+    // shape balances with a non-negative fee.
+    let spend_value = outputs as u64 + 1;
+    for i in 0..spends {
+        let note = sample_note(&address, spend_value, i);
+        let witness = sample_witness(&note, i);
+        transaction
+            .spend(key.clone(), &note, &witness)
+            .expect("sample spend should be valid");
+    }
+    for i in 0..outputs {
+        let note = sample_note(&address, 1, spends + i);
+        transaction
+            .receive(&key, &note)
+            .expect("sample output should be valid");
+    }
+
+    let transaction = if spends == 0 {
+        transaction.post_miners_fee()
+    } else {
+        let fee = spends as u64 * spend_value - outputs as u64;
+        transaction.post(&key, None, fee)
+    }
+    .expect("sample transaction should post");
+
+    WasmTransactionPosted { transaction }
+}
+
+/// First valid public address of the key, in diversifier order.
+fn sample_address(key: &Key) -> PublicAddress {
+    let mut diversifier = [0; 11];
+    loop {
+        if let Ok(address) = key.public_address(&diversifier) {
+            return address;
+        }
+        diversifier[0] += 1;
+    }
+}
+
+/// Note with randomness derived from its index, so its commitment is fixed.
+fn sample_note(owner: &PublicAddress, value: u64, index: usize) -> Note {
+    let mut bytes = vec![];
+    owner.write(&mut bytes).unwrap();
+    bytes.extend_from_slice(&value.to_le_bytes());
+    let mut randomness = [0; 32];
+    randomness[..8].copy_from_slice(&(index as u64 + 1).to_le_bytes());
+    bytes.extend_from_slice(&randomness);
+    bytes.extend_from_slice(&[0; 32]);
+    Note::read(&bytes[..], SAPLING.clone()).unwrap()
+}
+
+/// Witness placing the note in a tree with fixed sibling hashes.
+fn sample_witness(note: &Note, index: usize) -> Witness<Bls12> {
+    let mut cursor: std::io::Cursor<[u8; 32]> = std::io::Cursor::new(note.commitment());
+    let mut current = MerkleNoteHash::read(&mut cursor).unwrap().0;
+
+    let mut auth_path = Vec::with_capacity(NOTE_TREE_DEPTH);
+    for depth in 0..NOTE_TREE_DEPTH {
+        let sibling = Fr::from((index * NOTE_TREE_DEPTH + depth) as u64);
+        current = MerkleNoteHash::combine_hash(&SAPLING, depth, &current, &sibling);
+        auth_path.push(WitnessNode::Left(sibling));
+    }
+
+    Witness {
+        hasher: SAPLING.clone(),
+        tree_size: index + 1,
+        root_hash: current,
+        auth_path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::sample_posted_transaction;
+
+    #[test]
+    fn test_sample_posted_transaction() {
+        let transaction = sample_posted_transaction(2, 3);
+        assert_eq!(transaction.spends_length(), 2);
+        assert_eq!(transaction.notes_length(), 3);
+        assert_eq!(transaction.transaction_fee(), 5);
+        assert!(transaction.verify());
+
+        let miners_fee = sample_posted_transaction(0, 1);
+        assert_eq!(miners_fee.transaction_fee(), -1);
+        assert!(miners_fee.verify());
+
+        assert_eq!(
+            sample_posted_transaction(2, 3).to_bytes().unwrap(),
+            transaction.to_bytes().unwrap()
+        );
+    }
+}
@@ -0,0 +1,119 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::io;
+
+/// Writer that splits its output into chunks of at most `chunk_size`
+/// bytes, so large values can be serialized without one big allocation.
+pub(crate) struct ChunkWriter {
+    chunk_size: usize,
+    chunks: Vec<Vec<u8>>,
+}
+
+impl ChunkWriter {
+    /// `chunk_size` must be greater than zero.
+    pub(crate) fn new(chunk_size: usize) -> ChunkWriter {
+        assert!(chunk_size > 0);
+        ChunkWriter {
+            chunk_size,
+            chunks: vec![],
+        }
+    }
+
+    pub(crate) fn into_chunks(self) -> Vec<Vec<u8>> {
+        self.chunks
+    }
+}
+
+impl io::Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let chunk_size = self.chunk_size;
+        let needs_chunk = match self.chunks.last() {
+            Some(chunk) => chunk.len() == chunk_size,
+            None => true,
+        };
+        if needs_chunk {
+            self.chunks.push(Vec::with_capacity(chunk_size));
+        }
+
+        let chunk = self.chunks.last_mut().unwrap();
+        let written = std::cmp::min(chunk_size - chunk.len(), buf.len());
+        chunk.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reader over a list of chunks, as produced by `ChunkWriter`. The chunks
+/// don't need to be the same size.
+pub(crate) struct ChunkReader {
+    chunks: Vec<Vec<u8>>,
+    chunk: usize,
+    offset: usize,
+}
+
+impl ChunkReader {
+    pub(crate) fn new(chunks: Vec<Vec<u8>>) -> ChunkReader {
+        ChunkReader {
+            chunks,
+            chunk: 0,
+            offset: 0,
+        }
+    }
+}
+
+impl io::Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk < self.chunks.len() {
+            let chunk = &self.chunks[self.chunk];
+            if self.offset < chunk.len() {
+                let read = std::cmp::min(chunk.len() - self.offset, buf.len());
+                buf[..read].copy_from_slice(&chunk[self.offset..self.offset + read]);
+                self.offset += read;
+                return Ok(read);
+            }
+            self.chunk += 1;
+            self.offset = 0;
+        }
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkReader, ChunkWriter};
+    use std::io::{Read, Write};
+
+    #[test]
+    fn test_chunk_round_trip() {
+        let data: Vec<u8> = (0..100).collect();
+
+        let mut writer = ChunkWriter::new(30);
+        writer.write_all(&data[..10]).unwrap();
+        writer.write_all(&data[10..]).unwrap();
+        let chunks = writer.into_chunks();
+        assert_eq!(
+            chunks.iter().map(|c| c.len()).collect::<Vec<_>>(),
+            vec![30, 30, 30, 10]
+        );
+
+        let mut reader = ChunkReader::new(chunks);
+        let mut read_back = vec![];
+        reader.read_to_end(&mut read_back).unwrap();
+        assert_eq!(read_back, data);
+    }
+
+    #[test]
+    fn test_chunk_reader_uneven_chunks() {
+        let chunks = vec![vec![1, 2], vec![], vec![3], vec![4, 5, 6]];
+        let mut reader = ChunkReader::new(chunks);
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [1, 2, 3, 4, 5]);
+        assert!(reader.read_exact(&mut buf).is_err());
+    }
+}
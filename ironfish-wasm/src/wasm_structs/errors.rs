@@ -0,0 +1,184 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use ironfish_rust::errors::{
+    NoteError, SaplingKeyError, SaplingProofError, SelectionError, TransactionError,
+    VerificationError,
+};
+use wasm_bindgen::prelude::*;
+
+/// Error surfaced to javascript when building a transaction fails.
+///
+/// `code` is a stable identifier (usually the name of the underlying rust
+/// error variant) that callers can match on; `message` is a human readable
+/// description that may include extra context such as the index of the
+/// failing item.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct WasmSaplingError {
+    code: String,
+    message: String,
+}
+
+#[wasm_bindgen]
+impl WasmSaplingError {
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> String {
+        self.code.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl WasmSaplingError {
+    pub(crate) fn new(code: &str, message: String) -> WasmSaplingError {
+        WasmSaplingError {
+            code: code.into(),
+            message,
+        }
+    }
+
+    /// Prefix the message with the index of the item that caused the error.
+    pub(crate) fn at_index(self, index: usize) -> WasmSaplingError {
+        WasmSaplingError {
+            message: format!("Item at index {}: {}", index, self.message),
+            code: self.code,
+        }
+    }
+}
+
+impl From<SaplingProofError> for WasmSaplingError {
+    fn from(e: SaplingProofError) -> WasmSaplingError {
+        match e {
+            SaplingProofError::SpendCircuitProofError(d) => WasmSaplingError::new(
+                "SpendCircuitProofError",
+                format!("SpendCircuitProofError - {}", d),
+            ),
+            SaplingProofError::ReceiptCircuitProofError => WasmSaplingError::new(
+                "ReceiptCircuitProofError",
+                "ReceiptCircuitProofError".into(),
+            ),
+            SaplingProofError::SaplingKeyError => {
+                WasmSaplingError::new("SaplingKeyError", "SaplingKeyError".into())
+            }
+            SaplingProofError::IOError => WasmSaplingError::new("IOError", "IOError".into()),
+            SaplingProofError::SigningError => {
+                WasmSaplingError::new("SigningError", "SigningError".into())
+            }
+            SaplingProofError::VerificationFailed => {
+                WasmSaplingError::new("VerificationFailed", "VerificationFailed".into())
+            }
+            SaplingProofError::InconsistentWitness => {
+                WasmSaplingError::new("InconsistentWitness", "InconsistentWitness".into())
+            }
+            SaplingProofError::DuplicateNullifier => {
+                WasmSaplingError::new("DuplicateNullifier", "DuplicateNullifier".into())
+            }
+            SaplingProofError::ZeroValueSpend => {
+                WasmSaplingError::new("ZeroValueSpend", "ZeroValueSpend".into())
+            }
+        }
+    }
+}
+
+impl From<SaplingKeyError> for WasmSaplingError {
+    fn from(e: SaplingKeyError) -> WasmSaplingError {
+        WasmSaplingError::new("SaplingKeyError", format!("SaplingKeyError - {:?}", e))
+    }
+}
+
+impl From<TransactionError> for WasmSaplingError {
+    fn from(e: TransactionError) -> WasmSaplingError {
+        let code = match e {
+            TransactionError::InvalidBalanceError => "InvalidBalanceError",
+            TransactionError::IllegalValueError => "IllegalValueError",
+            TransactionError::SigningError => "SigningError",
+            TransactionError::ProvingError => "ProvingError",
+            TransactionError::IoError(_) => "IOError",
+            TransactionError::VerificationFailed => "VerificationFailed",
+            TransactionError::ExpiredError => "ExpiredError",
+            TransactionError::DuplicateNullifier => "DuplicateNullifier",
+            TransactionError::UnsupportedVersion(_) => "UnsupportedVersion",
+        };
+        WasmSaplingError::new(code, format!("{:?}", e))
+    }
+}
+
+impl From<VerificationError> for WasmSaplingError {
+    fn from(e: VerificationError) -> WasmSaplingError {
+        let (code, index) = match e {
+            VerificationError::SpendProofInvalid(index) => ("SpendProofInvalid", Some(index)),
+            VerificationError::SpendSignatureInvalid(index) => {
+                ("SpendSignatureInvalid", Some(index))
+            }
+            VerificationError::ReceiptProofInvalid(index) => ("ReceiptProofInvalid", Some(index)),
+            VerificationError::DuplicateNullifier => ("DuplicateNullifier", None),
+            VerificationError::BindingSignatureInvalid => ("BindingSignatureInvalid", None),
+            VerificationError::FeeMismatch => ("FeeMismatch", None),
+        };
+        let error = WasmSaplingError::new(code, code.into());
+        match index {
+            Some(index) => error.at_index(index),
+            None => error,
+        }
+    }
+}
+
+impl From<SelectionError> for WasmSaplingError {
+    fn from(e: SelectionError) -> WasmSaplingError {
+        let code = match e {
+            SelectionError::InsufficientFunds => "InsufficientFunds",
+            SelectionError::OutputTooLarge(_) => "OutputTooLarge",
+        };
+        WasmSaplingError::new(code, format!("{:?}", e))
+    }
+}
+
+impl From<NoteError> for WasmSaplingError {
+    fn from(e: NoteError) -> WasmSaplingError {
+        WasmSaplingError::new("NoteError", format!("NoteError - {:?}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmSaplingError;
+    use ironfish_rust::errors::SaplingProofError;
+
+    #[test]
+    fn test_sapling_proof_error_codes() {
+        // javascript matches on these codes, so they must not change
+        let errors = vec![
+            (
+                SaplingProofError::SpendCircuitProofError("bad witness".into()),
+                "SpendCircuitProofError",
+            ),
+            (
+                SaplingProofError::ReceiptCircuitProofError,
+                "ReceiptCircuitProofError",
+            ),
+            (SaplingProofError::SaplingKeyError, "SaplingKeyError"),
+            (SaplingProofError::IOError, "IOError"),
+            (SaplingProofError::SigningError, "SigningError"),
+            (SaplingProofError::VerificationFailed, "VerificationFailed"),
+            (
+                SaplingProofError::InconsistentWitness,
+                "InconsistentWitness",
+            ),
+            (SaplingProofError::DuplicateNullifier, "DuplicateNullifier"),
+            (SaplingProofError::ZeroValueSpend, "ZeroValueSpend"),
+        ];
+        for (error, code) in errors {
+            assert_eq!(WasmSaplingError::from(error).code(), code);
+        }
+
+        let error = WasmSaplingError::from(SaplingProofError::SpendCircuitProofError(
+            "bad witness".into(),
+        ));
+        assert_eq!(error.message(), "SpendCircuitProofError - bad witness");
+    }
+}
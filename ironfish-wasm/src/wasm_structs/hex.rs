@@ -0,0 +1,74 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use wasm_bindgen::prelude::*;
+
+use super::errors::WasmSaplingError;
+
+/// Encode bytes as lowercase hex without a prefix, the form every hex
+/// string this module returns takes.
+#[wasm_bindgen(js_name = "bytesToHex")]
+pub fn bytes_to_hex(bytes: &[u8]) -> String {
+    to_hex(bytes)
+}
+
+/// Decode a hex string, in either case and without a prefix. Throws an
+/// error with code `InvalidHex` if it has an odd length or a character that
+/// isn't a hex digit.
+#[wasm_bindgen(js_name = "hexToBytes")]
+pub fn hex_to_bytes(hex: &str) -> Result<Vec<u8>, JsValue> {
+    Ok(from_hex(hex)?)
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn from_hex(hex: &str) -> Result<Vec<u8>, WasmSaplingError> {
+    if hex.len() % 2 != 0 {
+        return Err(WasmSaplingError::new(
+            "InvalidHex",
+            format!("Expected an even number of digits, got {}", hex.len()),
+        ));
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .enumerate()
+        .map(|(index, pair)| match (digit(pair[0]), digit(pair[1])) {
+            (Some(high), Some(low)) => Ok(high << 4 | low),
+            _ => Err(WasmSaplingError::new(
+                "InvalidHex",
+                format!("Invalid hex digit at byte {}", index),
+            )),
+        })
+        .collect()
+}
+
+fn digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_hex, to_hex};
+
+    #[test]
+    fn test_hex_round_trip() {
+        let bytes = [0x00, 0x0f, 0xa5, 0xff];
+        assert_eq!(to_hex(&bytes), "000fa5ff");
+        assert_eq!(from_hex("000fa5ff").unwrap(), bytes);
+        assert_eq!(from_hex("000FA5FF").unwrap(), bytes);
+        assert_eq!(to_hex(&[]), "");
+        assert_eq!(from_hex("").unwrap(), Vec::<u8>::new());
+
+        assert_eq!(from_hex("abc").unwrap_err().code(), "InvalidHex");
+        assert_eq!(from_hex("0g").unwrap_err().code(), "InvalidHex");
+        assert_eq!(from_hex("0x00").unwrap_err().code(), "InvalidHex");
+    }
+}
@@ -0,0 +1,74 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::sapling_bls12::Transaction;
+
+/// Nullifiers spent and note commitments created by a single transaction,
+/// collected in one pass so both sides of an index stay consistent.
+#[wasm_bindgen]
+pub struct WasmIndexEntries {
+    nullifiers: Vec<[u8; 32]>,
+    commitments: Vec<[u8; 32]>,
+}
+
+#[wasm_bindgen]
+impl WasmIndexEntries {
+    /// Nullifiers of the spent notes, as an array of Uint8Arrays in spend
+    /// order.
+    #[wasm_bindgen(getter)]
+    pub fn nullifiers(&self) -> js_sys::Array {
+        to_array(&self.nullifiers)
+    }
+
+    /// Commitments of the created notes, as an array of Uint8Arrays in
+    /// output order.
+    #[wasm_bindgen(getter)]
+    pub fn commitments(&self) -> js_sys::Array {
+        to_array(&self.commitments)
+    }
+}
+
+impl WasmIndexEntries {
+    pub(crate) fn from_transaction(transaction: &Transaction) -> WasmIndexEntries {
+        WasmIndexEntries {
+            nullifiers: transaction
+                .iter_spends()
+                .map(|spend| spend.nullifier())
+                .collect(),
+            commitments: transaction
+                .iter_receipts()
+                .map(|receipt| receipt.merkle_note().note_commitment())
+                .collect(),
+        }
+    }
+}
+
+pub(crate) fn to_array(entries: &[[u8; 32]]) -> js_sys::Array {
+    entries
+        .iter()
+        .map(|entry| JsValue::from(js_sys::Uint8Array::from(&entry[..])))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmIndexEntries;
+    use crate::wasm_structs::transaction::miners_fee_transaction;
+
+    #[test]
+    fn test_index_entries() {
+        let (_, posted) = miners_fee_transaction();
+
+        let entries = WasmIndexEntries::from_transaction(&posted.transaction);
+        assert!(entries.nullifiers.is_empty());
+        assert_eq!(
+            entries.commitments,
+            vec![posted.transaction.receipts()[0]
+                .merkle_note()
+                .note_commitment()]
+        );
+    }
+}
@@ -0,0 +1,263 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::sapling_bls12::{Key, PublicAddress, SAPLING};
+
+use super::errors::WasmSaplingError;
+
+/// Language of the word lists used for mnemonics.
+const MNEMONIC_LANGUAGE: &str = "en";
+
+/// A spending key and everything derived from it.
+///
+/// Unlike the plain `Key` returned by `generateKey`, this keeps the key
+/// itself rather than its hex encodings, so view keys and addresses are
+/// derived on demand.
+#[wasm_bindgen]
+pub struct WasmKey {
+    pub(crate) key: Key,
+    public_address: PublicAddress,
+}
+
+#[wasm_bindgen]
+impl WasmKey {
+    /// Generate a new random spending key.
+    #[wasm_bindgen]
+    pub fn generate() -> WasmKey {
+        console_error_panic_hook::set_once();
+        WasmKey::from_key(Key::generate_key(SAPLING.clone()))
+    }
+
+    /// Load a hex encoded spending key, as returned by `hexSpendingKey`.
+    #[wasm_bindgen(js_name = "fromHex")]
+    pub fn from_hex(spending_key: &str) -> Result<WasmKey, JsValue> {
+        let key = Key::from_hex(SAPLING.clone(), spending_key).map_err(WasmSaplingError::from)?;
+        Ok(WasmKey::from_key(key))
+    }
+
+    /// Load a key from a 24 word English mnemonic. With an empty
+    /// `passphrase` the words encode the spending key itself, as written by
+    /// `toMnemonic`; otherwise the words and passphrase are stretched into
+    /// the key, and each passphrase gives a different key. Throws an error
+    /// with code `SaplingKeyError` if a word is not in the word list or the
+    /// checksum doesn't match.
+    #[wasm_bindgen(js_name = "fromMnemonic")]
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<WasmKey, JsValue> {
+        Ok(WasmKey::from_key(key_from_mnemonic(phrase, passphrase)?))
+    }
+
+    /// The spending key as a 24 word English mnemonic, which loads the same
+    /// key back with `fromMnemonic` and an empty passphrase.
+    #[wasm_bindgen(js_name = "toMnemonic")]
+    pub fn to_mnemonic(&self) -> String {
+        self.key
+            .words_spending_key(MNEMONIC_LANGUAGE)
+            .expect("mnemonic language should be supported")
+    }
+
+    /// Derive the key of the subaccount at `index`. The same index always
+    /// gives the same key, so every subaccount can be recreated from this
+    /// key alone.
+    #[wasm_bindgen(js_name = "deriveChild")]
+    pub fn derive_child(&self, index: u32) -> Result<WasmKey, JsValue> {
+        let child = self
+            .key
+            .derive_child(index)
+            .map_err(WasmSaplingError::from)?;
+        Ok(WasmKey::from_key(child))
+    }
+
+    #[wasm_bindgen(js_name = "hexSpendingKey")]
+    pub fn hex_spending_key(&self) -> String {
+        self.key.hex_spending_key()
+    }
+
+    /// Public address of this key, in the hex encoding accepted by
+    /// `WasmNote` and everything else that takes an address. The address is
+    /// picked once, when the key is created or loaded, and stays the same
+    /// for the lifetime of this object.
+    #[wasm_bindgen(getter, js_name = "publicAddress")]
+    pub fn public_address(&self) -> String {
+        self.public_address.hex_public_address()
+    }
+
+    /// Whether the hex encoded `address` is this key's `publicAddress`,
+    /// compared in constant time. Prefer this over comparing the hex strings
+    /// when checking ownership, for example of a decrypted note. Throws an
+    /// error with code `SaplingKeyError` if `address` is invalid.
+    #[wasm_bindgen(js_name = "publicAddressEquals")]
+    pub fn public_address_equals(&self, address: &str) -> Result<bool, JsValue> {
+        Ok(self.is_public_address(address)?)
+    }
+
+    /// The same public address as `publicAddress`, encoded as a bech32
+    /// string with a checksum, for showing to users.
+    #[wasm_bindgen(getter, js_name = "bech32PublicAddress")]
+    pub fn bech32_public_address(&self) -> String {
+        self.public_address.to_bech32()
+    }
+
+    /// Hex encoded incoming view key, for scanning for notes received by
+    /// this key without being able to spend them.
+    #[wasm_bindgen(getter, js_name = "incomingViewKey")]
+    pub fn incoming_view_key(&self) -> String {
+        self.key.incoming_view_key().hex_key()
+    }
+
+    /// Hex encoded outgoing view key, for decrypting notes sent by this key.
+    #[wasm_bindgen(getter, js_name = "outgoingViewKey")]
+    pub fn outgoing_view_key(&self) -> String {
+        self.key.outgoing_view_key().hex_key()
+    }
+}
+
+/// Whether `address` is a valid hex encoded public address, for validating
+/// an address field as it is typed. Never throws.
+#[wasm_bindgen(js_name = "publicAddressIsValid")]
+pub fn public_address_is_valid(address: &str) -> bool {
+    PublicAddress::is_valid_hex(&SAPLING, address)
+}
+
+/// Convert a bech32 encoded public address to the hex encoding accepted by
+/// `WasmNote` and everything else that takes an address. Throws an error
+/// with code `SaplingKeyError` if the checksum doesn't match, the prefix
+/// isn't `iron` or the address is invalid.
+#[wasm_bindgen(js_name = "bech32AddressToHex")]
+pub fn bech32_address_to_hex(address: &str) -> Result<String, JsValue> {
+    let address =
+        PublicAddress::from_bech32(SAPLING.clone(), address).map_err(WasmSaplingError::from)?;
+    Ok(address.hex_public_address())
+}
+
+/// Derive a public address of the hex encoded spending key, in the hex
+/// encoding accepted by `WasmNote`, without creating a `WasmKey`. Like
+/// the change address picked by `post`, the diversifier is chosen at
+/// random, so each call may return a different address of the same key.
+/// Throws an error with code `SaplingKeyError` if the key is invalid.
+#[wasm_bindgen(js_name = "publicAddressFromSpendingKey")]
+pub fn public_address_from_spending_key(spender_hex_key: &str) -> Result<String, JsValue> {
+    Ok(spender_public_address(spender_hex_key)?)
+}
+
+fn spender_public_address(spender_hex_key: &str) -> Result<String, WasmSaplingError> {
+    let key = Key::from_hex(SAPLING.clone(), spender_hex_key)?;
+    Ok(key.generate_public_address().hex_public_address())
+}
+
+fn key_from_mnemonic(phrase: &str, passphrase: &str) -> Result<Key, WasmSaplingError> {
+    Ok(Key::from_words_with_passphrase(
+        SAPLING.clone(),
+        MNEMONIC_LANGUAGE,
+        phrase.to_string(),
+        passphrase,
+    )?)
+}
+
+impl WasmKey {
+    fn is_public_address(&self, address: &str) -> Result<bool, WasmSaplingError> {
+        let address = PublicAddress::from_hex(SAPLING.clone(), address)?;
+        Ok(self.public_address.ct_eq(&address))
+    }
+
+    fn from_key(key: Key) -> WasmKey {
+        let public_address = key.generate_public_address();
+        WasmKey {
+            key,
+            public_address,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bech32_address_to_hex, key_from_mnemonic, public_address_is_valid, spender_public_address,
+        WasmKey,
+    };
+    use ironfish_rust::sapling_bls12::{Key, PublicAddress, SAPLING};
+
+    #[test]
+    fn test_wasm_key() {
+        let key = WasmKey::generate();
+        let read_back = WasmKey::from_hex(&key.hex_spending_key()).unwrap();
+
+        assert_eq!(read_back.hex_spending_key(), key.hex_spending_key());
+        assert_eq!(read_back.incoming_view_key(), key.incoming_view_key());
+        assert_eq!(read_back.outgoing_view_key(), key.outgoing_view_key());
+        let expected = Key::from_hex(SAPLING.clone(), &key.hex_spending_key())
+            .unwrap()
+            .generate_public_address()
+            .hex_public_address();
+        assert_eq!(read_back.public_address(), expected);
+
+        let address = PublicAddress::from_hex(SAPLING.clone(), &key.public_address()).unwrap();
+        assert_eq!(address.hex_public_address(), key.public_address());
+
+        assert_eq!(
+            bech32_address_to_hex(&key.bech32_public_address()).unwrap(),
+            key.public_address()
+        );
+
+        assert!(key.is_public_address(&key.public_address()).unwrap());
+        assert!(!key
+            .is_public_address(&WasmKey::generate().public_address())
+            .unwrap());
+        assert_eq!(
+            key.is_public_address("invalid").err().unwrap().code(),
+            "SaplingKeyError"
+        );
+
+        assert!(public_address_is_valid(&key.public_address()));
+        assert!(!public_address_is_valid("invalid"));
+        assert!(!public_address_is_valid(&key.bech32_public_address()));
+    }
+
+    #[test]
+    fn test_spender_public_address() {
+        let key = WasmKey::generate();
+        let address = spender_public_address(&key.hex_spending_key()).unwrap();
+        let read_back = PublicAddress::from_hex(SAPLING.clone(), &address).unwrap();
+        assert_eq!(read_back.hex_public_address(), address);
+
+        let err = spender_public_address("invalid").err().unwrap();
+        assert_eq!(err.code(), "SaplingKeyError");
+    }
+
+    #[test]
+    fn test_mnemonic() {
+        let key = WasmKey::generate();
+        let phrase = key.to_mnemonic();
+        assert_eq!(phrase.split(' ').count(), 24);
+
+        let read_back = WasmKey::from_mnemonic(&phrase, "").unwrap();
+        assert_eq!(read_back.hex_spending_key(), key.hex_spending_key());
+
+        let protected = WasmKey::from_mnemonic(&phrase, "secret").unwrap();
+        assert_ne!(protected.hex_spending_key(), key.hex_spending_key());
+
+        let err = key_from_mnemonic(&vec!["abandon"; 24].join(" "), "")
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "SaplingKeyError");
+    }
+
+    #[test]
+    fn test_derive_child() {
+        let key = WasmKey::generate();
+        let child = key.derive_child(1).unwrap();
+
+        assert_eq!(
+            child.hex_spending_key(),
+            key.derive_child(1).unwrap().hex_spending_key()
+        );
+        assert_ne!(
+            child.hex_spending_key(),
+            key.derive_child(2).unwrap().hex_spending_key()
+        );
+        assert_ne!(child.hex_spending_key(), key.hex_spending_key());
+        assert_ne!(child.public_address(), key.public_address());
+    }
+}
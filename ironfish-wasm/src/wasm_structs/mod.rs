@@ -2,19 +2,84 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+#[cfg(any(test, feature = "bench"))]
+mod bench;
+#[cfg(feature = "bench")]
+pub use bench::sample_posted_transaction;
+
+mod chunks;
+
+mod errors;
+pub use errors::WasmSaplingError;
+
+mod hex;
+pub use hex::{bytes_to_hex, hex_to_bytes};
+
+mod index_entries;
+pub use index_entries::WasmIndexEntries;
+
+mod key;
+pub use key::{
+    bech32_address_to_hex, public_address_from_spending_key, public_address_is_valid, WasmKey,
+};
+
 mod note_encrypted;
 pub use note_encrypted::WasmNoteEncrypted;
 
 mod note;
-pub use note::WasmNote;
+pub use note::{
+    canonicalize_memo, merkle_note_is_owned, select_notes_to_spend, verify_signed_receipt, WasmNote,
+};
+
+mod output_filter;
+pub use output_filter::output_filter_contains;
+
+mod post_progress;
+pub use post_progress::WasmPostProgress;
+
+mod receipt_proof;
+pub use receipt_proof::WasmReceiptProof;
+
+mod report;
+pub use report::WasmTransactionReport;
+
+mod root_set;
+pub use root_set::WasmRootSet;
 
 mod spend_proof;
 pub use spend_proof::WasmSpendProof;
 
+#[cfg(feature = "wasm-threads")]
+mod threads;
+#[cfg(all(feature = "wasm-threads", target_arch = "wasm32"))]
+pub use threads::init_thread_pool;
+#[cfg(feature = "wasm-threads")]
+pub use threads::verify_transactions_parallel;
+
+mod transaction_plan;
+pub use transaction_plan::{plan_transactions, WasmTransactionPlan};
+
 mod transaction;
+pub use transaction::deserialize_transactions;
+pub use transaction::sign_offline_transaction;
+pub use transaction::verify_block;
+pub use transaction::verify_block_fee_balance;
+pub use transaction::verify_transactions;
 pub use transaction::WasmSimpleTransaction;
 pub use transaction::WasmTransaction;
 pub use transaction::WasmTransactionPosted;
 
+mod verify_cache;
+pub use verify_cache::{verify_cached, WasmVerifyCache};
+
+mod verify_policy;
+pub use verify_policy::WasmVerifyPolicy;
+
+mod verifying_keys;
+pub use verifying_keys::WasmVerifyingKeys;
+
+mod verify_step;
+pub use verify_step::{WasmVerifyState, WasmVerifyStepResult};
+
 mod witness;
-pub use witness::JsWitness;
+pub use witness::{JsWitness, WasmWitness, WasmWitnessNode};
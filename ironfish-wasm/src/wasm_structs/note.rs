@@ -2,10 +2,16 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use ironfish_rust::note::Memo;
-use ironfish_rust::sapling_bls12::{Key, Note, SAPLING};
+use ironfish_rust::note::{Memo, MEMO_SIZE};
+use ironfish_rust::note_selection::select_notes;
+use ironfish_rust::sapling_bls12::{
+    IncomingViewKey, Key, MerkleNote, Note, OutgoingViewKey, PublicAddress, SAPLING,
+};
+use ironfish_rust::signed_receipt::{self, sign_receipt};
 use wasm_bindgen::prelude::*;
 
+use super::errors::WasmSaplingError;
+
 #[wasm_bindgen]
 pub struct WasmNote {
     pub(crate) note: Note,
@@ -13,12 +19,29 @@ pub struct WasmNote {
 
 #[wasm_bindgen]
 impl WasmNote {
+    /// Create a new note to send `value` to the hex encoded public address
+    /// `owner`, for passing to `WasmTransaction.receive`. The memo is
+    /// zero-padded to 32 bytes, and an error with code `MemoTooLong` is
+    /// thrown if it is longer than that. An error with code
+    /// `SaplingKeyError` is thrown if `owner` is not a valid address.
+    ///
+    /// `value` may be zero, to pad the outputs of a transaction, but such a
+    /// note can't be spent: `WasmTransaction.spend` throws an error with
+    /// code `ZeroValueSpend`.
     #[wasm_bindgen(constructor)]
-    pub fn new(owner: &str, value: u64, memo: &str) -> WasmNote {
-        let owner_address = ironfish_rust::PublicAddress::from_hex(SAPLING.clone(), owner).unwrap();
-        WasmNote {
-            note: Note::new(SAPLING.clone(), owner_address, value, Memo::from(memo)),
-        }
+    pub fn new(owner: &str, value: u64, memo: &str) -> Result<WasmNote, JsValue> {
+        let owner_address =
+            PublicAddress::from_hex(SAPLING.clone(), owner).map_err(WasmSaplingError::from)?;
+        let memo = Memo(canonical_memo(memo.as_bytes())?);
+        Ok(WasmNote {
+            note: Note::new(SAPLING.clone(), owner_address, value, memo),
+        })
+    }
+
+    /// Number of bytes a memo can hold.
+    #[wasm_bindgen(js_name = "memoSize")]
+    pub fn memo_size() -> usize {
+        MEMO_SIZE
     }
 
     #[wasm_bindgen]
@@ -29,6 +52,37 @@ impl WasmNote {
         WasmNote { note }
     }
 
+    /// Trial-decrypt a serialized merkle note, such as one returned by
+    /// `WasmTransactionPosted.getNote`, with the hex encoded incoming view
+    /// key of its owner. Throws an error with code `DecryptionFailed` if the
+    /// note is not addressed to that key.
+    #[wasm_bindgen(js_name = "fromMerkleNote")]
+    pub fn from_merkle_note(
+        merkle_note_bytes: &[u8],
+        incoming_view_key_hex: &str,
+    ) -> Result<WasmNote, JsValue> {
+        let incoming_view_key = IncomingViewKey::from_hex(SAPLING.clone(), incoming_view_key_hex)
+            .map_err(WasmSaplingError::from)?;
+        let note = decrypt_merkle_note(merkle_note_bytes, &incoming_view_key)?;
+        Ok(WasmNote { note })
+    }
+
+    /// Decrypt a serialized merkle note with the hex encoded outgoing view
+    /// key of its sender, to recover the value and memo of a note the wallet
+    /// sent to someone else. Throws an error with code `DecryptionFailed` if
+    /// the note was not created by that key. Miners fee notes are encrypted
+    /// with fixed keys and can't be decrypted this way.
+    #[wasm_bindgen(js_name = "fromMerkleNoteWithOutgoingKey")]
+    pub fn from_merkle_note_with_outgoing_key(
+        merkle_note_bytes: &[u8],
+        outgoing_view_key_hex: &str,
+    ) -> Result<WasmNote, JsValue> {
+        let outgoing_view_key = OutgoingViewKey::from_hex(SAPLING.clone(), outgoing_view_key_hex)
+            .map_err(WasmSaplingError::from)?;
+        let note = decrypt_merkle_note_for_spender(merkle_note_bytes, &outgoing_view_key)?;
+        Ok(WasmNote { note })
+    }
+
     #[wasm_bindgen]
     pub fn serialize(&self) -> Vec<u8> {
         let mut cursor: std::io::Cursor<Vec<u8>> = std::io::Cursor::new(vec![]);
@@ -42,6 +96,27 @@ impl WasmNote {
         self.note.value()
     }
 
+    /// 32 byte commitment of this note: the leaf hash of the note in the
+    /// note commitment tree, the same as `merkleHash` on the encrypted note
+    /// of the output that created it. Hash it up the tree with
+    /// `WasmNoteEncrypted.combineHash` to build the witness for spending it.
+    #[wasm_bindgen]
+    pub fn commitment(&self) -> Vec<u8> {
+        self.note.commitment().to_vec()
+    }
+
+    /// Whether spending this note would cost at least as much as it is
+    /// worth, given the caller's estimate of the fee each extra spend adds
+    /// to a transaction. Such notes add nothing to a transaction's balance,
+    /// so coin selection can skip them.
+    ///
+    /// This only compares the value to `fee_per_spend`; it doesn't account
+    /// for change or for any fixed cost of the transaction itself.
+    #[wasm_bindgen(js_name = "isDust")]
+    pub fn is_dust(&self, fee_per_spend: u64) -> bool {
+        self.note.value() <= fee_per_spend
+    }
+
     /// Arbitrary note the spender can supply when constructing a spend so the
     /// receiver has some record from whence it came.
     /// Note: While this is encrypted with the output, it is not encoded into
@@ -51,14 +126,394 @@ impl WasmNote {
         self.note.memo().to_string()
     }
 
+    /// The memo decoded as UTF-8, without the zero padding. Unlike `memo`,
+    /// this throws an error with code `InvalidMemo` instead of replacing
+    /// bytes that aren't valid UTF-8.
+    #[wasm_bindgen(js_name = "memoString")]
+    pub fn memo_string(&self) -> Result<String, JsValue> {
+        Ok(self.decoded_memo()?)
+    }
+
+    /// The full 32 byte memo buffer, including any zero padding, for callers
+    /// that need the exact bytes rather than the lossy UTF-8 `memo`.
+    #[wasm_bindgen(getter, js_name = "memoBytes")]
+    pub fn memo_bytes(&self) -> Vec<u8> {
+        self.note.memo().0.to_vec()
+    }
+
+    /// Check whether the memo starts with the given bytes.
+    ///
+    /// Applications that namespace their memos with a fixed prefix can use
+    /// this to skip notes that aren't meant for them.
+    #[wasm_bindgen(js_name = "memoMatchesPrefix")]
+    pub fn memo_matches_prefix(&self, prefix: &[u8]) -> bool {
+        self.note.memo().0.starts_with(prefix)
+    }
+
+    /// The memo bytes following the given prefix, or undefined if the memo
+    /// doesn't start with that prefix.
+    #[wasm_bindgen(js_name = "memoAfterPrefix")]
+    pub fn memo_after_prefix(&self, prefix: &[u8]) -> Option<Vec<u8>> {
+        let memo = self.note.memo();
+        if memo.0.starts_with(prefix) {
+            Some(memo.0[prefix.len()..].to_vec())
+        } else {
+            None
+        }
+    }
+
+    /// Sign a receipt proving that the owner of this note received it. The
+    /// incoming view key must belong to the note's owner.
+    ///
+    /// The receipt can be checked against the owner's public address with
+    /// `verifySignedReceipt`.
+    #[wasm_bindgen(js_name = "signedReceipt")]
+    pub fn signed_receipt(&self, incoming_view_key_hex: &str) -> Result<Vec<u8>, JsValue> {
+        let incoming_view_key = IncomingViewKey::from_hex(SAPLING.clone(), incoming_view_key_hex)
+            .map_err(WasmSaplingError::from)?;
+        let receipt =
+            sign_receipt(&self.note, &incoming_view_key).map_err(WasmSaplingError::from)?;
+        Ok(receipt)
+    }
+
     /// Compute the nullifier for this note, given the private key of its owner.
     ///
     /// The nullifier is a series of bytes that is published by the note owner
     /// only at the time the note is spent. This key is collected in a massive
     /// 'nullifier set', preventing double-spend.
+    ///
+    /// `position` is the leaf index of the note in the note commitment tree,
+    /// the same position the witness used to spend it refers to. Wallets can
+    /// match the resulting 32 bytes against the nullifiers of incoming
+    /// transactions to detect when the note is spent. Throws an error with
+    /// code `SaplingKeyError` if the key is invalid.
     #[wasm_bindgen]
-    pub fn nullifier(&self, owner_private_key: &str, position: u64) -> Vec<u8> {
-        let private_key = Key::from_hex(SAPLING.clone(), owner_private_key).unwrap();
-        self.note.nullifier(&private_key, position).to_vec()
+    pub fn nullifier(&self, owner_private_key: &str, position: u64) -> Result<Vec<u8>, JsValue> {
+        Ok(self.owner_nullifier(owner_private_key, position)?)
+    }
+}
+
+/// Whether the serialized merkle note can be decrypted with the hex encoded
+/// incoming view key, i.e. whether the output belongs to that key.
+///
+/// Meant for scanning loops: no `WasmNote` is created, and a malformed note
+/// or key is reported as not owned instead of throwing. The time taken is
+/// that of the trial decryption itself.
+#[wasm_bindgen(js_name = "merkleNoteIsOwned")]
+pub fn merkle_note_is_owned(merkle_note_bytes: &[u8], incoming_view_key_hex: &str) -> bool {
+    match IncomingViewKey::from_hex(SAPLING.clone(), incoming_view_key_hex) {
+        Ok(incoming_view_key) => decrypt_merkle_note(merkle_note_bytes, &incoming_view_key).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Choose notes to spend that together cover `target` plus `fee`, taking the
+/// largest notes first so as few as possible are spent.
+///
+/// `notes` is an array of serialized notes (as produced by
+/// `WasmNote.serialize`). Returns the indices of the chosen notes, in the
+/// order they were picked. Throws an error with code `InsufficientFunds` if
+/// all the notes together are not enough, or `SaplingKeyError` with the
+/// index of a note that could not be read.
+#[wasm_bindgen(js_name = "selectNotes")]
+pub fn select_notes_to_spend(
+    notes: js_sys::Array,
+    target: u64,
+    fee: u64,
+) -> Result<Vec<u32>, JsValue> {
+    let notes: Vec<Vec<u8>> = notes
+        .iter()
+        .map(|note| js_sys::Uint8Array::new(&note).to_vec())
+        .collect();
+    Ok(select_serialized_notes(&notes, target, fee)?)
+}
+
+fn select_serialized_notes(
+    notes: &[Vec<u8>],
+    target: u64,
+    fee: u64,
+) -> Result<Vec<u32>, WasmSaplingError> {
+    let notes = read_notes(notes)?;
+    let selected = select_notes(&notes, target, fee)?;
+    Ok(selected.into_iter().map(|index| index as u32).collect())
+}
+
+/// Read notes serialized by `WasmNote.serialize`, failing with the index of
+/// the first one that can't be read.
+pub(crate) fn read_notes(notes: &[Vec<u8>]) -> Result<Vec<Note>, WasmSaplingError> {
+    notes
+        .iter()
+        .enumerate()
+        .map(|(index, bytes)| {
+            Note::read(&bytes[..], SAPLING.clone())
+                .map_err(|e| WasmSaplingError::from(e).at_index(index))
+        })
+        .collect()
+}
+
+fn decrypt_merkle_note(
+    merkle_note_bytes: &[u8],
+    incoming_view_key: &IncomingViewKey,
+) -> Result<Note, WasmSaplingError> {
+    read_merkle_note(merkle_note_bytes)?
+        .decrypt_note_for_owner(incoming_view_key)
+        .map_err(|_| {
+            WasmSaplingError::new(
+                "DecryptionFailed",
+                "Note could not be decrypted with the incoming view key".into(),
+            )
+        })
+}
+
+fn decrypt_merkle_note_for_spender(
+    merkle_note_bytes: &[u8],
+    outgoing_view_key: &OutgoingViewKey,
+) -> Result<Note, WasmSaplingError> {
+    read_merkle_note(merkle_note_bytes)?
+        .decrypt_note_for_spender(outgoing_view_key)
+        .map_err(|_| {
+            WasmSaplingError::new(
+                "DecryptionFailed",
+                "Note could not be decrypted with the outgoing view key".into(),
+            )
+        })
+}
+
+impl WasmNote {
+    fn owner_nullifier(
+        &self,
+        owner_private_key: &str,
+        position: u64,
+    ) -> Result<Vec<u8>, WasmSaplingError> {
+        let private_key = Key::from_hex(SAPLING.clone(), owner_private_key)?;
+        Ok(self.note.nullifier(&private_key, position).to_vec())
+    }
+
+    fn decoded_memo(&self) -> Result<String, WasmSaplingError> {
+        let memo = self.note.memo();
+        memo.as_str()
+            .map(|memo| memo.to_string())
+            .map_err(|e| WasmSaplingError::new("InvalidMemo", format!("InvalidMemo - {:?}", e)))
+    }
+}
+
+fn read_merkle_note(merkle_note_bytes: &[u8]) -> Result<MerkleNote, WasmSaplingError> {
+    MerkleNote::read(merkle_note_bytes, SAPLING.clone()).map_err(|e| {
+        WasmSaplingError::new("InvalidMerkleNote", format!("InvalidMerkleNote - {:?}", e))
+    })
+}
+
+/// Check a receipt created by `WasmNote.signedReceipt` against the hex
+/// encoded public address the note was paid to.
+#[wasm_bindgen(js_name = "verifySignedReceipt")]
+pub fn verify_signed_receipt(receipt: &[u8], public_address: &str) -> bool {
+    match PublicAddress::from_hex(SAPLING.clone(), public_address) {
+        Ok(address) => signed_receipt::verify_signed_receipt(&SAPLING, receipt, &address),
+        Err(_) => false,
+    }
+}
+
+/// Zero-pad the memo to exactly 32 bytes, the way `WasmNote` stores it.
+///
+/// Returns an error with code `MemoTooLong` if the input is longer than 32
+/// bytes, rather than silently truncating it.
+#[wasm_bindgen(js_name = "canonicalizeMemo")]
+pub fn canonicalize_memo(input: &[u8]) -> Result<Vec<u8>, JsValue> {
+    Ok(canonical_memo(input)?.to_vec())
+}
+
+fn canonical_memo(input: &[u8]) -> Result<[u8; MEMO_SIZE], WasmSaplingError> {
+    if input.len() > MEMO_SIZE {
+        return Err(WasmSaplingError::new(
+            "MemoTooLong",
+            format!(
+                "Memo is {} bytes, but can be at most {}",
+                input.len(),
+                MEMO_SIZE
+            ),
+        ));
+    }
+    let mut memo = [0; MEMO_SIZE];
+    memo[..input.len()].copy_from_slice(input);
+    Ok(memo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        canonical_memo, decrypt_merkle_note, decrypt_merkle_note_for_spender, merkle_note_is_owned,
+        select_serialized_notes, WasmNote,
+    };
+    use crate::wasm_structs::transaction::miners_fee_transaction;
+    use crate::wasm_structs::{WasmTransaction, WasmWitness};
+    use ironfish_rust::note::{Memo, MEMO_SIZE};
+    use ironfish_rust::sapling_bls12::{Bls12, Key, MerkleNote, Note, SAPLING};
+    use ironfish_rust::witness::NOTE_TREE_DEPTH;
+    use zcash_primitives::{
+        jubjub::{fs::Fs, ToUniform},
+        primitives::ValueCommitment,
+    };
+
+    #[test]
+    fn test_select_serialized_notes() {
+        let key = Key::generate_key(SAPLING.clone());
+        let address = key.generate_public_address().hex_public_address();
+        let notes: Vec<Vec<u8>> = [3, 10, 7]
+            .iter()
+            .map(|value| WasmNote::new(&address, *value, "").unwrap().serialize())
+            .collect();
+
+        assert_eq!(select_serialized_notes(&notes, 12, 1).unwrap(), vec![1, 2]);
+        let err = select_serialized_notes(&notes, 20, 1).unwrap_err();
+        assert_eq!(err.code(), "InsufficientFunds");
+        let err = select_serialized_notes(&[notes[0].clone(), vec![0; 3]], 1, 0).unwrap_err();
+        assert!(err.message().starts_with("Item at index 1"));
+    }
+
+    #[test]
+    fn test_is_dust() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        assert!(!note.is_dust(0));
+        assert!(!note.is_dust(4));
+        assert!(note.is_dust(5));
+        assert!(note.is_dust(6));
+    }
+
+    #[test]
+    fn test_canonical_memo() {
+        assert_eq!(canonical_memo(b"").unwrap(), [0; 32]);
+
+        let memo = canonical_memo(b"abc").unwrap();
+        assert_eq!(&memo[..3], b"abc");
+        assert_eq!(memo[3..], [0; 29]);
+
+        assert_eq!(canonical_memo(&[7; 32]).unwrap(), [7; 32]);
+
+        let err = canonical_memo(&[7; 33]).unwrap_err();
+        assert_eq!(err.code(), "MemoTooLong");
+    }
+
+    #[test]
+    fn test_zero_value_note() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 0, "").unwrap();
+        assert_eq!(note.value(), 0);
+
+        // A full depth witness with an all zero path, good enough to reach
+        // the value check
+        let mut bytes = vec![0; 32];
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.push(NOTE_TREE_DEPTH as u8);
+        bytes.extend(vec![0; NOTE_TREE_DEPTH * 33]);
+        let witness = WasmWitness::deserialize(&bytes).unwrap();
+
+        let mut transaction = WasmTransaction::new();
+        let err = transaction
+            .spend_note(&key.hex_spending_key(), &note, &witness.witness)
+            .unwrap_err();
+        assert_eq!(err.code(), "ZeroValueSpend");
+    }
+
+    #[test]
+    fn test_memo_bytes() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note = WasmNote::new(
+            &key.generate_public_address().hex_public_address(),
+            5,
+            "abc",
+        )
+        .unwrap();
+        assert_eq!(note.value(), 5);
+        assert_eq!(note.memo_bytes(), canonical_memo(b"abc").unwrap().to_vec());
+        assert_eq!(note.decoded_memo().unwrap(), "abc");
+
+        let mut memo = [0; MEMO_SIZE];
+        memo[0] = 0xff;
+        let note = WasmNote {
+            note: Note::new(
+                SAPLING.clone(),
+                key.generate_public_address(),
+                5,
+                Memo(memo),
+            ),
+        };
+        assert_eq!(note.decoded_memo().unwrap_err().code(), "InvalidMemo");
+    }
+
+    #[test]
+    fn test_nullifier() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+
+        let nullifier = note.nullifier(&key.hex_spending_key(), 0).unwrap();
+        assert_eq!(nullifier.len(), 32);
+        assert_eq!(
+            nullifier,
+            note.nullifier(&key.hex_spending_key(), 0).unwrap()
+        );
+        assert_ne!(
+            nullifier,
+            note.nullifier(&key.hex_spending_key(), 1).unwrap()
+        );
+
+        let err = note.owner_nullifier("invalid", 0).unwrap_err();
+        assert_eq!(err.code(), "SaplingKeyError");
+    }
+
+    #[test]
+    fn test_decrypt_merkle_note() {
+        let (key, posted) = miners_fee_transaction();
+        let other_key = Key::generate_key(SAPLING.clone());
+        let merkle_note = posted.get_note(0);
+
+        let decrypted = decrypt_merkle_note(&merkle_note, key.incoming_view_key()).unwrap();
+        assert_eq!(decrypted.value(), 5);
+
+        let err = decrypt_merkle_note(&merkle_note, other_key.incoming_view_key()).unwrap_err();
+        assert_eq!(err.code(), "DecryptionFailed");
+
+        let err = decrypt_merkle_note(&merkle_note[1..], key.incoming_view_key()).unwrap_err();
+        assert_eq!(err.code(), "InvalidMerkleNote");
+
+        let view_key = key.incoming_view_key().hex_key();
+        assert!(merkle_note_is_owned(&merkle_note, &view_key));
+        assert!(!merkle_note_is_owned(
+            &merkle_note,
+            &other_key.incoming_view_key().hex_key()
+        ));
+        assert!(!merkle_note_is_owned(&merkle_note[1..], &view_key));
+        assert!(!merkle_note_is_owned(&merkle_note, "invalid"));
+    }
+
+    #[test]
+    fn test_decrypt_merkle_note_for_spender() {
+        let spender_key = Key::generate_key(SAPLING.clone());
+        let receiver_key = Key::generate_key(SAPLING.clone());
+        let owner = receiver_key.generate_public_address();
+        let note = Note::new(SAPLING.clone(), owner.clone(), 42, Memo::from("sent"));
+        let value_commitment = ValueCommitment::<Bls12> {
+            value: note.value(),
+            randomness: Fs::to_uniform(&[7; 64]),
+        };
+        let diffie_hellman_keys = owner.generate_diffie_hellman_keys(&SAPLING.jubjub);
+        let merkle_note =
+            MerkleNote::new(&spender_key, &note, &value_commitment, &diffie_hellman_keys);
+        let mut merkle_note_bytes = vec![];
+        merkle_note.write(&mut merkle_note_bytes).unwrap();
+
+        let decrypted =
+            decrypt_merkle_note_for_spender(&merkle_note_bytes, spender_key.outgoing_view_key())
+                .unwrap();
+        assert_eq!(decrypted.value(), 42);
+        assert_eq!(decrypted.memo(), Memo::from("sent"));
+
+        let err =
+            decrypt_merkle_note_for_spender(&merkle_note_bytes, receiver_key.outgoing_view_key())
+                .unwrap_err();
+        assert_eq!(err.code(), "DecryptionFailed");
     }
 }
@@ -0,0 +1,150 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Bloom filters over the note commitments of a transaction's outputs, so
+//! light clients can tell which transactions may be relevant to them without
+//! downloading them.
+//!
+//! A serialized filter is:
+//!
+//! * the number of hash functions `k` (u8)
+//! * the number of bits `m` (u32, little endian)
+//! * `ceil(m / 8)` bytes of bits, bit `j` being bit `j % 8` (least
+//!   significant first) of byte `j / 8`
+//!
+//! A commitment sets bits `(h1 + i * h2) mod m` for `i` in `0..k`, where `h1`
+//! and `h2` are the first and second 8 bytes, read as little endian u64s, of
+//! the 32 byte blake2b hash of the commitment with personalization
+//! `Iron Fish bloomf`.
+
+use std::convert::TryInto;
+
+use blake2b_simd::Params as Blake2b;
+use wasm_bindgen::prelude::*;
+
+use super::errors::WasmSaplingError;
+
+const FILTER_PERSONALIZATION: &[u8; 16] = b"Iron Fish bloomf";
+const HEADER_SIZE: usize = 5;
+const MAX_HASHES: usize = 32;
+
+pub(crate) fn build_filter(
+    commitments: &[[u8; 32]],
+    false_positive_rate: f64,
+) -> Result<Vec<u8>, WasmSaplingError> {
+    if false_positive_rate.is_nan() || false_positive_rate <= 0.0 || false_positive_rate >= 1.0 {
+        return Err(WasmSaplingError::new(
+            "InvalidFalsePositiveRate",
+            format!(
+                "False positive rate must be between 0 and 1, got {}",
+                false_positive_rate
+            ),
+        ));
+    }
+
+    // Standard bloom filter sizing for n items and false positive rate p:
+    // m = -n ln(p) / ln(2)^2 and k = m / n * ln(2)
+    let items = commitments.len().max(1) as f64;
+    let ln2 = std::f64::consts::LN_2;
+    let bits = (-items * false_positive_rate.ln() / (ln2 * ln2))
+        .ceil()
+        .max(8.0)
+        .min(u32::MAX as f64) as u32;
+    let hashes = ((bits as f64 / items) * ln2).round() as usize;
+    let hashes = hashes.max(1).min(MAX_HASHES);
+
+    let mut filter = vec![0u8; filter_size(bits)];
+    filter[0] = hashes as u8;
+    filter[1..HEADER_SIZE].copy_from_slice(&bits.to_le_bytes());
+    for commitment in commitments {
+        for bit in bit_indexes(commitment, hashes, bits) {
+            filter[HEADER_SIZE + bit / 8] |= 1 << (bit % 8);
+        }
+    }
+    Ok(filter)
+}
+
+fn filter_contains(filter: &[u8], commitment: &[u8]) -> Result<bool, WasmSaplingError> {
+    let invalid = || WasmSaplingError::new("InvalidFilter", "Malformed output filter".into());
+    if filter.len() < HEADER_SIZE {
+        return Err(invalid());
+    }
+    let hashes = filter[0] as usize;
+    let bits = u32::from_le_bytes(filter[1..HEADER_SIZE].try_into().unwrap());
+    if hashes == 0 || bits == 0 || filter.len() != filter_size(bits) {
+        return Err(invalid());
+    }
+    Ok(bit_indexes(commitment, hashes, bits)
+        .all(|bit| filter[HEADER_SIZE + bit / 8] & (1 << (bit % 8)) != 0))
+}
+
+/// Serialized size of a filter of `bits` bits. The arithmetic is done in u64
+/// since `bits` may come from an untrusted filter and `usize` is only 32 bits
+/// on wasm32; the result is at most `2^29 + HEADER_SIZE`, so it always fits.
+fn filter_size(bits: u32) -> usize {
+    ((u64::from(bits) + 7) / 8) as usize + HEADER_SIZE
+}
+
+fn bit_indexes(commitment: &[u8], hashes: usize, bits: u32) -> impl Iterator<Item = usize> {
+    let hash = Blake2b::new()
+        .hash_length(32)
+        .personal(FILTER_PERSONALIZATION)
+        .hash(commitment);
+    let h1 = u64::from_le_bytes(hash.as_bytes()[..8].try_into().unwrap());
+    let h2 = u64::from_le_bytes(hash.as_bytes()[8..16].try_into().unwrap());
+    (0..hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bits as u64) as usize)
+}
+
+/// Check whether a note commitment may be in a filter produced by
+/// `WasmTransactionPosted.outputFilter`. False means the commitment is
+/// definitely not one of the transaction's outputs.
+#[wasm_bindgen(js_name = "outputFilterContains")]
+pub fn output_filter_contains(filter: &[u8], commitment: &[u8]) -> Result<bool, JsValue> {
+    Ok(filter_contains(filter, commitment)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_filter, filter_contains};
+
+    #[test]
+    fn test_output_filter() {
+        let commitments: Vec<[u8; 32]> = (0..20u8).map(|i| [i; 32]).collect();
+        let filter = build_filter(&commitments, 0.01).unwrap();
+
+        for commitment in commitments.iter() {
+            assert!(filter_contains(&filter, commitment).unwrap());
+        }
+        let false_positives = (100..=255u8)
+            .filter(|i| filter_contains(&filter, &[*i; 32]).unwrap())
+            .count();
+        assert!(false_positives < 10);
+
+        let empty = build_filter(&[], 0.01).unwrap();
+        assert!(!filter_contains(&empty, &[0; 32]).unwrap());
+
+        assert_eq!(
+            build_filter(&commitments, 1.0).err().unwrap().code(),
+            "InvalidFalsePositiveRate"
+        );
+        assert_eq!(
+            filter_contains(&filter[..filter.len() - 1], &[0; 32])
+                .err()
+                .unwrap()
+                .code(),
+            "InvalidFilter"
+        );
+    }
+
+    #[test]
+    fn test_output_filter_size_overflow() {
+        // On a 32 bit target the size of this many bits used to wrap to zero
+        // extra bytes, so a bare header passed the length check
+        let filter = [1, 0xff, 0xff, 0xff, 0xff];
+        assert_eq!(
+            filter_contains(&filter, &[0; 32]).err().unwrap().code(),
+            "InvalidFilter"
+        );
+    }
+}
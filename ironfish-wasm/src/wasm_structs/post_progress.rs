@@ -0,0 +1,34 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use wasm_bindgen::prelude::*;
+
+/// Outcome of a single call to `WasmTransaction.postStep`.
+///
+/// `done` is set once every proof posting needs has been created, after
+/// which `finishPost` produces the posted transaction.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WasmPostProgress {
+    pub(crate) completed: usize,
+    pub(crate) total: usize,
+}
+
+#[wasm_bindgen]
+impl WasmPostProgress {
+    #[wasm_bindgen(getter)]
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn done(&self) -> bool {
+        self.completed == self.total
+    }
+}
@@ -0,0 +1,69 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::merkle_note::MERKLE_NOTE_SIZE;
+use ironfish_rust::receiving::RECEIPT_PROOF_SIZE;
+use ironfish_rust::sapling_bls12::{ReceiptProof, SAPLING};
+
+#[wasm_bindgen]
+pub struct WasmReceiptProof {
+    pub(crate) proof: ReceiptProof,
+}
+
+#[wasm_bindgen]
+impl WasmReceiptProof {
+    /// The serialized merkle note of this output, the same bytes returned by
+    /// `WasmTransactionPosted.getNote`.
+    #[wasm_bindgen(getter, js_name = "merkleNote")]
+    pub fn merkle_note(&self) -> Vec<u8> {
+        let mut cursor: Vec<u8> = Vec::with_capacity(MERKLE_NOTE_SIZE);
+        self.proof.merkle_note().write(&mut cursor).unwrap();
+        cursor
+    }
+
+    #[wasm_bindgen(getter, js_name = "noteCommitment")]
+    pub fn note_commitment(&self) -> Vec<u8> {
+        self.proof.merkle_note().note_commitment().to_vec()
+    }
+
+    #[wasm_bindgen(getter, js_name = "valueCommitment")]
+    pub fn value_commitment(&self) -> Vec<u8> {
+        self.proof.merkle_note().value_commitment().to_vec()
+    }
+
+    /// Check the proof of this output on its own, without the rest of the
+    /// transaction.
+    #[wasm_bindgen]
+    pub fn verify(&self) -> bool {
+        self.proof.verify_proof(&SAPLING).is_ok()
+    }
+
+    /// Number of bytes this output occupies in a serialized transaction.
+    #[wasm_bindgen(js_name = "serializedSize")]
+    pub fn serialized_size(&self) -> usize {
+        RECEIPT_PROOF_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wasm_structs::transaction::miners_fee_transaction;
+
+    #[test]
+    fn test_receipt_proof() {
+        let (_, posted) = miners_fee_transaction();
+
+        assert_eq!(posted.receipts_length(), 1);
+        let receipt = posted.get_receipt(0);
+        assert!(receipt.verify());
+        assert_eq!(receipt.merkle_note(), posted.get_note(0));
+        assert_eq!(receipt.value_commitment().len(), 32);
+        assert_eq!(
+            &receipt.merkle_note()[..32],
+            &receipt.value_commitment()[..]
+        );
+    }
+}
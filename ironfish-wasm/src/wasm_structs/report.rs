@@ -0,0 +1,128 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::sapling_bls12::Transaction;
+
+use super::errors::WasmSaplingError;
+use super::index_entries::{to_array, WasmIndexEntries};
+use super::root_set::{anchor_of, WasmRootSet};
+
+/// Result of verifying a transaction, together with the data nodes log
+/// about it.
+#[wasm_bindgen]
+pub struct WasmTransactionReport {
+    error: Option<WasmSaplingError>,
+    fee: i64,
+    size: usize,
+    proof_count: usize,
+    entries: WasmIndexEntries,
+    anchors: Vec<[u8; 32]>,
+}
+
+#[wasm_bindgen]
+impl WasmTransactionReport {
+    #[wasm_bindgen(getter)]
+    pub fn valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn fee(&self) -> i64 {
+        self.fee
+    }
+
+    /// Serialized size of the transaction in bytes.
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of groth16 proofs in the transaction, one per spend and one
+    /// per output.
+    #[wasm_bindgen(getter, js_name = "proofCount")]
+    pub fn proof_count(&self) -> usize {
+        self.proof_count
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn nullifiers(&self) -> js_sys::Array {
+        self.entries.nullifiers()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn commitments(&self) -> js_sys::Array {
+        self.entries.commitments()
+    }
+
+    /// Root hashes of the note trees the spends were proven against, in
+    /// spend order.
+    #[wasm_bindgen(getter)]
+    pub fn anchors(&self) -> js_sys::Array {
+        to_array(&self.anchors)
+    }
+
+    /// Code of the reason the transaction was rejected: `UnknownAnchor` if a
+    /// spend refers to a root not in the given set, otherwise the code of the
+    /// failed verification. Undefined for valid transactions.
+    #[wasm_bindgen(getter, js_name = "errorCode")]
+    pub fn error_code(&self) -> Option<String> {
+        self.error.as_ref().map(|e| e.code())
+    }
+
+    #[wasm_bindgen(getter, js_name = "errorMessage")]
+    pub fn error_message(&self) -> Option<String> {
+        self.error.as_ref().map(|e| e.message())
+    }
+}
+
+impl WasmTransactionReport {
+    pub(crate) fn from_transaction(
+        transaction: &Transaction,
+        valid_roots: &WasmRootSet,
+    ) -> WasmTransactionReport {
+        let anchors: Vec<[u8; 32]> = transaction.iter_spends().map(anchor_of).collect();
+        let error = match anchors
+            .iter()
+            .position(|anchor| !valid_roots.contains_root(anchor))
+        {
+            Some(index) => Some(
+                WasmSaplingError::new("UnknownAnchor", "Spend anchor is not a known root".into())
+                    .at_index(index),
+            ),
+            None => transaction.verify().err().map(WasmSaplingError::from),
+        };
+
+        WasmTransactionReport {
+            error,
+            fee: transaction.transaction_fee(),
+            size: transaction.serialized_size(),
+            proof_count: transaction.spends().len() + transaction.receipts().len(),
+            entries: WasmIndexEntries::from_transaction(transaction),
+            anchors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmTransactionReport;
+    use crate::wasm_structs::transaction::miners_fee_transaction;
+    use crate::wasm_structs::WasmRootSet;
+
+    #[test]
+    fn test_full_report() {
+        let (_, posted) = miners_fee_transaction();
+
+        let report =
+            WasmTransactionReport::from_transaction(&posted.transaction, &WasmRootSet::new());
+        assert!(report.valid());
+        assert_eq!(report.error_code(), None);
+        assert_eq!(report.fee(), -5);
+        assert_eq!(report.size(), posted.transaction.serialized_size());
+        assert_eq!(report.proof_count(), 1);
+        assert!(report.anchors.is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::collections::HashSet;
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::sapling_bls12::{MerkleNoteHash, SpendProof, Transaction};
+
+use super::errors::WasmSaplingError;
+
+type RootHash = [u8; 32];
+
+/// Set of note tree root hashes known to exist in a chain.
+///
+/// Roots are added in their serialized form, the same bytes returned by
+/// `WasmSpendProof.rootHash`.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct WasmRootSet {
+    roots: HashSet<RootHash>,
+}
+
+#[wasm_bindgen]
+impl WasmRootSet {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmRootSet {
+        WasmRootSet::default()
+    }
+
+    /// Add a root hash to the set. Fails if `root` is not 32 bytes long.
+    #[wasm_bindgen]
+    pub fn add(&mut self, root: &[u8]) -> Result<(), JsValue> {
+        self.roots.insert(to_root_hash(root)?);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn contains(&self, root: &[u8]) -> bool {
+        to_root_hash(root).map_or(false, |root| self.roots.contains(&root))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.roots.len()
+    }
+}
+
+impl WasmRootSet {
+    pub(crate) fn contains_root(&self, root: &RootHash) -> bool {
+        self.roots.contains(root)
+    }
+
+    /// Whether the anchor of every spend in the transaction is in the set.
+    pub(crate) fn contains_anchors_of(&self, transaction: &Transaction) -> bool {
+        transaction
+            .iter_spends()
+            .all(|spend| self.contains_root(&anchor_of(spend)))
+    }
+}
+
+/// Serialized root hash of the note tree a spend was proven against.
+pub(crate) fn anchor_of(spend: &SpendProof) -> RootHash {
+    let mut root = [0; 32];
+    MerkleNoteHash::new(spend.root_hash())
+        .write(&mut &mut root[..])
+        .unwrap();
+    root
+}
+
+fn to_root_hash(root: &[u8]) -> Result<RootHash, WasmSaplingError> {
+    if root.len() != 32 {
+        return Err(WasmSaplingError::new(
+            "InvalidRootHash",
+            format!("Root hash must be 32 bytes, got {}", root.len()),
+        ));
+    }
+    let mut hash = [0; 32];
+    hash.copy_from_slice(root);
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_root_hash, WasmRootSet};
+
+    #[test]
+    fn test_root_set() {
+        let mut roots = WasmRootSet::new();
+        roots.add(&[1; 32]).unwrap();
+        roots.add(&[1; 32]).unwrap();
+        roots.add(&[2; 32]).unwrap();
+
+        assert_eq!(roots.size(), 2);
+        assert!(roots.contains(&[1; 32]));
+        assert!(roots.contains_root(&[2; 32]));
+        assert!(!roots.contains(&[3; 32]));
+        assert!(!roots.contains(&[1; 31]));
+        assert_eq!(
+            to_root_hash(&[1; 33]).err().unwrap().code(),
+            "InvalidRootHash"
+        );
+    }
+}
@@ -4,7 +4,10 @@
 
 use wasm_bindgen::prelude::*;
 
-use ironfish_rust::sapling_bls12::{MerkleNoteHash, SpendProof};
+use ironfish_rust::sapling_bls12::{MerkleNoteHash, SpendProof, SAPLING};
+use ironfish_rust::spending::SPEND_PROOF_SIZE;
+
+use super::errors::WasmSaplingError;
 
 #[wasm_bindgen]
 pub struct WasmSpendProof {
@@ -18,6 +21,7 @@ impl WasmSpendProof {
         self.proof.tree_size()
     }
 
+    /// Root hash of the note tree the spend was proven against (its anchor).
     #[wasm_bindgen(getter, js_name = "rootHash")]
     pub fn root_hash(&self) -> Vec<u8> {
         let mut cursor: std::io::Cursor<Vec<u8>> = std::io::Cursor::new(vec![]);
@@ -31,4 +35,163 @@ impl WasmSpendProof {
     pub fn nullifier(&self) -> Vec<u8> {
         self.proof.nullifier().to_vec()
     }
+
+    #[wasm_bindgen(getter, js_name = "valueCommitment")]
+    pub fn value_commitment(&self) -> Vec<u8> {
+        self.proof.value_commitment().to_vec()
+    }
+
+    /// The re-randomized public key (`rk`) the spend authorizing signature
+    /// was created with. Together with the signature and the transaction
+    /// hash, this is enough to check the spend authorization externally.
+    #[wasm_bindgen(getter)]
+    pub fn rk(&self) -> Vec<u8> {
+        let mut cursor: Vec<u8> = Vec::with_capacity(32);
+        self.proof
+            .randomized_public_key()
+            .write(&mut cursor)
+            .unwrap();
+        cursor
+    }
+
+    /// The 64 byte signature of the note owner authorizing this spend, made
+    /// over the `transactionHash` of the transaction it belongs to.
+    #[wasm_bindgen(getter, js_name = "authorizingSignature")]
+    pub fn authorizing_signature(&self) -> Vec<u8> {
+        let mut cursor: Vec<u8> = Vec::with_capacity(64);
+        self.proof
+            .authorizing_signature()
+            .write(&mut cursor)
+            .unwrap();
+        cursor
+    }
+
+    /// Check the authorizing signature of this spend against its `rk`, for
+    /// the 32 byte `transactionHash` of the transaction it belongs to.
+    /// Unlike verifying the whole transaction, this tells which spend has a
+    /// bad signature. Returns false if the hash is not 32 bytes.
+    #[wasm_bindgen(js_name = "verifyAuthorization")]
+    pub fn verify_authorization(&self, transaction_hash: &[u8]) -> bool {
+        if transaction_hash.len() != 32 {
+            return false;
+        }
+        let mut hash = [0; 32];
+        hash.copy_from_slice(transaction_hash);
+        self.proof.verify_signature(&SAPLING.jubjub, &hash).is_ok()
+    }
+
+    /// Number of bytes this spend occupies in a serialized transaction.
+    #[wasm_bindgen(js_name = "serializedSize")]
+    pub fn serialized_size(&self) -> usize {
+        SPEND_PROOF_SIZE
+    }
+
+    /// The bytes of this spend, exactly as they appear in its transaction.
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut cursor: Vec<u8> = Vec::with_capacity(SPEND_PROOF_SIZE);
+        self.proof.write(&mut cursor).unwrap();
+        cursor
+    }
+
+    /// Read a spend written by `serialize`. The bytes must hold exactly one
+    /// spend: an error with code `UnexpectedEof` or `TrailingBytes` is
+    /// thrown if there are too few or too many.
+    #[wasm_bindgen]
+    pub fn deserialize(bytes: &[u8]) -> Result<WasmSpendProof, JsValue> {
+        Ok(read_spend(bytes)?)
+    }
+
+    /// Check the zero knowledge proof of this spend against its public
+    /// inputs. The authorizing signature is checked by
+    /// `verifyAuthorization`.
+    #[wasm_bindgen(js_name = "verifyProof")]
+    pub fn verify_proof(&self) -> bool {
+        self.proof.verify_proof(&SAPLING).is_ok()
+    }
+}
+
+fn read_spend(bytes: &[u8]) -> Result<WasmSpendProof, WasmSaplingError> {
+    if bytes.len() != SPEND_PROOF_SIZE {
+        let code = if bytes.len() < SPEND_PROOF_SIZE {
+            "UnexpectedEof"
+        } else {
+            "TrailingBytes"
+        };
+        return Err(WasmSaplingError::new(
+            code,
+            format!(
+                "A spend is {} bytes, but {} bytes were given",
+                SPEND_PROOF_SIZE,
+                bytes.len()
+            ),
+        ));
+    }
+    let proof = SpendProof::read(&SAPLING.jubjub, bytes)?;
+    Ok(WasmSpendProof { proof })
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_verify_authorization() {
+        use crate::wasm_structs::bench::sample_posted_transaction;
+
+        let posted = sample_posted_transaction(2, 1);
+        let hash = posted.transaction_hash();
+        let spend = posted.get_spend(1);
+        assert_eq!(spend.authorizing_signature().len(), 64);
+        assert!(spend.verify_authorization(&hash));
+
+        let mut other_hash = hash.clone();
+        other_hash[0] ^= 1;
+        assert!(!spend.verify_authorization(&other_hash));
+        assert!(!spend.verify_authorization(&hash[..31]));
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        use super::read_spend;
+        use crate::wasm_structs::bench::sample_posted_transaction;
+
+        let posted = sample_posted_transaction(2, 1);
+        let hash = posted.transaction_hash();
+        let spend = posted.get_spend(1);
+        let bytes = spend.serialize();
+        assert_eq!(bytes.len(), spend.serialized_size());
+
+        let read_back = read_spend(&bytes).unwrap();
+        assert_eq!(read_back.serialize(), bytes);
+        assert!(read_back.verify_proof());
+        assert!(read_back.verify_authorization(&hash));
+
+        // the value commitment is one of the public inputs of the proof
+        let mut other = sample_posted_transaction(1, 1).get_spend(0).serialize();
+        other[192..224].copy_from_slice(&bytes[192..224]);
+        assert!(!read_spend(&other).unwrap().verify_proof());
+
+        let err = read_spend(&bytes[..bytes.len() - 1]).err().unwrap();
+        assert_eq!(err.code(), "UnexpectedEof");
+        let mut extended = bytes;
+        extended.push(0);
+        assert_eq!(read_spend(&extended).err().unwrap().code(), "TrailingBytes");
+    }
+
+    #[test]
+    fn test_rk_matches_serialization() {
+        use crate::wasm_structs::bench::sample_posted_transaction;
+        use ironfish_rust::spending::SPEND_PROOF_SIZE;
+        use ironfish_rust::transaction::TRANSACTION_HEADER_SIZE;
+
+        let posted = sample_posted_transaction(2, 1);
+        let bytes = posted.to_bytes().unwrap();
+        for index in 0..2 {
+            // The proof (192) and value commitment (32) come before rk
+            let start = TRANSACTION_HEADER_SIZE + index * SPEND_PROOF_SIZE + 224;
+            assert_eq!(
+                posted.get_spend(index).rk(),
+                bytes[start..start + 32].to_vec()
+            );
+        }
+    }
 }
@@ -0,0 +1,76 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Batch verification on a pool of web workers. Only compiled with the
+//! `wasm-threads` feature.
+//!
+//! The pool has to be started from javascript with `initThreadPool` before
+//! `verifyTransactionsParallel` is called.
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::transaction;
+
+#[cfg(target_arch = "wasm32")]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+use super::errors::WasmSaplingError;
+use super::transaction::{batch_bitmap, read_batch};
+
+/// Same as `verifyTransactions`, with the proofs and signatures of the
+/// transactions checked on the thread pool.
+///
+/// The transactions are deserialized on the calling thread; only the ones
+/// that could be read are handed to the pool.
+#[wasm_bindgen(js_name = "verifyTransactionsParallel")]
+pub fn verify_transactions_parallel(batch: &[u8], lengths: &[u32]) -> Result<Vec<u8>, JsValue> {
+    Ok(verify_batch_parallel(batch, lengths)?)
+}
+
+fn verify_batch_parallel(batch: &[u8], lengths: &[u32]) -> Result<Vec<u8>, WasmSaplingError> {
+    let (indices, transactions): (Vec<usize>, Vec<_>) = read_batch(batch, lengths)?
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, transaction)| transaction.map(|transaction| (index, transaction)))
+        .unzip();
+
+    let mut valid = vec![false; lengths.len()];
+    let results = transaction::verify_transactions_parallel(&transactions);
+    for (index, result) in indices.into_iter().zip(results) {
+        valid[index] = result.is_ok();
+    }
+    Ok(batch_bitmap(valid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_batch_parallel;
+    use crate::wasm_structs::transaction::miners_fee_transaction;
+
+    #[test]
+    fn test_verify_batch_parallel() {
+        let serialized = miners_fee_transaction().1.to_bytes().unwrap();
+
+        let mut corrupted = serialized.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+
+        let mut batch = serialized.clone();
+        batch.extend_from_slice(&corrupted);
+        batch.extend_from_slice(&[0; 4]);
+        batch.extend_from_slice(&serialized);
+        let length = serialized.len() as u32;
+        let lengths = [length, length, 4, length];
+
+        assert_eq!(
+            verify_batch_parallel(&batch, &lengths).unwrap(),
+            vec![0b1001]
+        );
+        assert_eq!(verify_batch_parallel(&[], &[]).unwrap(), Vec::<u8>::new());
+        assert_eq!(
+            verify_batch_parallel(&batch, &[length]).unwrap_err().code(),
+            "LengthMismatch"
+        );
+    }
+}
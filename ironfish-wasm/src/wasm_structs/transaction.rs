@@ -2,36 +2,155 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::io;
+
+use blake2b_simd::Params as Blake2b;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 
+use ironfish_rust::errors::{SaplingProofError, TransactionError, VerificationError};
+use ironfish_rust::merkle_note::MERKLE_NOTE_SIZE;
+use ironfish_rust::proof_verifier::{serialize_public_inputs, ProofVerifier};
+use ironfish_rust::receiving::RECEIPT_PROOF_SIZE;
 use ironfish_rust::sapling_bls12::{
-    Key, ProposedTransaction, PublicAddress, SimpleTransaction, Transaction, SAPLING,
+    Bls12, IncomingViewKey, Key, MerkleNote, MerkleNoteHash, Note, OutgoingViewKey,
+    ProposedTransaction, PublicAddress, ReceiptProof, Sapling, SimpleTransaction, SpendProof,
+    Transaction, SAPLING,
 };
+use ironfish_rust::transaction::{block_fees_balance, sign_offline};
+use ironfish_rust::witness::WitnessTrait;
 
+use super::chunks::{ChunkReader, ChunkWriter};
+use super::errors::WasmSaplingError;
+use super::hex::to_hex;
+use super::index_entries::WasmIndexEntries;
 use super::note::WasmNote;
+use super::output_filter::build_filter;
+use super::post_progress::WasmPostProgress;
+use super::receipt_proof::WasmReceiptProof;
+use super::report::WasmTransactionReport;
+use super::root_set::{anchor_of, WasmRootSet};
 use super::spend_proof::WasmSpendProof;
+use super::verify_policy::WasmVerifyPolicy;
+use super::verify_step::{WasmVerifyState, WasmVerifyStepResult};
+use super::verifying_keys::WasmVerifyingKeys;
 use super::witness::JsWitness;
 
+const CHECKSUM_PERSONALIZATION: &[u8; 16] = b"Iron Fish chksum";
+const CHECKSUM_SIZE: usize = 4;
+
 #[wasm_bindgen]
+#[derive(Clone)]
 pub struct WasmTransactionPosted {
-    transaction: Transaction,
+    pub(crate) transaction: Transaction,
 }
 
 #[wasm_bindgen]
 impl WasmTransactionPosted {
+    /// Read a transaction from its serialized bytes. Throws an error with
+    /// code `UnexpectedEof` if the bytes are truncated, `InvalidEncoding` if
+    /// a point, scalar or proof in them can't be decoded, `UnsupportedVersion`
+    /// if the transaction was written in a newer format, and `TrailingBytes`
+    /// if there is any data after the transaction.
     #[wasm_bindgen]
-    pub fn deserialize(bytes: &[u8]) -> WasmTransactionPosted {
+    pub fn deserialize(bytes: &[u8]) -> Result<WasmTransactionPosted, JsValue> {
+        console_error_panic_hook::set_once();
+        let transaction = read_transaction(bytes, false)?;
+        Ok(WasmTransactionPosted { transaction })
+    }
+
+    /// Read a transaction from the start of `bytes`, ignoring any data that
+    /// follows it.
+    #[wasm_bindgen(js_name = "deserializeAllowTrailing")]
+    pub fn deserialize_allow_trailing(bytes: &[u8]) -> Result<WasmTransactionPosted, JsValue> {
         console_error_panic_hook::set_once();
-        let mut cursor: std::io::Cursor<&[u8]> = std::io::Cursor::new(bytes);
-        let transaction = Transaction::read(SAPLING.clone(), &mut cursor).unwrap();
-        WasmTransactionPosted { transaction }
+        let transaction = read_transaction(bytes, true)?;
+        Ok(WasmTransactionPosted { transaction })
+    }
+
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Result<Vec<u8>, JsValue> {
+        Ok(self.to_bytes()?)
     }
 
+    /// Make an independent copy of the transaction, without going through
+    /// `serialize` and `deserialize`.
+    #[wasm_bindgen(js_name = "cloneTransaction")]
+    pub fn clone_transaction(&self) -> WasmTransactionPosted {
+        self.clone()
+    }
+
+    /// Whether the two transactions serialize to the same bytes. The
+    /// serialization is canonical, so this is true exactly when they are the
+    /// same transaction.
     #[wasm_bindgen]
-    pub fn serialize(&self) -> Vec<u8> {
-        let mut cursor: std::io::Cursor<Vec<u8>> = std::io::Cursor::new(vec![]);
-        self.transaction.write(&mut cursor).unwrap();
-        cursor.into_inner()
+    pub fn equals(&self, other: &WasmTransactionPosted) -> bool {
+        match (self.to_bytes(), other.to_bytes()) {
+            (Ok(bytes), Ok(other_bytes)) => bytes == other_bytes,
+            _ => false,
+        }
+    }
+
+    /// Serialize the transaction followed by a 4 byte checksum of the
+    /// serialized bytes, for storage or transport that doesn't detect
+    /// corruption itself. Use `deserializeWithChecksum` to read it back.
+    #[wasm_bindgen(js_name = "serializeWithChecksum")]
+    pub fn serialize_with_checksum(&self) -> Result<Vec<u8>, JsValue> {
+        let mut bytes = self.to_bytes()?;
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        Ok(bytes)
+    }
+
+    /// Read a transaction written by `serializeWithChecksum`. Throws an error
+    /// with code `ChecksumMismatch` if the bytes were corrupted, before any
+    /// attempt is made to parse the transaction.
+    #[wasm_bindgen(js_name = "deserializeWithChecksum")]
+    pub fn deserialize_with_checksum(bytes: &[u8]) -> Result<WasmTransactionPosted, JsValue> {
+        console_error_panic_hook::set_once();
+        let transaction = read_transaction_with_checksum(bytes)?;
+        Ok(WasmTransactionPosted { transaction })
+    }
+
+    /// Serialize the transaction into an array of Uint8Arrays of at most
+    /// `chunk_size` bytes each, so very large transactions don't need a
+    /// single contiguous buffer. Use `deserializeFromChunks` to read it back.
+    #[wasm_bindgen(js_name = "serializeChunks")]
+    pub fn serialize_chunks(&self, chunk_size: usize) -> Result<js_sys::Array, JsValue> {
+        if chunk_size == 0 {
+            return Err(WasmSaplingError::new(
+                "InvalidChunkSize",
+                "Chunk size must be greater than zero".into(),
+            )
+            .into());
+        }
+        let mut writer = ChunkWriter::new(chunk_size);
+        self.transaction
+            .write(&mut writer)
+            .map_err(|e| WasmSaplingError::new("IOError", e.to_string()))?;
+        Ok(writer
+            .into_chunks()
+            .iter()
+            .map(|chunk| JsValue::from(js_sys::Uint8Array::from(&chunk[..])))
+            .collect())
+    }
+
+    /// Read a transaction from an array of Uint8Arrays, as produced by
+    /// `serializeChunks`. The chunks may be any size.
+    #[wasm_bindgen(js_name = "deserializeFromChunks")]
+    pub fn deserialize_from_chunks(
+        chunks: js_sys::Array,
+    ) -> Result<WasmTransactionPosted, JsValue> {
+        console_error_panic_hook::set_once();
+        let chunks: Vec<Vec<u8>> = chunks
+            .iter()
+            .map(|chunk| js_sys::Uint8Array::new(&chunk).to_vec())
+            .collect();
+        let length = chunks.iter().map(|chunk| chunk.len()).sum();
+        let (transaction, _) = read_transaction_from(ChunkReader::new(chunks), length)?;
+        Ok(WasmTransactionPosted { transaction })
     }
 
     #[wasm_bindgen]
@@ -42,25 +161,315 @@ impl WasmTransactionPosted {
         }
     }
 
+    /// Verify the transaction like `verify`, but check the proofs against
+    /// `keys` instead of the parameters built into this module, for example
+    /// those of another network.
+    #[wasm_bindgen(js_name = "verifyWithKeys")]
+    pub fn verify_with_keys(&self, keys: &WasmVerifyingKeys) -> bool {
+        self.transaction.verify_with(&keys.keys).is_ok()
+    }
+
+    /// Whether this is a valid miners fee transaction that mints exactly
+    /// `expectedReward`: the block subsidy plus the fees of the other
+    /// transactions in the block. `verify` alone accepts any transaction
+    /// with a negative fee, so use this for a block's miners fee.
+    #[wasm_bindgen(js_name = "verifyMinersFee")]
+    pub fn verify_miners_fee(&self, expected_reward: u64) -> bool {
+        self.transaction.verify_miners_fee(expected_reward).is_ok()
+    }
+
+    /// Verify the transaction like `verify`, throwing an error whose code
+    /// names the check that failed instead of returning false: one of
+    /// `SpendProofInvalid`, `SpendSignatureInvalid`, `ReceiptProofInvalid`,
+    /// `DuplicateNullifier`, `BindingSignatureInvalid` or `FeeMismatch`.
+    /// Errors about a single spend or receipt carry its index in the message.
+    #[wasm_bindgen(js_name = "verifyDetailed")]
+    pub fn verify_detailed(&self) -> Result<(), JsValue> {
+        self.transaction
+            .verify_detailed()
+            .map_err(WasmSaplingError::from)?;
+        Ok(())
+    }
+
+    /// Admission check for nodes: apply the structural rules of `policy`
+    /// first, throwing an error whose code names the first rule that is
+    /// broken, then verify the proofs and signatures like `verify`.
+    #[wasm_bindgen(js_name = "verifyWithPolicy")]
+    pub fn verify_with_policy(&self, policy: &WasmVerifyPolicy) -> Result<(), JsValue> {
+        policy.check(&self.transaction)?;
+        self.transaction.verify().map_err(WasmSaplingError::from)?;
+        Ok(())
+    }
+
+    /// Verify the transaction against the chain state in `valid_roots` and
+    /// collect everything a node logs about it in one report. Unlike
+    /// `verify`, a failure carries the reason it was rejected.
+    #[wasm_bindgen(js_name = "fullReport")]
+    pub fn full_report(&self, valid_roots: &WasmRootSet) -> WasmTransactionReport {
+        WasmTransactionReport::from_transaction(&self.transaction, valid_roots)
+    }
+
+    /// Verify the transaction one proof at a time, so verification can be
+    /// interleaved with other work. Pass nothing to start, then the `state`
+    /// of the previous result until it is `done`; the transaction is valid if
+    /// the last result is `valid`. Passing the state of a finished
+    /// verification again is reported as invalid, since it checks nothing.
+    #[wasm_bindgen(js_name = "verifyStep")]
+    pub fn verify_step(&self, state: Option<WasmVerifyState>) -> WasmVerifyStepResult {
+        let transaction_hash = self.transaction.transaction_signature_hash();
+        let state = state.unwrap_or(WasmVerifyState {
+            transaction_hash,
+            step: 0,
+        });
+        let steps = self.transaction.verification_steps();
+        let step = state.step as usize;
+
+        if state.transaction_hash != transaction_hash {
+            return WasmVerifyStepResult {
+                state,
+                done: true,
+                valid: false,
+            };
+        }
+
+        // Steps past the last one fail, like in `Transaction::verify_step`
+        let valid = self.transaction.verify_step(step).is_ok();
+        WasmVerifyStepResult {
+            done: !valid || step + 1 == steps,
+            valid,
+            state: WasmVerifyState {
+                transaction_hash,
+                step: state.step.saturating_add(1),
+            },
+        }
+    }
+
+    /// Verify the transaction like `verify`, and also check that it has not
+    /// expired by the block with sequence `current_sequence`.
+    #[wasm_bindgen(js_name = "verifyAtSequence")]
+    pub fn verify_at_sequence(&self, current_sequence: u32) -> bool {
+        self.transaction.verify_expiration(current_sequence).is_ok() && self.verify()
+    }
+
+    /// Sequence of the last block this transaction can be mined in, or 0 if
+    /// it never expires.
+    #[wasm_bindgen(getter, js_name = "expirationSequence")]
+    pub fn expiration_sequence(&self) -> u32 {
+        self.transaction.expiration_sequence()
+    }
+
+    /// Check that the transaction conserves value: that the spends, outputs
+    /// and fee balance out, as proven by the binding signature. Unlike
+    /// `verify`, this does not check any of the proofs.
+    #[wasm_bindgen(js_name = "verifyValueBalance")]
+    pub fn verify_value_balance(&self) -> bool {
+        self.transaction.verify_value_balance().is_ok()
+    }
+
+    /// Same as `postedValueCommitmentSum`.
+    #[wasm_bindgen(js_name = "valueCommitmentSum")]
+    pub fn value_commitment_sum(&self) -> Vec<u8> {
+        self.posted_value_commitment_sum()
+    }
+
+    /// Check only the binding signature against the value commitments and
+    /// the fee, without any of the proofs. Same as `verifyValueBalance`,
+    /// under the name used when debugging which part of a transaction fails
+    /// `verify()`.
+    #[wasm_bindgen(js_name = "verifyBindingSignature")]
+    pub fn verify_binding_signature(&self) -> bool {
+        self.verify_value_balance()
+    }
+
+    /// Check only the spend at `index`: its proof and its signature over the
+    /// transaction. False if there is no such spend.
+    #[wasm_bindgen(js_name = "verifySpend")]
+    pub fn verify_spend(&self, index: usize) -> bool {
+        index < self.transaction.spends().len() && self.transaction.verify_step(index).is_ok()
+    }
+
+    /// Check only the proof of the output at `index`. False if there is no
+    /// such output.
+    #[wasm_bindgen(js_name = "verifyReceipt")]
+    pub fn verify_receipt(&self, index: usize) -> bool {
+        let spends = self.transaction.spends().len();
+        index < self.transaction.receipts().len()
+            && self.transaction.verify_step(spends + index).is_ok()
+    }
+
+    /// Verify the transaction, delegating the zero knowledge proof checks to
+    /// `verifier`, which is called as `verifier(kind, proof, publicInputs)`:
+    ///
+    /// * `kind` is either "spend" or "receipt"
+    /// * `proof` is the serialized groth16 proof
+    /// * `publicInputs` is the concatenation of the 32 byte little endian
+    ///   public inputs, in circuit order
+    ///
+    /// It must return true if the proof is valid. All signatures are still
+    /// checked in rust.
+    #[wasm_bindgen(js_name = "verifyWith")]
+    pub fn verify_with(&self, verifier: &js_sys::Function) -> bool {
+        self.transaction
+            .verify_with(&JsProofVerifier { callback: verifier })
+            .is_ok()
+    }
+
     #[wasm_bindgen(getter, js_name = "notesLength")]
     pub fn notes_length(&self) -> usize {
         self.transaction.receipts().len()
     }
 
+    /// Number of outputs (receipts) in this transaction; the same as
+    /// `notesLength`, named to match `spendsLength`.
+    #[wasm_bindgen(getter, js_name = "receiptsLength")]
+    pub fn receipts_length(&self) -> usize {
+        self.transaction.receipts().len()
+    }
+
+    #[wasm_bindgen(js_name = "getReceipt")]
+    pub fn get_receipt(&self, index: usize) -> WasmReceiptProof {
+        let proof = &self.transaction.receipts()[index];
+        WasmReceiptProof {
+            proof: proof.clone(),
+        }
+    }
+
     #[wasm_bindgen(js_name = "getNote")]
     pub fn get_note(&self, index: usize) -> Vec<u8> {
+        merkle_note_bytes(&self.transaction.receipts()[index])
+    }
+
+    /// Every output's serialized note, in order, as an array of
+    /// Uint8Arrays: the same as calling `getNote` for each index, in a
+    /// single call.
+    #[wasm_bindgen(js_name = "allNotes")]
+    pub fn all_notes(&self) -> js_sys::Array {
+        self.serialized_notes()
+            .iter()
+            .map(|note| JsValue::from(js_sys::Uint8Array::from(&note[..])))
+            .collect()
+    }
+
+    /// The nullifier of every spend, in order, as an array of 32 byte
+    /// Uint8Arrays. Same as `spentNullifiers`, under a name matching
+    /// `allNotes`.
+    #[wasm_bindgen(js_name = "allSpendNullifiers")]
+    pub fn all_spend_nullifiers(&self) -> js_sys::Array {
+        self.spent_nullifiers()
+    }
+
+    /// The 32 byte commitment of the output at `index`: the value inserted
+    /// into the note commitment tree at the note's position. Same as
+    /// `merkleHash` on the `WasmNoteEncrypted` read from `getNote(index)`,
+    /// without parsing the note again.
+    #[wasm_bindgen(js_name = "getNoteCommitment")]
+    pub fn get_note_commitment(&self, index: usize) -> Vec<u8> {
         let proof = &self.transaction.receipts()[index];
-        // Note bytes are 275
-        let mut cursor: Vec<u8> = Vec::with_capacity(275);
-        proof.merkle_note().write(&mut cursor).unwrap();
+        let mut cursor: Vec<u8> = Vec::with_capacity(32);
+        proof
+            .merkle_note()
+            .merkle_hash()
+            .write(&mut cursor)
+            .unwrap();
         cursor
     }
 
+    /// Number of bytes each output (receipt) occupies in the serialized
+    /// transaction. Receipts have a fixed size, so this is the same for every
+    /// index.
+    #[wasm_bindgen(js_name = "receiptSerializedSize")]
+    pub fn receipt_serialized_size(&self) -> usize {
+        RECEIPT_PROOF_SIZE
+    }
+
+    /// Number of bytes `serialize()` produces for this transaction: the
+    /// fixed header, every spend, every receipt and the binding signature.
+    #[wasm_bindgen(js_name = "serializedSize")]
+    pub fn serialized_size(&self) -> usize {
+        self.transaction.serialized_size()
+    }
+
+    /// Format version of the transaction. Transactions that expire are
+    /// version 1, others version 0.
+    #[wasm_bindgen(getter)]
+    pub fn version(&self) -> u8 {
+        self.transaction.version()
+    }
+
+    /// `transactionFee` divided by `serializedSize`, for ordering a mempool
+    /// by fee density. The fee is in the smallest unit of currency, the same
+    /// unit as note values, so this is that unit per byte. Negative for a
+    /// miners fee transaction.
+    #[wasm_bindgen(js_name = "feeRate")]
+    pub fn fee_rate(&self) -> f64 {
+        self.transaction.transaction_fee() as f64 / self.serialized_size() as f64
+    }
+
+    /// Check whether the serialized merkle note has the same note commitment
+    /// as one of the outputs of this transaction, so a claim from an
+    /// untrusted source that a note was created by this transaction can be
+    /// checked. Returns false if the bytes are not a valid merkle note.
+    #[wasm_bindgen(js_name = "containsNote")]
+    pub fn contains_note(&self, merkle_note_bytes: &[u8]) -> bool {
+        let merkle_note = match MerkleNote::read(merkle_note_bytes, SAPLING.clone()) {
+            Ok(merkle_note) => merkle_note,
+            Err(_) => return false,
+        };
+        let commitment = merkle_note.note_commitment();
+        self.transaction
+            .iter_receipts()
+            .any(|receipt| receipt.merkle_note().note_commitment() == commitment)
+    }
+
+    /// Bloom filter over the note commitments of this transaction's outputs
+    /// with roughly the given false positive rate, for light clients to test
+    /// their notes against with `outputFilterContains`. The format is
+    /// described in `output_filter.rs`.
+    #[wasm_bindgen(js_name = "outputFilter")]
+    pub fn output_filter(&self, false_positive_rate: f64) -> Result<Vec<u8>, JsValue> {
+        let commitments: Vec<[u8; 32]> = self
+            .transaction
+            .iter_receipts()
+            .map(|receipt| receipt.merkle_note().note_commitment())
+            .collect();
+        Ok(build_filter(&commitments, false_positive_rate)?)
+    }
+
+    /// The data a light client needs for every output of this transaction,
+    /// as an array of `{ index, commitment, epk }` objects, where `index` is
+    /// the position of the output in the transaction, `commitment` is the
+    /// note commitment and `epk` is the ephemeral public key used for trial
+    /// decryption.
+    #[wasm_bindgen(js_name = "outputSyncData")]
+    pub fn output_sync_data(&self) -> Result<js_sys::Array, JsValue> {
+        let outputs = js_sys::Array::new();
+        for (index, receipt) in self.transaction.iter_receipts().enumerate() {
+            let merkle_note = receipt.merkle_note();
+            let output = js_sys::Object::new();
+            js_sys::Reflect::set(&output, &"index".into(), &JsValue::from(index as u32))?;
+            js_sys::Reflect::set(
+                &output,
+                &"commitment".into(),
+                &js_sys::Uint8Array::from(&merkle_note.note_commitment()[..]),
+            )?;
+            js_sys::Reflect::set(
+                &output,
+                &"epk".into(),
+                &js_sys::Uint8Array::from(&merkle_note.ephemeral_public_key()[..]),
+            )?;
+            outputs.push(&output);
+        }
+        Ok(outputs)
+    }
+
     #[wasm_bindgen(getter, js_name = "spendsLength")]
     pub fn spends_length(&self) -> usize {
         self.transaction.spends().len()
     }
 
+    /// A copy of the spend at `index`. To read only its nullifier, anchor
+    /// or tree size, use `getSpendNullifier`, `getSpendRootHash` or
+    /// `getSpendTreeSize`, which don't copy the proof.
     #[wasm_bindgen(js_name = "getSpend")]
     pub fn get_spend(&self, index: usize) -> WasmSpendProof {
         let proof = &self.transaction.spends()[index];
@@ -69,11 +478,212 @@ impl WasmTransactionPosted {
         }
     }
 
+    /// Same as `getSpend(index).nullifier`.
+    #[wasm_bindgen(js_name = "getSpendNullifier")]
+    pub fn get_spend_nullifier(&self, index: usize) -> Vec<u8> {
+        self.transaction.spends()[index].nullifier().to_vec()
+    }
+
+    /// Same as `getSpend(index).rootHash`.
+    #[wasm_bindgen(js_name = "getSpendRootHash")]
+    pub fn get_spend_root_hash(&self, index: usize) -> Vec<u8> {
+        anchor_of(&self.transaction.spends()[index]).to_vec()
+    }
+
+    /// Same as `getSpend(index).treeSize`.
+    #[wasm_bindgen(js_name = "getSpendTreeSize")]
+    pub fn get_spend_tree_size(&self, index: usize) -> u32 {
+        self.transaction.spends()[index].tree_size()
+    }
+
+    /// Check that the anchor (note tree root) of every spend is in
+    /// `known_roots`.
+    ///
+    /// After a reorg, a wallet can pass the roots of the chain it is now on
+    /// to tell whether a pending transaction can still be mined there, or
+    /// whether it refers to a tree state that only existed on the abandoned
+    /// fork and should be considered dropped. Transactions without spends are
+    /// always valid.
+    #[wasm_bindgen(js_name = "anchorsValidInChain")]
+    pub fn anchors_valid_in_chain(&self, known_roots: &WasmRootSet) -> bool {
+        known_roots.contains_anchors_of(&self.transaction)
+    }
+
+    /// Verify the transaction like `verify`, after checking that the anchor
+    /// of every spend is one of `roots`, an array of 32 byte root hashes of
+    /// the note tree that are valid in the chain.
+    ///
+    /// Throws an error with code `UnknownAnchor` and the index of the first
+    /// spend whose anchor is not in `roots`, or `InvalidRootHash` if a root
+    /// isn't 32 bytes. Otherwise returns the result of `verify`.
+    #[wasm_bindgen(js_name = "verifyWithRoots")]
+    pub fn verify_with_roots(&self, roots: js_sys::Array) -> Result<bool, JsValue> {
+        let mut root_set = WasmRootSet::new();
+        for root in roots.iter() {
+            root_set.add(&js_sys::Uint8Array::new(&root).to_vec())?;
+        }
+        Ok(self.verify_with_root_set(&root_set)?)
+    }
+
+    /// Number of pairing checks `verify()` will perform on this transaction.
+    ///
+    /// Every spend and every receipt carries a groth16 proof, and each proof
+    /// is checked with one pairing check, so this is a cheap upper bound on
+    /// the verification cost that can be computed before calling `verify()`.
+    #[wasm_bindgen(js_name = "pairingCount")]
+    pub fn pairing_count(&self) -> usize {
+        self.transaction.spends().len() + self.transaction.receipts().len()
+    }
+
+    /// Number of value commitments in the value balance equation of this
+    /// transaction: one for every spend, one for every receipt, and one for
+    /// the public transaction fee.
+    #[wasm_bindgen(js_name = "valueCommitmentCount")]
+    pub fn value_commitment_count(&self) -> usize {
+        self.transaction.spends().len() + self.transaction.receipts().len() + 1
+    }
+
+    /// Net value this transaction moves out of the shielded pool, as far as
+    /// anyone without the keys can tell.
+    ///
+    /// All spends and outputs are shielded, so their individual values are
+    /// hidden behind value commitments. The only publicly derivable value is
+    /// the one the binding signature proves: the sum of the spends minus the
+    /// sum of the outputs, which is exactly the transaction fee. It is
+    /// positive for regular transactions (value paid to the miner) and
+    /// negative for miners fee transactions (value created by the block).
+    /// Neither the number of notes nor the size of any individual transfer
+    /// can be learned from it.
+    #[wasm_bindgen(js_name = "publicValueBalance")]
+    pub fn public_value_balance(&self) -> i64 {
+        self.transaction.transaction_fee()
+    }
+
+    /// Serialized sum of the value commitments of the spends minus those of
+    /// the receipts.
+    ///
+    /// It is public, but only commits to the net value of the transaction
+    /// under blinding randomness known to the poster, so it can't be opened
+    /// to learn any value. Clients can check the balance themselves by
+    /// verifying the binding signature against it and `transactionFee`, as
+    /// `verifyValueBalance` does. The first half of `bindingSignatureMessage`
+    /// is this sum with the fee taken out.
+    #[wasm_bindgen(js_name = "postedValueCommitmentSum")]
+    pub fn posted_value_commitment_sum(&self) -> Vec<u8> {
+        self.transaction.value_commitment_sum().to_vec()
+    }
+
+    /// Total value of the outputs of this transaction that can be decrypted
+    /// with the given incoming view key, i.e. the value paid to its owner.
+    ///
+    /// Output values are encrypted, so unlike `transactionFee` and
+    /// `notesLength` this is only available to the holder of the key. Outputs
+    /// belonging to anyone else are left out of the sum.
+    #[wasm_bindgen(js_name = "outputValue")]
+    pub fn output_value(&self, incoming_view_key_hex: &str) -> Result<u64, JsValue> {
+        let incoming_view_key = IncomingViewKey::from_hex(SAPLING.clone(), incoming_view_key_hex)
+            .map_err(WasmSaplingError::from)?;
+
+        let mut total: u128 = 0;
+        for receipt in self.transaction.iter_receipts() {
+            if let Ok(note) = receipt
+                .merkle_note()
+                .decrypt_note_for_owner(&incoming_view_key)
+            {
+                total += note.value() as u128;
+            }
+        }
+
+        let total = u64::try_from(total).map_err(|_| {
+            WasmSaplingError::new("IllegalValue", "Output value overflows u64".into())
+        })?;
+        Ok(total)
+    }
+
+    /// Nullifiers of all the notes spent by this transaction, as an array of
+    /// Uint8Arrays.
+    ///
+    /// The tree positions of spent notes are hidden by the spend proofs, and
+    /// even the spender's key can't recover them without the notes, so
+    /// wallets should match these against the nullifiers of the notes they
+    /// hold (`WasmNote.nullifier`) to find which of them were consumed. No
+    /// key is needed for that, since nullifiers are public.
+    #[wasm_bindgen(js_name = "spentNullifiers")]
+    pub fn spent_nullifiers(&self) -> js_sys::Array {
+        self.transaction
+            .iter_spends()
+            .map(|spend| JsValue::from(js_sys::Uint8Array::from(&spend.nullifier()[..])))
+            .collect()
+    }
+
+    /// Nullifiers spent and note commitments created by this transaction,
+    /// for populating both sides of a block index in one call.
+    #[wasm_bindgen(js_name = "indexEntries")]
+    pub fn index_entries(&self) -> WasmIndexEntries {
+        WasmIndexEntries::from_transaction(&self.transaction)
+    }
+
     #[wasm_bindgen(getter, js_name = "transactionFee")]
     pub fn transaction_fee(&self) -> i64 {
         self.transaction.transaction_fee()
     }
 
+    /// Difference between the fee this transaction actually pays and the
+    /// fee the sender intended to pay (`actual - intended`).
+    ///
+    /// `post` turns everything that isn't the intended fee into change, so
+    /// this is zero for transactions built by this library; anything else
+    /// points at a bug in how change was computed.
+    #[wasm_bindgen(js_name = "feeDelta")]
+    pub fn fee_delta(&self, intended_fee: u64) -> Result<i64, JsValue> {
+        let delta = self.transaction.transaction_fee() as i128 - intended_fee as i128;
+        let delta = i64::try_from(delta).map_err(|_| {
+            WasmSaplingError::new("IllegalValue", "Fee difference overflows i64".into())
+        })?;
+        Ok(delta)
+    }
+
+    /// Net change in the balance of the wallet owning the given view keys
+    /// caused by this transaction.
+    ///
+    /// This is the value of the outputs that can be decrypted with the
+    /// incoming view key, minus the value of the outputs (including change)
+    /// that can be decrypted with the outgoing view key. If any output was
+    /// created by the wallet, the wallet is the sender and the transaction
+    /// fee is subtracted too.
+    #[wasm_bindgen(js_name = "netBalanceDelta")]
+    pub fn net_balance_delta(
+        &self,
+        incoming_view_key_hex: &str,
+        outgoing_view_key_hex: &str,
+    ) -> Result<i64, JsValue> {
+        let incoming_view_key = IncomingViewKey::from_hex(SAPLING.clone(), incoming_view_key_hex)
+            .map_err(WasmSaplingError::from)?;
+        let outgoing_view_key = OutgoingViewKey::from_hex(SAPLING.clone(), outgoing_view_key_hex)
+            .map_err(WasmSaplingError::from)?;
+
+        let mut delta: i128 = 0;
+        let mut is_sender = false;
+        for receipt in self.transaction.iter_receipts() {
+            let merkle_note = receipt.merkle_note();
+            if let Ok(note) = merkle_note.decrypt_note_for_owner(&incoming_view_key) {
+                delta += note.value() as i128;
+            }
+            if let Ok(note) = merkle_note.decrypt_note_for_spender(&outgoing_view_key) {
+                delta -= note.value() as i128;
+                is_sender = true;
+            }
+        }
+        if is_sender {
+            delta -= self.transaction.transaction_fee() as i128;
+        }
+
+        let delta = i64::try_from(delta).map_err(|_| {
+            WasmSaplingError::new("IllegalValue", "Balance change overflows i64".into())
+        })?;
+        Ok(delta)
+    }
+
     #[wasm_bindgen(getter, js_name = "transactionSignature")]
     pub fn transaction_signature(&self) -> Vec<u8> {
         let mut serialized_signature = vec![];
@@ -84,210 +694,1828 @@ impl WasmTransactionPosted {
         serialized_signature
     }
 
+    /// `transactionSignature` as a hex string.
+    #[wasm_bindgen(getter, js_name = "transactionSignatureHex")]
+    pub fn transaction_signature_hex(&self) -> String {
+        to_hex(&self.transaction_signature())
+    }
+
+    /// The exact 64 bytes the binding signature (`transactionSignature`)
+    /// signs: the binding verification key with the fee taken out of it,
+    /// followed by `transactionHash`. External verifiers can check the
+    /// signature as a RedJubjub signature over these bytes.
+    #[wasm_bindgen(js_name = "bindingSignatureMessage")]
+    pub fn binding_signature_message(&self) -> Result<Vec<u8>, JsValue> {
+        let message = self
+            .transaction
+            .binding_signature_message()
+            .map_err(WasmSaplingError::from)?;
+        Ok(message.to_vec())
+    }
+
+    /// Hash of the content of the transaction that the spends and the
+    /// binding signature sign, which excludes the signatures themselves.
+    /// Use `fingerprint` to tell transactions apart.
     #[wasm_bindgen(getter, js_name = "transactionHash")]
     pub fn transaction_hash(&self) -> Vec<u8> {
         self.transaction.transaction_signature_hash().to_vec()
     }
-}
 
-#[wasm_bindgen]
-pub struct WasmTransaction {
-    transaction: ProposedTransaction,
-}
+    /// `transactionHash` as a hex string.
+    #[wasm_bindgen(getter, js_name = "transactionHashHex")]
+    pub fn transaction_hash_hex(&self) -> String {
+        to_hex(&self.transaction.transaction_signature_hash())
+    }
 
-#[wasm_bindgen]
-impl WasmTransaction {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> WasmTransaction {
-        console_error_panic_hook::set_once();
-        WasmTransaction {
-            transaction: ProposedTransaction::new(SAPLING.clone()),
-        }
+    /// 32 byte hash of the whole serialized transaction, signatures
+    /// included, for use as a map key when deduplicating transactions, for
+    /// example in a mempool. It is the same after a round trip through
+    /// `serialize` and `deserialize`.
+    ///
+    /// Unlike `transactionHash`, two transactions with the same content but
+    /// different signatures have different fingerprints.
+    #[wasm_bindgen(getter)]
+    pub fn fingerprint(&self) -> Vec<u8> {
+        self.transaction.fingerprint().to_vec()
     }
 
-    /// Create a proof of a new note owned by the recipient in this transaction.
-    #[wasm_bindgen]
-    pub fn receive(&mut self, spender_hex_key: &str, note: &WasmNote) -> String {
-        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key).unwrap();
-        match self.transaction.receive(&spender_key, &note.note) {
-            Ok(_) => "".into(),
-            Err(e) => match e {
-                ironfish_rust::errors::SaplingProofError::InconsistentWitness => {
-                    "InconsistentWitness".into()
-                }
-                ironfish_rust::errors::SaplingProofError::IOError => "IOError".into(),
-                ironfish_rust::errors::SaplingProofError::ReceiptCircuitProofError => {
-                    "ReceiptCircuitProofError".into()
-                }
-                ironfish_rust::errors::SaplingProofError::SaplingKeyError => {
-                    "SaplingKeyError".into()
-                }
-                ironfish_rust::errors::SaplingProofError::SigningError => "SigningError".into(),
-                ironfish_rust::errors::SaplingProofError::SpendCircuitProofError(d) => {
-                    format!("SpendCircuitProofError - {}", d)
-                }
-                ironfish_rust::errors::SaplingProofError::VerificationFailed => {
-                    "VerificationFailed".into()
-                }
-            },
-        }
+    /// `fingerprint` as a hex string.
+    #[wasm_bindgen(getter, js_name = "fingerprintHex")]
+    pub fn fingerprint_hex(&self) -> String {
+        to_hex(&self.transaction.fingerprint())
     }
 
-    /// Spend the note owned by spender_hex_key at the given witness location.
-    #[wasm_bindgen]
-    pub fn spend(&mut self, spender_hex_key: &str, note: &WasmNote, witness: &JsWitness) -> String {
-        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key).unwrap();
-        match self.transaction.spend(spender_key, &note.note, witness) {
-            Ok(_) => "".into(),
-            Err(e) => match e {
-                ironfish_rust::errors::SaplingProofError::InconsistentWitness => {
-                    "InconsistentWitness".into()
-                }
-                ironfish_rust::errors::SaplingProofError::IOError => "IOError".into(),
-                ironfish_rust::errors::SaplingProofError::ReceiptCircuitProofError => {
-                    "ReceiptCircuitProofError".into()
-                }
-                ironfish_rust::errors::SaplingProofError::SaplingKeyError => {
-                    "SaplingKeyError".into()
-                }
-
-                ironfish_rust::errors::SaplingProofError::SigningError => "SigningError".into(),
-                ironfish_rust::errors::SaplingProofError::SpendCircuitProofError(d) => {
-                    format!("SpendCircuitProofError - {}", d)
-                }
-                ironfish_rust::errors::SaplingProofError::VerificationFailed => {
-                    "VerificationFailed".into()
-                }
-            },
-        }
+    /// JSON description of the transaction in a canonical form, so that two
+    /// serializations of the same transaction are byte for byte identical
+    /// and can be hashed:
+    ///
+    /// * object keys are sorted alphabetically
+    /// * there is no whitespace
+    /// * byte strings are lowercase hex without a prefix
+    /// * integers are plain decimal
+    ///
+    /// The output has the shape
+    /// `{"bindingSignature":hex,"expirationSequence":int,"fee":int,"receipts":[{"commitment":hex,"serialized":hex}],"spends":[{"nullifier":hex,"rootHash":hex,"serialized":hex,"treeSize":int}]}`
+    /// where `serialized` holds the full bytes of each spend or receipt as
+    /// they appear in `serialize()`.
+    #[wasm_bindgen(js_name = "toCanonicalJSON")]
+    pub fn to_canonical_json(&self) -> String {
+        let spends: Vec<String> = self
+            .transaction
+            .iter_spends()
+            .map(|spend| {
+                let mut root_hash = vec![];
+                MerkleNoteHash::new(spend.root_hash())
+                    .write(&mut root_hash)
+                    .unwrap();
+                let mut serialized = vec![];
+                spend.write(&mut serialized).unwrap();
+                format!(
+                    r#"{{"nullifier":"{}","rootHash":"{}","serialized":"{}","treeSize":{}}}"#,
+                    to_hex(&spend.nullifier()),
+                    to_hex(&root_hash),
+                    to_hex(&serialized),
+                    spend.tree_size()
+                )
+            })
+            .collect();
+        let receipts: Vec<String> = self
+            .transaction
+            .iter_receipts()
+            .map(|receipt| {
+                let mut serialized = vec![];
+                receipt.write(&mut serialized).unwrap();
+                format!(
+                    r#"{{"commitment":"{}","serialized":"{}"}}"#,
+                    to_hex(&receipt.merkle_note().note_commitment()),
+                    to_hex(&serialized)
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"bindingSignature":"{}","expirationSequence":{},"fee":{},"receipts":[{}],"spends":[{}]}}"#,
+            to_hex(&self.transaction_signature()),
+            self.transaction.expiration_sequence(),
+            self.transaction.transaction_fee(),
+            receipts.join(","),
+            spends.join(",")
+        )
     }
+}
 
-    /// Special case for posting a miners fee transaction. Miner fee transactions
-    /// are unique in that they generate currency. They do not have any spends
-    /// or change and therefore have a negative transaction fee. In normal use,
-    /// a miner would not accept such a transaction unless it was explicitly set
-    /// as the miners fee.
-    #[wasm_bindgen]
-    pub fn post_miners_fee(&mut self) -> WasmTransactionPosted {
-        WasmTransactionPosted {
-            transaction: self.transaction.post_miners_fee().unwrap(),
+impl WasmTransactionPosted {
+    fn serialized_notes(&self) -> Vec<Vec<u8>> {
+        self.transaction
+            .iter_receipts()
+            .map(merkle_note_bytes)
+            .collect()
+    }
+
+    fn verify_with_root_set(&self, roots: &WasmRootSet) -> Result<bool, WasmSaplingError> {
+        if let Some(index) = self
+            .transaction
+            .iter_spends()
+            .position(|spend| !roots.contains_root(&anchor_of(spend)))
+        {
+            return Err(WasmSaplingError::new(
+                "UnknownAnchor",
+                "Spend anchor is not a known root".into(),
+            )
+            .at_index(index));
         }
+        Ok(self.verify())
     }
 
-    /// Post the transaction. This performs a bit of validation, and signs
-    /// the spends with a signature that proves the spends are part of this
-    /// transaction.
-    ///
-    /// Transaction fee is the amount the spender wants to send to the miner
-    /// for mining this transaction. This has to be non-negative; sane miners
-    /// wouldn't accept a transaction that takes money away from them.
-    ///
-    /// sum(spends) - sum(outputs) - intended_transaction_fee - change = 0
-    /// aka: self.transaction_fee - intended_transaction_fee - change = 0
-    #[wasm_bindgen]
-    pub fn post(
-        &mut self,
-        spender_hex_key: &str,
-        change_goes_to: Option<String>,
-        intended_transaction_fee: u64,
-    ) -> WasmTransactionPosted {
-        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key).unwrap();
-        let change_key = match change_goes_to {
-            Some(s) => Some(PublicAddress::from_hex(SAPLING.clone(), &s).unwrap()),
-            None => None,
-        };
-        WasmTransactionPosted {
-            transaction: self
-                .transaction
-                .post(&spender_key, change_key, intended_transaction_fee)
-                .unwrap(),
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, WasmSaplingError> {
+        let mut bytes = Vec::with_capacity(self.transaction.serialized_size());
+        self.transaction
+            .write(&mut bytes)
+            .map_err(|e| WasmSaplingError::new("IOError", e.to_string()))?;
+        Ok(bytes)
+    }
+}
+
+/// Proof verifier backed by a javascript callback, see
+/// `WasmTransactionPosted.verifyWith`
+struct JsProofVerifier<'a> {
+    callback: &'a js_sys::Function,
+}
+
+impl JsProofVerifier<'_> {
+    fn call(
+        &self,
+        kind: &str,
+        proof: Vec<u8>,
+        public_inputs: Vec<u8>,
+    ) -> Result<(), SaplingProofError> {
+        let result = self.callback.call3(
+            &JsValue::NULL,
+            &JsValue::from(kind),
+            &js_sys::Uint8Array::from(&proof[..]),
+            &js_sys::Uint8Array::from(&public_inputs[..]),
+        );
+        match result {
+            Ok(valid) if valid.as_bool() == Some(true) => Ok(()),
+            _ => Err(SaplingProofError::VerificationFailed),
         }
     }
 }
 
-impl Default for WasmTransaction {
-    fn default() -> Self {
-        WasmTransaction::new()
+impl ProofVerifier<Bls12> for JsProofVerifier<'_> {
+    fn verify_spend_proof(
+        &self,
+        _sapling: &Sapling,
+        spend: &SpendProof,
+    ) -> Result<(), SaplingProofError> {
+        let mut proof = vec![];
+        spend.proof().write(&mut proof)?;
+        self.call(
+            "spend",
+            proof,
+            serialize_public_inputs(&spend.public_inputs()),
+        )
+    }
+
+    fn verify_receipt_proof(
+        &self,
+        _sapling: &Sapling,
+        receipt: &ReceiptProof,
+    ) -> Result<(), SaplingProofError> {
+        let mut proof = vec![];
+        receipt.proof().write(&mut proof)?;
+        self.call(
+            "receipt",
+            proof,
+            serialize_public_inputs(&receipt.public_inputs()),
+        )
     }
 }
 
-#[wasm_bindgen]
-pub struct WasmSimpleTransaction {
-    transaction: SimpleTransaction,
+fn merkle_note_bytes(receipt: &ReceiptProof) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(MERKLE_NOTE_SIZE);
+    receipt.merkle_note().write(&mut bytes).unwrap();
+    bytes
 }
 
-#[wasm_bindgen]
-impl WasmSimpleTransaction {
-    #[wasm_bindgen(constructor)]
-    pub fn new(spender_hex_key: &str, intended_transaction_fee: u64) -> WasmSimpleTransaction {
-        console_error_panic_hook::set_once();
-        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key).unwrap();
-        WasmSimpleTransaction {
-            transaction: SimpleTransaction::new(
-                SAPLING.clone(),
-                spender_key,
-                intended_transaction_fee,
+fn read_transaction(bytes: &[u8], allow_trailing: bool) -> Result<Transaction, WasmSaplingError> {
+    let (transaction, consumed) = read_transaction_from(bytes, bytes.len())?;
+    if !allow_trailing && consumed != bytes.len() {
+        return Err(WasmSaplingError::new(
+            "TrailingBytes",
+            format!(
+                "Transaction is {} bytes, but {} bytes were given",
+                consumed,
+                bytes.len()
             ),
-        }
+        ));
     }
+    Ok(transaction)
+}
 
-    #[wasm_bindgen]
-    pub fn spend(&mut self, note: &WasmNote, witness: &JsWitness) -> String {
-        match self.transaction.spend(&note.note, witness) {
-            Ok(_) => "".into(),
-            Err(e) => match e {
-                ironfish_rust::errors::SaplingProofError::InconsistentWitness => {
-                    "InconsistentWitness".into()
-                }
-                ironfish_rust::errors::SaplingProofError::IOError => "IOError".into(),
-                ironfish_rust::errors::SaplingProofError::ReceiptCircuitProofError => {
-                    "ReceiptCircuitProofError".into()
-                }
-                ironfish_rust::errors::SaplingProofError::SaplingKeyError => {
-                    "SaplingKeyError".into()
-                }
-
-                ironfish_rust::errors::SaplingProofError::SigningError => "SigningError".into(),
-                ironfish_rust::errors::SaplingProofError::SpendCircuitProofError(d) => {
-                    format!("SpendCircuitProofError - {}", d)
-                }
-                ironfish_rust::errors::SaplingProofError::VerificationFailed => {
-                    "VerificationFailed".into()
-                }
-            },
-        }
+/// Read a transaction from `reader`, which holds `length` bytes in total,
+/// returning it along with the number of bytes it took up.
+///
+/// The spend and receipt readers don't keep the underlying io errors, so a
+/// failure is described by how far reading got: running out of bytes means
+/// the transaction was truncated, stopping earlier means some point, scalar
+/// or proof in it could not be decoded.
+fn read_transaction_from<R: io::Read>(
+    reader: R,
+    length: usize,
+) -> Result<(Transaction, usize), WasmSaplingError> {
+    let mut reader = CountingReader {
+        inner: reader,
+        count: 0,
+    };
+    match Transaction::read(SAPLING.clone(), &mut reader) {
+        Ok(transaction) => Ok((transaction, reader.count)),
+        Err(e @ TransactionError::UnsupportedVersion(_)) => Err(e.into()),
+        Err(e) if reader.count >= length => Err(WasmSaplingError::new(
+            "UnexpectedEof",
+            format!(
+                "Transaction is truncated, ran out of bytes after {} bytes: {:?}",
+                length, e
+            ),
+        )),
+        Err(e) => Err(WasmSaplingError::new(
+            "InvalidEncoding",
+            format!(
+                "Could not decode the transaction before byte {}; a point, scalar or \
+                 proof is invalid, or was created with different SAPLING parameters: {:?}",
+                reader.count, e
+            ),
+        )),
     }
+}
 
-    #[wasm_bindgen]
-    pub fn receive(&mut self, note: &WasmNote) -> String {
-        match self.transaction.receive(&note.note) {
-            Ok(_) => "".into(),
-            Err(e) => match e {
-                ironfish_rust::errors::SaplingProofError::InconsistentWitness => {
-                    "InconsistentWitness".into()
-                }
-                ironfish_rust::errors::SaplingProofError::IOError => "IOError".into(),
-                ironfish_rust::errors::SaplingProofError::ReceiptCircuitProofError => {
-                    "ReceiptCircuitProofError".into()
-                }
-                ironfish_rust::errors::SaplingProofError::SaplingKeyError => {
-                    "SaplingKeyError".into()
-                }
-                ironfish_rust::errors::SaplingProofError::SigningError => "SigningError".into(),
-                ironfish_rust::errors::SaplingProofError::SpendCircuitProofError(d) => {
-                    format!("SpendCircuitProofError - {}", d)
-                }
-                ironfish_rust::errors::SaplingProofError::VerificationFailed => {
-                    "VerificationFailed".into()
-                }
-            },
-        }
+/// Reader that keeps track of how many bytes were read through it.
+struct CountingReader<R: io::Read> {
+    inner: R,
+    count: usize,
+}
+
+impl<R: io::Read> io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read;
+        Ok(read)
     }
+}
 
-    #[wasm_bindgen]
-    pub fn post(&mut self) -> WasmTransactionPosted {
-        WasmTransactionPosted {
-            transaction: self.transaction.post().unwrap(),
-        }
+fn read_transaction_with_checksum(bytes: &[u8]) -> Result<Transaction, WasmSaplingError> {
+    if bytes.len() < CHECKSUM_SIZE {
+        return Err(WasmSaplingError::new(
+            "ChecksumMismatch",
+            format!("Expected at least {} bytes", CHECKSUM_SIZE),
+        ));
+    }
+    let (transaction_bytes, expected) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+    if checksum(transaction_bytes)[..] != *expected {
+        return Err(WasmSaplingError::new(
+            "ChecksumMismatch",
+            "Transaction bytes do not match their checksum".into(),
+        ));
+    }
+    read_transaction(transaction_bytes, false)
+}
+
+/// Truncated blake2b hash of the serialized transaction.
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_SIZE] {
+    let mut checksum = [0; CHECKSUM_SIZE];
+    checksum.copy_from_slice(
+        Blake2b::new()
+            .hash_length(CHECKSUM_SIZE)
+            .personal(CHECKSUM_PERSONALIZATION)
+            .hash(bytes)
+            .as_bytes(),
+    );
+    checksum
+}
+
+/// Sign a transaction serialized with `WasmTransaction.serializeUnsigned`
+/// with the given spending key.
+///
+/// Throws an error with code `IOError` if the bytes aren't exactly one
+/// unsigned transaction, and with code `SigningError` if its spends weren't
+/// created with the key.
+#[wasm_bindgen(js_name = "signOffline")]
+pub fn sign_offline_transaction(
+    unsigned: &[u8],
+    spender_hex_key: &str,
+) -> Result<WasmTransactionPosted, JsValue> {
+    Ok(sign_unsigned(unsigned, spender_hex_key)?)
+}
+
+fn sign_unsigned(
+    unsigned: &[u8],
+    spender_hex_key: &str,
+) -> Result<WasmTransactionPosted, WasmSaplingError> {
+    let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key)?;
+    let transaction = sign_offline(SAPLING.clone(), unsigned, &spender_key)?;
+    Ok(WasmTransactionPosted { transaction })
+}
+
+fn read_draft(bytes: &[u8], spender_hex_key: &str) -> Result<WasmTransaction, WasmSaplingError> {
+    let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key)?;
+    let transaction = ProposedTransaction::read_draft(SAPLING.clone(), &spender_key, bytes)?;
+    Ok(WasmTransaction {
+        transaction,
+        pending_post: None,
+    })
+}
+
+/// Check that the fee on a block's miners fee transaction balances the fees
+/// on the other transactions in the block plus the block subsidy.
+///
+/// `fees` holds the `transactionFee` of every other transaction in the block.
+#[wasm_bindgen(js_name = "verifyBlockFeeBalance")]
+pub fn verify_block_fee_balance(
+    coinbase: &WasmTransactionPosted,
+    fees: &[i64],
+    subsidy: u64,
+) -> bool {
+    block_fees_balance(coinbase.transaction.transaction_fee(), fees, subsidy)
+}
+
+/// Verify a batch of serialized transactions, such as all the transactions
+/// of a block, in one call.
+///
+/// `batch` is the concatenation of the serialized transactions and `lengths`
+/// holds the length of each of them, in order. The result is a bitmap with
+/// one bit per transaction, least significant bit of the first byte first,
+/// that is set if the transaction deserialized and verified. A bad entry
+/// only clears its own bit; an error with code `LengthMismatch` is thrown
+/// only if `lengths` doesn't add up to the size of `batch`.
+#[wasm_bindgen(js_name = "verifyTransactions")]
+pub fn verify_transactions(batch: &[u8], lengths: &[u32]) -> Result<Vec<u8>, JsValue> {
+    Ok(verify_batch(batch, lengths)?)
+}
+
+fn verify_batch(batch: &[u8], lengths: &[u32]) -> Result<Vec<u8>, WasmSaplingError> {
+    let transactions = read_batch(batch, lengths)?;
+    Ok(batch_bitmap(transactions.iter().map(|transaction| {
+        transaction
+            .as_ref()
+            .map_or(false, |transaction| transaction.verify().is_ok())
+    })))
+}
+
+/// Split `batch` into the transactions described by `lengths` and read each
+/// of them, with `None` in place of any entry that can't be read.
+pub(super) fn read_batch(
+    batch: &[u8],
+    lengths: &[u32],
+) -> Result<Vec<Option<Transaction>>, WasmSaplingError> {
+    Ok(split_batch(batch, lengths)?
+        .map(|bytes| read_transaction(bytes, false).ok())
+        .collect())
+}
+
+/// The serialized transactions in `batch`, after checking that `lengths`
+/// adds up to its size.
+fn split_batch<'a>(
+    batch: &'a [u8],
+    lengths: &'a [u32],
+) -> Result<impl Iterator<Item = &'a [u8]>, WasmSaplingError> {
+    let total: u64 = lengths.iter().map(|length| *length as u64).sum();
+    if total != batch.len() as u64 {
+        return Err(WasmSaplingError::new(
+            "LengthMismatch",
+            format!(
+                "Lengths add up to {} bytes but the batch is {} bytes",
+                total,
+                batch.len()
+            ),
+        ));
+    }
+
+    let mut offset = 0;
+    Ok(lengths.iter().map(move |length| {
+        let end = offset + *length as usize;
+        let bytes = &batch[offset..end];
+        offset = end;
+        bytes
+    }))
+}
+
+/// Verify the transactions of a block, laid out as for `verifyTransactions`,
+/// in order, stopping at the first one that can't be read or doesn't
+/// verify, or that spends a note an earlier one already spent. Use this
+/// when only the validity of the whole block matters. The fees are not
+/// checked; use `verifyMinersFee` on the block's miners fee transaction.
+///
+/// The error thrown for the failing transaction has the code of the reason,
+/// as for `verifyDetailed` or `deserialize`, and its message starts with
+/// the index of the transaction in the batch. An error with code
+/// `LengthMismatch` is thrown before anything is verified if `lengths`
+/// doesn't add up to the size of `batch`.
+#[wasm_bindgen(js_name = "verifyBlock")]
+pub fn verify_block(batch: &[u8], lengths: &[u32]) -> Result<(), JsValue> {
+    Ok(verify_block_batch(batch, lengths)?)
+}
+
+fn verify_block_batch(batch: &[u8], lengths: &[u32]) -> Result<(), WasmSaplingError> {
+    let mut spent = HashSet::new();
+    for (index, bytes) in split_batch(batch, lengths)?.enumerate() {
+        let transaction = read_transaction(bytes, false).map_err(|e| e.at_index(index))?;
+        transaction
+            .verify_detailed()
+            .map_err(|e| WasmSaplingError::from(e).at_index(index))?;
+        for spend in transaction.spends() {
+            if !spent.insert(spend.nullifier()) {
+                return Err(
+                    WasmSaplingError::from(VerificationError::DuplicateNullifier).at_index(index),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Pack one bit per entry, least significant bit of the first byte first, as
+/// returned by `verifyTransactions`.
+pub(super) fn batch_bitmap<I: IntoIterator<Item = bool>>(valid: I) -> Vec<u8> {
+    let mut bitmap = vec![];
+    for (index, valid) in valid.into_iter().enumerate() {
+        if index % 8 == 0 {
+            bitmap.push(0);
+        }
+        if valid {
+            bitmap[index / 8] |= 1 << (index % 8);
+        }
+    }
+    bitmap
+}
+
+/// Read every transaction in `bytes`, which holds any number of serialized
+/// transactions back to back, without needing to know their lengths.
+///
+/// Throws the same errors as `WasmTransactionPosted.deserialize`, with the
+/// index of the transaction that could not be read.
+#[wasm_bindgen(js_name = "deserializeTransactions")]
+pub fn deserialize_transactions(bytes: &[u8]) -> Result<js_sys::Array, JsValue> {
+    let array = js_sys::Array::new();
+    for transaction in read_transactions(bytes)? {
+        array.push(&JsValue::from(WasmTransactionPosted { transaction }));
+    }
+    Ok(array)
+}
+
+fn read_transactions(bytes: &[u8]) -> Result<Vec<Transaction>, WasmSaplingError> {
+    let mut transactions = vec![];
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let (transaction, consumed) = read_transaction_from(&bytes[offset..], bytes.len() - offset)
+            .map_err(|e| e.at_index(transactions.len()))?;
+        transactions.push(transaction);
+        offset += consumed;
+    }
+    Ok(transactions)
+}
+
+#[wasm_bindgen]
+pub struct WasmTransaction {
+    transaction: ProposedTransaction,
+    pending_post: Option<PendingPost>,
+}
+
+/// Arguments given to `startPost`, kept for the calls that follow it.
+struct PendingPost {
+    spender_key: Key,
+    change_goes_to: Option<PublicAddress>,
+    intended_transaction_fee: u64,
+    completed: usize,
+    total: usize,
+}
+
+#[wasm_bindgen]
+impl WasmTransaction {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmTransaction {
+        console_error_panic_hook::set_once();
+        WasmTransaction {
+            transaction: ProposedTransaction::new(SAPLING.clone()),
+            pending_post: None,
+        }
+    }
+
+    /// Create a transaction with room for `spends` spends and `outputs`
+    /// outputs, so adding them doesn't reallocate. Count the change output
+    /// `post` may add as well.
+    #[wasm_bindgen(js_name = "withCapacity")]
+    pub fn with_capacity(spends: usize, outputs: usize) -> WasmTransaction {
+        console_error_panic_hook::set_once();
+        WasmTransaction {
+            transaction: ProposedTransaction::with_capacity(SAPLING.clone(), spends, outputs),
+            pending_post: None,
+        }
+    }
+
+    /// Set the sequence of the last block the transaction can be mined in.
+    /// 0, the default, means the transaction never expires.
+    #[wasm_bindgen(js_name = "setExpiration")]
+    pub fn set_expiration(&mut self, sequence: u32) {
+        self.transaction.set_expiration_sequence(sequence);
+    }
+
+    /// Drop every spend and output, along with any post started with
+    /// `startPost`, returning the transaction to the state the constructor
+    /// creates. The memory already allocated is kept, so reusing one
+    /// transaction while trying candidate sets of notes avoids allocating a
+    /// new one for each attempt.
+    #[wasm_bindgen]
+    pub fn reset(&mut self) {
+        self.transaction.reset();
+        self.pending_post = None;
+    }
+
+    /// Create a proof of a new note owned by the recipient in this transaction.
+    #[wasm_bindgen]
+    pub fn receive(&mut self, spender_hex_key: &str, note: &WasmNote) -> Result<(), JsValue> {
+        let spender_key =
+            Key::from_hex(SAPLING.clone(), spender_hex_key).map_err(WasmSaplingError::from)?;
+        self.transaction
+            .receive(&spender_key, &note.note)
+            .map_err(WasmSaplingError::from)?;
+        Ok(())
+    }
+
+    /// Spend the note owned by spender_hex_key at the given witness location.
+    #[wasm_bindgen]
+    pub fn spend(
+        &mut self,
+        spender_hex_key: &str,
+        note: &WasmNote,
+        witness: &JsWitness,
+    ) -> Result<(), JsValue> {
+        Ok(self.spend_note(spender_hex_key, note, witness)?)
+    }
+
+    /// Spend several notes owned by spender_hex_key in a single call.
+    ///
+    /// `notes` is an array of serialized notes (as produced by
+    /// `WasmNote.serialize`) and `witnesses` is an array of witnesses for
+    /// those notes, in the same order. The arrays must be the same length.
+    /// Spending stops at the first failure, and the returned error includes
+    /// the index of the note that could not be spent. Spends that were
+    /// added before the failure remain on the transaction.
+    #[wasm_bindgen(js_name = "spendMany")]
+    pub fn spend_many(
+        &mut self,
+        spender_hex_key: &str,
+        notes: js_sys::Array,
+        witnesses: js_sys::Array,
+    ) -> Result<(), JsValue> {
+        if notes.length() != witnesses.length() {
+            return Err(WasmSaplingError::new(
+                "LengthMismatch",
+                format!(
+                    "Got {} notes but {} witnesses",
+                    notes.length(),
+                    witnesses.length()
+                ),
+            )
+            .into());
+        }
+
+        let spender_key =
+            Key::from_hex(SAPLING.clone(), spender_hex_key).map_err(WasmSaplingError::from)?;
+
+        for (index, (note, witness)) in notes.iter().zip(witnesses.iter()).enumerate() {
+            let note_bytes = js_sys::Uint8Array::new(&note).to_vec();
+            let note = Note::read(&note_bytes[..], SAPLING.clone())
+                .map_err(|e| WasmSaplingError::from(e).at_index(index))?;
+            let witness: JsWitness = witness.unchecked_into();
+
+            self.transaction
+                .spend(spender_key.clone(), &note, &witness)
+                .map_err(|e| WasmSaplingError::from(e).at_index(index))?;
+        }
+
+        Ok(())
+    }
+
+    /// Create proofs of several new notes in a single call.
+    ///
+    /// `notes` is an array of serialized notes (as produced by
+    /// `WasmNote.serialize`). Receiving stops at the first failure, and the
+    /// returned error includes the index of the note that could not be
+    /// received. Outputs that were added before the failure remain on the
+    /// transaction.
+    #[wasm_bindgen(js_name = "receiveMany")]
+    pub fn receive_many(
+        &mut self,
+        spender_hex_key: &str,
+        notes: js_sys::Array,
+    ) -> Result<(), JsValue> {
+        let notes: Vec<Vec<u8>> = notes
+            .iter()
+            .map(|note| js_sys::Uint8Array::new(&note).to_vec())
+            .collect();
+        Ok(self.receive_serialized_notes(spender_hex_key, &notes)?)
+    }
+
+    /// Check whether any output added so far has one of the given note
+    /// commitments (an array of Uint8Arrays), meaning the transaction would
+    /// create a note the wallet already holds, for example when a send is
+    /// retried.
+    #[wasm_bindgen(js_name = "wouldDuplicate")]
+    pub fn would_duplicate(&self, existing_commitments: js_sys::Array) -> bool {
+        let existing_commitments: Vec<Vec<u8>> = existing_commitments
+            .iter()
+            .map(|existing| js_sys::Uint8Array::new(&existing).to_vec())
+            .collect();
+        self.has_output_commitment(&existing_commitments)
+    }
+
+    /// Randomness of the value commitment of the spend at `index`, in the
+    /// order spends were added, or undefined if there is no such spend.
+    ///
+    /// Notes don't store it, and it can't be recovered once the transaction
+    /// is posted. It is secret material: anyone holding it can learn the
+    /// value of the note from the posted transaction.
+    #[wasm_bindgen(js_name = "spendValueCommitmentRandomness")]
+    pub fn spend_value_commitment_randomness(&self, index: usize) -> Option<Vec<u8>> {
+        self.transaction
+            .spend_value_commitment_randomness()
+            .get(index)
+            .map(|randomness| randomness.to_vec())
+    }
+
+    /// Same as `spendValueCommitmentRandomness`, for the output at `index`.
+    #[wasm_bindgen(js_name = "outputValueCommitmentRandomness")]
+    pub fn output_value_commitment_randomness(&self, index: usize) -> Option<Vec<u8>> {
+        self.transaction
+            .receipt_value_commitment_randomness()
+            .get(index)
+            .map(|randomness| randomness.to_vec())
+    }
+
+    /// Check the proofs of the spends and outputs added so far, before the
+    /// transaction is posted, so a bad contribution can be rejected early.
+    #[wasm_bindgen(js_name = "verifyGeneratedProofs")]
+    pub fn verify_generated_proofs(&self) -> bool {
+        self.transaction.verify_proofs().is_ok()
+    }
+
+    /// Estimate how many more outputs this transaction can hold while paying
+    /// at least `target_rate` in fees per serialized byte out of
+    /// `fee_budget`.
+    ///
+    /// Spends and outputs added so far are taken into account, every extra
+    /// output is assumed to add one fixed size receipt, and a change output
+    /// is not reserved, so callers that expect change should subtract one.
+    #[wasm_bindgen(js_name = "outputsForFeeRate")]
+    pub fn outputs_for_fee_rate(&self, target_rate: f64, fee_budget: u64) -> usize {
+        self.transaction
+            .outputs_for_fee_rate(target_rate, fee_budget)
+    }
+
+    /// Fee implied by the spends and outputs added so far: the sum of the
+    /// spent values minus the sum of the output values. Posting with an
+    /// intended fee lower than this sends the difference back as change, and
+    /// posting with a higher one fails. Cheap to call after every change,
+    /// since no proofs are generated.
+    #[wasm_bindgen(js_name = "estimateFee")]
+    pub fn estimate_fee(&self) -> i64 {
+        self.transaction.transaction_fee()
+    }
+
+    /// Hash of the spends, outputs, fee and expiration added so far: the
+    /// message an external signer signs. It is the `transactionHash` the
+    /// posted transaction will have, as long as nothing else is added.
+    /// Adding a spend or output, setting the expiration, posting with change,
+    /// or posting as a miners fee all change the hash, so it has to be
+    /// computed again afterwards.
+    #[wasm_bindgen(js_name = "signatureHash")]
+    pub fn signature_hash(&self) -> Vec<u8> {
+        self.transaction.transaction_signature_hash().to_vec()
+    }
+
+    /// Serialize the transaction without signing it, to be signed with
+    /// `signOffline` on a machine that holds the spending key.
+    ///
+    /// Everything the spends leave after the outputs becomes the fee, so
+    /// add change as an output with `receive` first. Throws an error with
+    /// code `InvalidBalanceError` if the outputs are worth more than the
+    /// spends.
+    #[wasm_bindgen(js_name = "serializeUnsigned")]
+    pub fn serialize_unsigned(&self) -> Result<Vec<u8>, JsValue> {
+        Ok(self.unsigned_bytes()?)
+    }
+
+    /// Save the transaction as it stands, with its spends and outputs, so it
+    /// can be picked up again with `deserializeDraft` after the wallet
+    /// restarts. The spending key is not saved, but the draft reveals the
+    /// values of the notes, so store it like the wallet itself.
+    #[wasm_bindgen(js_name = "serializeDraft")]
+    pub fn serialize_draft(&self) -> Result<Vec<u8>, JsValue> {
+        Ok(self.draft_bytes()?)
+    }
+
+    /// Load a transaction saved with `serializeDraft`. The spending key must
+    /// be the one the spends were created with. Throws an error with code
+    /// `SigningError` if it isn't, and with code `IOError` if the bytes
+    /// aren't a draft.
+    #[wasm_bindgen(js_name = "deserializeDraft")]
+    pub fn deserialize_draft(
+        bytes: &[u8],
+        spender_hex_key: &str,
+    ) -> Result<WasmTransaction, JsValue> {
+        console_error_panic_hook::set_once();
+        Ok(read_draft(bytes, spender_hex_key)?)
+    }
+
+    /// Special case for posting a miners fee transaction. Miner fee transactions
+    /// are unique in that they generate currency. They do not have any spends
+    /// or change and therefore have a negative transaction fee. In normal use,
+    /// a miner would not accept such a transaction unless it was explicitly set
+    /// as the miners fee.
+    #[wasm_bindgen]
+    pub fn post_miners_fee(&mut self) -> WasmTransactionPosted {
+        WasmTransactionPosted {
+            transaction: self.transaction.post_miners_fee().unwrap(),
+        }
+    }
+
+    /// Post the transaction. This performs a bit of validation, and signs
+    /// the spends with a signature that proves the spends are part of this
+    /// transaction.
+    ///
+    /// Transaction fee is the amount the spender wants to send to the miner
+    /// for mining this transaction. This has to be non-negative; sane miners
+    /// wouldn't accept a transaction that takes money away from them.
+    ///
+    /// sum(spends) - sum(outputs) - intended_transaction_fee - change = 0
+    /// aka: self.transaction_fee - intended_transaction_fee - change = 0
+    ///
+    /// Throws an error with code `InvalidBalanceError` if the spends don't
+    /// cover the outputs and the fee. This is checked before the proof for
+    /// the change note is created.
+    ///
+    /// If `progress` is given, it is called as `progress(completed, total)`
+    /// after each step of posting: the proof of the change note, the binding
+    /// signature, and the signature of each spend. The proofs of the spends
+    /// and outputs are created by `spend` and `receive`, before this is
+    /// called. Errors thrown by `progress` are ignored.
+    #[wasm_bindgen]
+    pub fn post(
+        &mut self,
+        spender_hex_key: &str,
+        change_goes_to: Option<String>,
+        intended_transaction_fee: u64,
+        progress: Option<js_sys::Function>,
+    ) -> Result<WasmTransactionPosted, JsValue> {
+        let report_progress = |completed: usize, total: usize| {
+            if let Some(progress) = &progress {
+                let _ = progress.call2(
+                    &JsValue::NULL,
+                    &JsValue::from(completed as u32),
+                    &JsValue::from(total as u32),
+                );
+            }
+        };
+        Ok(self.post_with_fee(
+            spender_hex_key,
+            change_goes_to,
+            intended_transaction_fee,
+            report_progress,
+        )?)
+    }
+
+    /// The change note `post` would add when called with the same hex encoded
+    /// `change_goes_to` address and fee, without creating its proof, or
+    /// undefined if there is no change. Use it to show what will come back as
+    /// change before posting; only its value and owner match the note `post`
+    /// creates. The address is required because the spender's default address
+    /// differs on every call, so pass the same one to `post`.
+    ///
+    /// Throws an error with code `InvalidBalanceError` if the spends don't
+    /// cover the outputs and the fee.
+    #[wasm_bindgen(js_name = "previewChange")]
+    pub fn preview_change(
+        &self,
+        change_goes_to: &str,
+        intended_transaction_fee: u64,
+    ) -> Result<Option<WasmNote>, JsValue> {
+        Ok(self.change_preview(change_goes_to, intended_transaction_fee)?)
+    }
+
+    /// Post the transaction like `post`, splitting the change across several
+    /// notes. `changes` is an array of `{ address, value }` objects, with hex
+    /// encoded addresses and number values, whose values must add up to
+    /// exactly what the spends leave after the outputs and the fee.
+    ///
+    /// Throws an error with code `InvalidChangeOutput` and the index of the
+    /// entry if an entry is malformed, and with code `InvalidBalanceError`
+    /// if the values don't balance.
+    #[wasm_bindgen(js_name = "postWithChangeOutputs")]
+    pub fn post_with_change_outputs(
+        &mut self,
+        spender_hex_key: &str,
+        changes: js_sys::Array,
+        intended_transaction_fee: u64,
+    ) -> Result<WasmTransactionPosted, JsValue> {
+        let changes = read_change_outputs(&changes)?;
+        Ok(self.post_with_changes(spender_hex_key, &changes, intended_transaction_fee)?)
+    }
+
+    /// Start posting the transaction a step at a time, so javascript can
+    /// yield to the event loop between steps instead of blocking on `post`.
+    /// The arguments are the same as those of `post`.
+    ///
+    /// Call `postStep` until the progress it returns is `done`, then
+    /// `finishPost`. Only the proof of the change note is left to create
+    /// when posting; the proofs of the spends and outputs are created by
+    /// `spend` and `receive`, so awaiting between those calls keeps the rest
+    /// of building a transaction responsive.
+    ///
+    /// Throws an error with code `InvalidBalanceError` if the spends don't
+    /// cover the outputs and the fee. Calling this again starts over.
+    #[wasm_bindgen(js_name = "startPost")]
+    pub fn start_post(
+        &mut self,
+        spender_hex_key: &str,
+        change_goes_to: Option<String>,
+        intended_transaction_fee: u64,
+    ) -> Result<WasmPostProgress, JsValue> {
+        Ok(self.start_pending_post(spender_hex_key, change_goes_to, intended_transaction_fee)?)
+    }
+
+    /// Create the next proof needed to post the transaction.
+    ///
+    /// Throws an error with code `PostNotStarted` if `startPost` wasn't
+    /// called first.
+    #[wasm_bindgen(js_name = "postStep")]
+    pub fn post_step(&mut self) -> Result<WasmPostProgress, JsValue> {
+        Ok(self.next_post_step()?)
+    }
+
+    /// Sign the transaction once `postStep` reports it is `done`, and return
+    /// it posted.
+    ///
+    /// Throws an error with code `PostNotStarted` if `startPost` wasn't
+    /// called, and with code `PostNotFinished` if steps remain.
+    #[wasm_bindgen(js_name = "finishPost")]
+    pub fn finish_post(&mut self) -> Result<WasmTransactionPosted, JsValue> {
+        Ok(self.finish_pending_post()?)
+    }
+}
+
+/// Read the `{ address, value }` entries passed to `postWithChangeOutputs`.
+fn read_change_outputs(changes: &js_sys::Array) -> Result<Vec<(String, u64)>, WasmSaplingError> {
+    // Largest integer a javascript number holds exactly
+    const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+    changes
+        .iter()
+        .enumerate()
+        .map(|(index, change)| {
+            let invalid = |message: &str| {
+                WasmSaplingError::new("InvalidChangeOutput", message.into()).at_index(index)
+            };
+            let address = js_sys::Reflect::get(&change, &"address".into())
+                .ok()
+                .and_then(|address| address.as_string())
+                .ok_or_else(|| invalid("Change address must be a string"))?;
+            let value = js_sys::Reflect::get(&change, &"value".into())
+                .ok()
+                .and_then(|value| value.as_f64())
+                .filter(|value| *value >= 0.0 && *value <= MAX_SAFE_INTEGER && value.fract() == 0.0)
+                .ok_or_else(|| invalid("Change value must be a non-negative integer"))?;
+            Ok((address, value as u64))
+        })
+        .collect()
+}
+
+impl WasmTransaction {
+    pub(crate) fn spend_note(
+        &mut self,
+        spender_hex_key: &str,
+        note: &WasmNote,
+        witness: &dyn WitnessTrait<Bls12>,
+    ) -> Result<(), WasmSaplingError> {
+        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key)?;
+        self.transaction.spend(spender_key, &note.note, witness)?;
+        Ok(())
+    }
+
+    fn receive_serialized_notes(
+        &mut self,
+        spender_hex_key: &str,
+        notes: &[Vec<u8>],
+    ) -> Result<(), WasmSaplingError> {
+        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key)?;
+        for (index, note_bytes) in notes.iter().enumerate() {
+            let note = Note::read(&note_bytes[..], SAPLING.clone())
+                .map_err(|e| WasmSaplingError::from(e).at_index(index))?;
+            self.transaction
+                .receive(&spender_key, &note)
+                .map_err(|e| WasmSaplingError::from(e).at_index(index))?;
+        }
+        Ok(())
+    }
+
+    fn has_output_commitment(&self, existing_commitments: &[Vec<u8>]) -> bool {
+        let commitments = self.transaction.receipt_commitments();
+        existing_commitments.iter().any(|existing| {
+            commitments
+                .iter()
+                .any(|commitment| commitment[..] == existing[..])
+        })
+    }
+
+    fn post_with_fee<F: FnMut(usize, usize)>(
+        &mut self,
+        spender_hex_key: &str,
+        change_goes_to: Option<String>,
+        intended_transaction_fee: u64,
+        progress: F,
+    ) -> Result<WasmTransactionPosted, WasmSaplingError> {
+        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key)?;
+        let change_key = match change_goes_to {
+            Some(s) => Some(PublicAddress::from_hex(SAPLING.clone(), &s)?),
+            None => None,
+        };
+        let transaction = self.transaction.post_with_progress(
+            &spender_key,
+            change_key,
+            intended_transaction_fee,
+            progress,
+        )?;
+        Ok(WasmTransactionPosted { transaction })
+    }
+
+    fn change_preview(
+        &self,
+        change_goes_to: &str,
+        intended_transaction_fee: u64,
+    ) -> Result<Option<WasmNote>, WasmSaplingError> {
+        let change_goes_to = PublicAddress::from_hex(SAPLING.clone(), change_goes_to)?;
+        let note = self
+            .transaction
+            .preview_change(change_goes_to, intended_transaction_fee)?;
+        Ok(note.map(|note| WasmNote { note }))
+    }
+
+    fn unsigned_bytes(&self) -> Result<Vec<u8>, WasmSaplingError> {
+        let mut bytes = vec![];
+        self.transaction.write_unsigned(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn draft_bytes(&self) -> Result<Vec<u8>, WasmSaplingError> {
+        let mut bytes = vec![];
+        self.transaction.write_draft(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn start_pending_post(
+        &mut self,
+        spender_hex_key: &str,
+        change_goes_to: Option<String>,
+        intended_transaction_fee: u64,
+    ) -> Result<WasmPostProgress, WasmSaplingError> {
+        self.pending_post = None;
+        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key)?;
+        let change_goes_to = match change_goes_to {
+            Some(s) => Some(PublicAddress::from_hex(SAPLING.clone(), &s)?),
+            None => None,
+        };
+        let change_amount = self.transaction.change_amount(intended_transaction_fee)?;
+        let pending = PendingPost {
+            spender_key,
+            change_goes_to,
+            intended_transaction_fee,
+            completed: 0,
+            total: (change_amount > 0) as usize,
+        };
+        let progress = pending.progress();
+        self.pending_post = Some(pending);
+        Ok(progress)
+    }
+
+    fn next_post_step(&mut self) -> Result<WasmPostProgress, WasmSaplingError> {
+        let pending = self.pending_post.as_mut().ok_or_else(post_not_started)?;
+        if pending.completed < pending.total {
+            self.transaction.add_change(
+                &pending.spender_key,
+                pending.change_goes_to.clone(),
+                pending.intended_transaction_fee,
+            )?;
+            pending.completed += 1;
+        }
+        Ok(pending.progress())
+    }
+
+    fn finish_pending_post(&mut self) -> Result<WasmTransactionPosted, WasmSaplingError> {
+        let pending = self.pending_post.as_ref().ok_or_else(post_not_started)?;
+        if pending.completed < pending.total {
+            return Err(WasmSaplingError::new(
+                "PostNotFinished",
+                format!(
+                    "{} of {} posting steps are done",
+                    pending.completed, pending.total
+                ),
+            ));
+        }
+        let pending = self.pending_post.take().unwrap();
+        let transaction = self.transaction.post(
+            &pending.spender_key,
+            pending.change_goes_to,
+            pending.intended_transaction_fee,
+        )?;
+        Ok(WasmTransactionPosted { transaction })
+    }
+
+    fn post_with_changes(
+        &mut self,
+        spender_hex_key: &str,
+        changes: &[(String, u64)],
+        intended_transaction_fee: u64,
+    ) -> Result<WasmTransactionPosted, WasmSaplingError> {
+        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key)?;
+        let changes = changes
+            .iter()
+            .enumerate()
+            .map(|(index, (address, value))| {
+                PublicAddress::from_hex(SAPLING.clone(), address)
+                    .map(|address| (address, *value))
+                    .map_err(|e| WasmSaplingError::from(e).at_index(index))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let transaction = self.transaction.post_with_change_outputs(
+            &spender_key,
+            &changes,
+            intended_transaction_fee,
+        )?;
+        Ok(WasmTransactionPosted { transaction })
+    }
+}
+
+impl PendingPost {
+    fn progress(&self) -> WasmPostProgress {
+        WasmPostProgress {
+            completed: self.completed,
+            total: self.total,
+        }
+    }
+}
+
+fn post_not_started() -> WasmSaplingError {
+    WasmSaplingError::new(
+        "PostNotStarted",
+        "startPost must be called before postStep or finishPost".into(),
+    )
+}
+
+impl Default for WasmTransaction {
+    fn default() -> Self {
+        WasmTransaction::new()
+    }
+}
+
+#[wasm_bindgen]
+pub struct WasmSimpleTransaction {
+    transaction: SimpleTransaction,
+}
+
+#[wasm_bindgen]
+impl WasmSimpleTransaction {
+    #[wasm_bindgen(constructor)]
+    pub fn new(spender_hex_key: &str, intended_transaction_fee: u64) -> WasmSimpleTransaction {
+        console_error_panic_hook::set_once();
+        let spender_key = Key::from_hex(SAPLING.clone(), spender_hex_key).unwrap();
+        WasmSimpleTransaction {
+            transaction: SimpleTransaction::new(
+                SAPLING.clone(),
+                spender_key,
+                intended_transaction_fee,
+            ),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn spend(&mut self, note: &WasmNote, witness: &JsWitness) -> Result<(), JsValue> {
+        self.transaction
+            .spend(&note.note, witness)
+            .map_err(WasmSaplingError::from)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn receive(&mut self, note: &WasmNote) -> Result<(), JsValue> {
+        self.transaction
+            .receive(&note.note)
+            .map_err(WasmSaplingError::from)?;
+        Ok(())
+    }
+
+    /// Sum of the values spent minus the sum of the values received so far.
+    /// `post` sends anything above the intended fee back to the spender as
+    /// change.
+    #[wasm_bindgen(getter, js_name = "transactionFee")]
+    pub fn transaction_fee(&self) -> i64 {
+        self.transaction.transaction_fee()
+    }
+
+    /// Throws an error with code `InvalidBalanceError` if the spends don't
+    /// cover the outputs and the intended fee.
+    #[wasm_bindgen]
+    pub fn post(&mut self) -> Result<WasmTransactionPosted, JsValue> {
+        let transaction = self.transaction.post().map_err(WasmSaplingError::from)?;
+        Ok(WasmTransactionPosted { transaction })
+    }
+}
+
+/// A fresh key and a posted miners fee transaction paying 5 to it.
+#[cfg(test)]
+pub(crate) fn miners_fee_transaction() -> (Key, WasmTransactionPosted) {
+    let key = Key::generate_key(SAPLING.clone());
+    let note = WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+    let mut transaction = WasmTransaction::new();
+    transaction.receive(&key.hex_spending_key(), &note).unwrap();
+    let posted = transaction.post_miners_fee();
+    (key, posted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        miners_fee_transaction, read_draft, read_transaction, read_transaction_with_checksum,
+        read_transactions, sign_unsigned, verify_batch, verify_block_batch, WasmSimpleTransaction,
+        WasmTransaction, WasmTransactionPosted,
+    };
+    use crate::wasm_structs::hex::from_hex;
+    use crate::wasm_structs::{WasmNote, WasmNoteEncrypted, WasmRootSet, WasmSaplingError};
+    use ironfish_rust::errors::VerificationError;
+    use ironfish_rust::sapling_bls12::{Key, SAPLING};
+
+    #[test]
+    fn test_deserialize_rejects_trailing_bytes() {
+        let mut bytes = miners_fee_transaction().1.to_bytes().unwrap();
+
+        assert!(read_transaction(&bytes, false).is_ok());
+
+        bytes.push(0);
+        let err = read_transaction(&bytes, false).err().unwrap();
+        assert_eq!(err.code(), "TrailingBytes");
+        assert!(read_transaction(&bytes, true).is_ok());
+
+        let err = read_transaction(&bytes[..10], true).err().unwrap();
+        assert_eq!(err.code(), "UnexpectedEof");
+        let err = read_transaction(&bytes[..bytes.len() - 30], true)
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "UnexpectedEof");
+
+        // Corrupt the value commitment of the receipt, which follows the
+        // 24 byte header and the 192 byte proof
+        for byte in bytes[24 + 192..24 + 192 + 32].iter_mut() {
+            *byte = 0xff;
+        }
+        let err = read_transaction(&bytes, true).err().unwrap();
+        assert_eq!(err.code(), "InvalidEncoding");
+    }
+
+    #[test]
+    fn test_checksum_detects_corruption() {
+        let (_, posted) = miners_fee_transaction();
+
+        let mut bytes = posted.serialize_with_checksum().unwrap();
+        assert_eq!(bytes.len(), posted.to_bytes().unwrap().len() + 4);
+        assert!(read_transaction_with_checksum(&bytes).is_ok());
+
+        bytes[30] ^= 1;
+        let err = read_transaction_with_checksum(&bytes).err().unwrap();
+        assert_eq!(err.code(), "ChecksumMismatch");
+
+        let err = read_transaction_with_checksum(&bytes[..3]).err().unwrap();
+        assert_eq!(err.code(), "ChecksumMismatch");
+    }
+
+    #[test]
+    fn test_contains_note() {
+        let (key, posted) = miners_fee_transaction();
+
+        // A different note, even with the same owner and value, has a
+        // different commitment
+        let other_note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut other_transaction = WasmTransaction::new();
+        other_transaction
+            .receive(&key.hex_spending_key(), &other_note)
+            .unwrap();
+        let other_posted = other_transaction.post_miners_fee();
+
+        assert!(posted.contains_note(&posted.get_note(0)));
+        assert!(!posted.contains_note(&other_posted.get_note(0)));
+        assert!(!posted.contains_note(&[0; 10]));
+    }
+
+    #[test]
+    fn test_canonical_json() {
+        let (_, posted) = miners_fee_transaction();
+
+        let json = posted.to_canonical_json();
+        assert!(json.starts_with(r#"{"bindingSignature":""#));
+        assert!(json.contains(r#","fee":-5,"receipts":[{"commitment":""#));
+        assert!(json.ends_with(r#"],"spends":[]}"#));
+        assert!(!json.contains(' '));
+
+        let read_back = WasmTransactionPosted {
+            transaction: read_transaction(&posted.to_bytes().unwrap(), false).unwrap(),
+        };
+        assert_eq!(read_back.to_canonical_json(), json);
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let serialized = miners_fee_transaction().1.to_bytes().unwrap();
+
+        let mut corrupted = serialized.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+
+        let mut batch = serialized.clone();
+        batch.extend_from_slice(&corrupted);
+        batch.extend_from_slice(&serialized);
+        let length = serialized.len() as u32;
+
+        assert_eq!(
+            verify_batch(&batch, &[length, length, length]).unwrap(),
+            vec![0b101]
+        );
+        assert_eq!(verify_batch(&[], &[]).unwrap(), Vec::<u8>::new());
+        assert_eq!(
+            verify_batch(&batch, &[length, length]).unwrap_err().code(),
+            "LengthMismatch"
+        );
+    }
+
+    #[test]
+    fn test_verify_block() {
+        let serialized = miners_fee_transaction().1.to_bytes().unwrap();
+        let length = serialized.len() as u32;
+
+        // The fee follows the spend and receipt counts
+        let mut tampered = serialized.clone();
+        tampered[16] ^= 1;
+
+        // A miners fee spends nothing, so repeating it spends no note twice
+        let mut batch = serialized.clone();
+        batch.extend_from_slice(&serialized);
+        assert!(verify_block_batch(&batch, &[length, length]).is_ok());
+        assert!(verify_block_batch(&[], &[]).is_ok());
+
+        let mut batch = serialized.clone();
+        batch.extend_from_slice(&tampered);
+        batch.extend_from_slice(&[0; 4]);
+        let err = verify_block_batch(&batch, &[length, length, 4]).unwrap_err();
+        assert_eq!(err.code(), "BindingSignatureInvalid");
+        assert!(err.message().starts_with("Item at index 1: "));
+
+        let mut batch = serialized.clone();
+        batch.extend_from_slice(&[0; 4]);
+        let err = verify_block_batch(&batch, &[length, 4]).unwrap_err();
+        assert_eq!(err.code(), "UnexpectedEof");
+        assert!(err.message().starts_with("Item at index 1: "));
+
+        assert_eq!(
+            verify_block_batch(&batch, &[length]).unwrap_err().code(),
+            "LengthMismatch"
+        );
+    }
+
+    #[test]
+    fn test_expiration() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+        transaction.set_expiration(10);
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+        let posted = transaction.post_miners_fee();
+
+        let read_back = WasmTransactionPosted {
+            transaction: read_transaction(&posted.to_bytes().unwrap(), false).unwrap(),
+        };
+        assert_eq!(read_back.expiration_sequence(), 10);
+        assert!(read_back.verify_at_sequence(10));
+        assert!(!read_back.verify_at_sequence(11));
+        assert!(read_back
+            .to_canonical_json()
+            .contains(r#""expirationSequence":10,"#));
+    }
+
+    #[test]
+    fn test_output_value() {
+        let key = Key::generate_key(SAPLING.clone());
+        let other_key = Key::generate_key(SAPLING.clone());
+        let address = key.generate_public_address().hex_public_address();
+        let mut transaction = WasmTransaction::new();
+        for value in &[5, 7] {
+            let note = WasmNote::new(&address, *value, "").unwrap();
+            transaction.receive(&key.hex_spending_key(), &note).unwrap();
+        }
+        let posted = transaction.post_miners_fee();
+
+        assert_eq!(
+            posted
+                .output_value(&key.incoming_view_key().hex_key())
+                .unwrap(),
+            12
+        );
+        assert_eq!(
+            posted
+                .output_value(&other_key.incoming_view_key().hex_key())
+                .unwrap(),
+            0
+        );
+        assert_eq!(posted.posted_value_commitment_sum().len(), 32);
+    }
+
+    #[test]
+    fn test_read_transactions() {
+        let key = Key::generate_key(SAPLING.clone());
+        let mut bytes = vec![];
+        for value in &[5, 7] {
+            let note = WasmNote::new(
+                &key.generate_public_address().hex_public_address(),
+                *value,
+                "",
+            )
+            .unwrap();
+            let mut transaction = WasmTransaction::new();
+            transaction.receive(&key.hex_spending_key(), &note).unwrap();
+            bytes.extend(transaction.post_miners_fee().to_bytes().unwrap());
+        }
+
+        let transactions = read_transactions(&bytes).unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].transaction_fee(), -5);
+        assert_eq!(transactions[1].transaction_fee(), -7);
+        assert!(read_transactions(&[]).unwrap().is_empty());
+
+        let err = read_transactions(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.code(), "UnexpectedEof");
+
+        // Concatenated transactions are never read as a single one
+        let err = read_transaction(&bytes, false).err().unwrap();
+        assert_eq!(err.code(), "TrailingBytes");
+        let first = read_transaction(&bytes, true).unwrap();
+        assert_eq!(first.transaction_fee(), -5);
+        assert_eq!(first.receipts().len(), 1);
+    }
+
+    #[test]
+    fn test_post_step() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+
+        let err = transaction.next_post_step().err().unwrap();
+        assert_eq!(err.code(), "PostNotStarted");
+        let err = transaction.finish_pending_post().err().unwrap();
+        assert_eq!(err.code(), "PostNotStarted");
+
+        // Nothing is left over for change, so there are no steps to take
+        let progress = transaction
+            .start_pending_post(&key.hex_spending_key(), None, 0)
+            .unwrap();
+        assert_eq!(progress.total(), 0);
+        assert!(progress.done());
+        assert!(transaction.next_post_step().unwrap().done());
+        let posted = transaction.finish_pending_post().unwrap();
+        assert!(posted.verify());
+
+        let err = transaction.finish_pending_post().err().unwrap();
+        assert_eq!(err.code(), "PostNotStarted");
+
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+        let err = transaction
+            .start_pending_post(&key.hex_spending_key(), None, 0)
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "InvalidBalanceError");
+        let err = transaction.next_post_step().err().unwrap();
+        assert_eq!(err.code(), "PostNotStarted");
+    }
+
+    #[test]
+    fn test_sign_unsigned() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+
+        let unsigned = transaction.unsigned_bytes().unwrap();
+        let posted = sign_unsigned(&unsigned, &key.hex_spending_key()).unwrap();
+        assert!(posted.verify());
+        assert_eq!(posted.transaction_fee(), 0);
+
+        let err = sign_unsigned(&unsigned[1..], &key.hex_spending_key())
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "IOError");
+
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+        let err = transaction.unsigned_bytes().err().unwrap();
+        assert_eq!(err.code(), "InvalidBalanceError");
+    }
+
+    #[test]
+    fn test_draft() {
+        let key = Key::generate_key(SAPLING.clone());
+        let other_key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+        transaction.set_expiration(10);
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+
+        let draft = transaction.draft_bytes().unwrap();
+        let mut restored = read_draft(&draft, &key.hex_spending_key()).unwrap();
+        assert_eq!(restored.signature_hash(), transaction.signature_hash());
+        let posted = restored.post_miners_fee();
+        assert!(posted.verify());
+        assert_eq!(posted.transaction_fee(), -5);
+        assert_eq!(posted.expiration_sequence(), 10);
+
+        let err = read_draft(&draft[1..], &key.hex_spending_key())
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "IOError");
+        // Without spends, nothing in the draft ties it to a key
+        assert!(read_draft(&draft, &other_key.hex_spending_key()).is_ok());
+    }
+
+    #[test]
+    fn test_post_rejects_unaffordable_fee() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+
+        let err = transaction
+            .post_with_fee(&key.hex_spending_key(), None, u64::MAX, |_, _| {})
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "InvalidBalanceError");
+    }
+
+    #[test]
+    fn test_granular_verification() {
+        let mut bytes = miners_fee_transaction().1.to_bytes().unwrap();
+
+        let posted = WasmTransactionPosted {
+            transaction: read_transaction(&bytes, false).unwrap(),
+        };
+        assert!(posted.verify_binding_signature());
+        assert!(posted.verify_receipt(0));
+        assert!(!posted.verify_receipt(1));
+        assert!(!posted.verify_spend(0));
+
+        // Corrupt the binding signature, which is the last field
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let corrupted = WasmTransactionPosted {
+            transaction: read_transaction(&bytes, false).unwrap(),
+        };
+        assert!(!corrupted.verify());
+        assert!(!corrupted.verify_binding_signature());
+        assert!(corrupted.verify_receipt(0));
+        assert_eq!(
+            corrupted.transaction.verify_detailed(),
+            Err(VerificationError::BindingSignatureInvalid)
+        );
+    }
+
+    #[test]
+    fn test_verification_error_codes() {
+        let err = WasmSaplingError::from(VerificationError::ReceiptProofInvalid(2));
+        assert_eq!(err.code(), "ReceiptProofInvalid");
+        assert!(err.message().starts_with("Item at index 2"));
+
+        let err = WasmSaplingError::from(VerificationError::BindingSignatureInvalid);
+        assert_eq!(err.code(), "BindingSignatureInvalid");
+        assert_eq!(err.message(), "BindingSignatureInvalid");
+    }
+
+    #[test]
+    fn test_fee_rate() {
+        let (_, posted) = miners_fee_transaction();
+
+        let size = posted.to_bytes().unwrap().len();
+        assert_eq!(posted.serialized_size(), size);
+        assert_eq!(posted.fee_rate(), -5.0 / size as f64);
+    }
+
+    #[test]
+    fn test_version() {
+        let (_, posted) = miners_fee_transaction();
+        assert_eq!(posted.version(), 0);
+
+        let mut bytes = posted.to_bytes().unwrap();
+
+        // version 1 without an expiration signs the same as version 0
+        let mut unexpiring_v1 = bytes[..24].to_vec();
+        unexpiring_v1[7] = 1;
+        unexpiring_v1.extend_from_slice(&[0; 4]);
+        unexpiring_v1.extend_from_slice(&bytes[24..]);
+        let err = read_transaction(&unexpiring_v1, false).err().unwrap();
+        assert_eq!(err.code(), "InvalidEncoding");
+
+        bytes[7] = 2;
+        let err = read_transaction(&bytes, false).err().unwrap();
+        assert_eq!(err.code(), "UnsupportedVersion");
+    }
+
+    #[test]
+    fn test_post_with_changes() {
+        let key = Key::generate_key(SAPLING.clone());
+        let address = key.generate_public_address().hex_public_address();
+        let note = WasmNote::new(&address, 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+
+        let err = transaction
+            .post_with_changes(&key.hex_spending_key(), &[(address, 1)], 0)
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "InvalidBalanceError");
+
+        let err = transaction
+            .post_with_changes(&key.hex_spending_key(), &[("invalid".into(), 1)], 0)
+            .err()
+            .unwrap();
+        assert!(err.message().starts_with("Item at index 0"));
+    }
+
+    #[test]
+    fn test_receive_many() {
+        let key = Key::generate_key(SAPLING.clone());
+        let address = key.generate_public_address().hex_public_address();
+        let notes = vec![
+            WasmNote::new(&address, 5, "").unwrap().serialize(),
+            WasmNote::new(&address, 7, "").unwrap().serialize(),
+        ];
+
+        let mut transaction = WasmTransaction::new();
+        transaction
+            .receive_serialized_notes(&key.hex_spending_key(), &notes)
+            .unwrap();
+        assert_eq!(transaction.estimate_fee(), -12);
+
+        let mut transaction = WasmTransaction::new();
+        let bad_notes = vec![notes[0].clone(), vec![0; 3]];
+        let err = transaction
+            .receive_serialized_notes(&key.hex_spending_key(), &bad_notes)
+            .unwrap_err();
+        assert!(err.message().starts_with("Item at index 1"));
+        assert_eq!(transaction.estimate_fee(), -5);
+    }
+
+    #[test]
+    fn test_would_duplicate() {
+        let key = Key::generate_key(SAPLING.clone());
+        let address = key.generate_public_address().hex_public_address();
+        let note = WasmNote::new(&address, 5, "").unwrap();
+        let other = WasmNote::new(&address, 5, "").unwrap();
+
+        let mut transaction = WasmTransaction::new();
+        assert!(!transaction.has_output_commitment(&[note.commitment()]));
+
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+        assert!(transaction.has_output_commitment(&[note.commitment()]));
+        assert!(transaction.has_output_commitment(&[other.commitment(), note.commitment()]));
+        assert!(!transaction.has_output_commitment(&[other.commitment()]));
+        assert!(!transaction.has_output_commitment(&[]));
+    }
+
+    #[test]
+    fn test_get_note_commitment() {
+        let (_, posted) = miners_fee_transaction();
+
+        let commitment = posted.get_note_commitment(0);
+        assert_eq!(commitment.len(), 32);
+        assert_eq!(
+            commitment,
+            WasmNoteEncrypted::deserialize(&posted.get_note(0)).merkle_hash()
+        );
+    }
+
+    #[test]
+    fn test_verify_with_root_set() {
+        let (_, posted) = miners_fee_transaction();
+
+        // Without spends there are no anchors to check
+        assert!(posted.verify_with_root_set(&WasmRootSet::new()).unwrap());
+    }
+
+    #[test]
+    fn test_verify_with_root_set_rejects_unknown_anchor() {
+        use crate::wasm_structs::bench::sample_posted_transaction;
+
+        let posted = sample_posted_transaction(1, 1);
+        let err = posted
+            .verify_with_root_set(&WasmRootSet::new())
+            .unwrap_err();
+        assert_eq!(err.code(), "UnknownAnchor");
+
+        let mut roots = WasmRootSet::new();
+        roots.add(&posted.get_spend(0).root_hash()).unwrap();
+        assert!(posted.verify_with_root_set(&roots).unwrap());
+    }
+
+    #[test]
+    fn test_spend_fields_without_copy() {
+        use crate::wasm_structs::bench::sample_posted_transaction;
+
+        let posted = sample_posted_transaction(2, 1);
+        for index in 0..2 {
+            let spend = posted.get_spend(index);
+            assert_eq!(posted.get_spend_nullifier(index), spend.nullifier());
+            assert_eq!(posted.get_spend_root_hash(index), spend.root_hash());
+            assert_eq!(posted.get_spend_tree_size(index), spend.tree_size());
+        }
+        assert_ne!(posted.get_spend_nullifier(0), posted.get_spend_nullifier(1));
+    }
+
+    #[test]
+    fn test_simple_transaction_fee() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmSimpleTransaction::new(&key.hex_spending_key(), 0);
+        assert_eq!(transaction.transaction_fee(), 0);
+        transaction.receive(&note).unwrap();
+        assert_eq!(transaction.transaction_fee(), -5);
+    }
+
+    #[test]
+    fn test_clone_and_equals() {
+        let (_, posted) = miners_fee_transaction();
+
+        let copy = posted.clone_transaction();
+        assert!(copy.equals(&posted));
+        assert!(copy.verify());
+        assert_eq!(copy.to_bytes().unwrap(), posted.to_bytes().unwrap());
+
+        // Same shape and value, but a different key and randomness
+        let (_, other) = miners_fee_transaction();
+        assert!(!other.equals(&posted));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let (_, posted) = miners_fee_transaction();
+
+        let read_back = WasmTransactionPosted {
+            transaction: read_transaction(&posted.to_bytes().unwrap(), false).unwrap(),
+        };
+        assert_eq!(posted.fingerprint().len(), 32);
+        assert_eq!(read_back.fingerprint(), posted.fingerprint());
+        assert_ne!(posted.fingerprint(), posted.transaction_hash());
+    }
+
+    #[test]
+    fn test_value_commitment_randomness() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+
+        assert_eq!(
+            transaction
+                .output_value_commitment_randomness(0)
+                .unwrap()
+                .len(),
+            32
+        );
+        assert_eq!(transaction.output_value_commitment_randomness(1), None);
+        assert_eq!(transaction.spend_value_commitment_randomness(0), None);
+    }
+
+    #[test]
+    fn test_preview_change() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+
+        let change_address = key.generate_public_address().hex_public_address();
+
+        // Outputs without spends leave nothing for change or the fee
+        let err = transaction
+            .change_preview(&change_address, 0)
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "InvalidBalanceError");
+
+        let err = WasmTransaction::new()
+            .change_preview("not an address", 0)
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "SaplingKeyError");
+
+        let transaction = WasmTransaction::new();
+        assert!(transaction
+            .change_preview(&change_address, 0)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_serialized_notes() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+        let posted = transaction.post_miners_fee();
+
+        let notes = posted.serialized_notes();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0], posted.get_note(0));
+        assert_eq!(notes[1], posted.get_note(1));
+    }
+
+    #[test]
+    fn test_verify_miners_fee() {
+        let (_, posted) = miners_fee_transaction();
+
+        assert!(posted.verify_miners_fee(5));
+        assert!(!posted.verify_miners_fee(4));
+        assert!(!posted.verify_miners_fee(6));
+    }
+
+    #[test]
+    fn test_value_commitment_sum() {
+        let (_, posted) = miners_fee_transaction();
+
+        let sum = posted.value_commitment_sum();
+        assert_eq!(sum.len(), 32);
+        let read_back = WasmTransactionPosted {
+            transaction: read_transaction(&posted.to_bytes().unwrap(), false).unwrap(),
+        };
+        assert_eq!(read_back.value_commitment_sum(), sum);
+
+        // Without spends, the sum is the negated value commitment of the
+        // only output, which differs from it in the sign bit of x
+        let mut negated = posted.get_receipt(0).value_commitment();
+        negated[31] ^= 0x80;
+        assert_eq!(sum, negated);
+    }
+
+    #[test]
+    fn test_reset() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let mut transaction = WasmTransaction::new();
+        let empty_hash = transaction.signature_hash();
+        transaction.set_expiration(10);
+        transaction
+            .start_pending_post(&key.hex_spending_key(), None, 0)
+            .unwrap();
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+
+        transaction.reset();
+        assert_eq!(transaction.signature_hash(), empty_hash);
+        assert_eq!(
+            transaction.next_post_step().err().unwrap().code(),
+            "PostNotStarted"
+        );
+
+        transaction.receive(&key.hex_spending_key(), &note).unwrap();
+        let posted = transaction.post_miners_fee();
+        assert!(posted.verify());
+        assert_eq!(posted.transaction_fee(), -5);
+        assert_eq!(posted.expiration_sequence(), 0);
+    }
+
+    #[test]
+    fn test_hex_getters() {
+        let (_, posted) = miners_fee_transaction();
+
+        assert_eq!(
+            from_hex(&posted.transaction_hash_hex()).unwrap(),
+            posted.transaction_hash()
+        );
+        assert_eq!(
+            from_hex(&posted.transaction_signature_hex()).unwrap(),
+            posted.transaction_signature()
+        );
+        assert_eq!(
+            from_hex(&posted.fingerprint_hex()).unwrap(),
+            posted.fingerprint()
+        );
     }
 }
@@ -0,0 +1,158 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::note_selection::{self, TransactionPlan};
+use ironfish_rust::sapling_bls12::{PublicAddress, SAPLING};
+
+use super::errors::WasmSaplingError;
+use super::note::read_notes;
+
+/// One of the transactions `planTransactions` splits a send into. Build it
+/// with a `WasmTransaction`: spend the notes at `spends`, receive the
+/// outputs at `outputs`, and post it with `fee` as the intended fee.
+#[wasm_bindgen]
+pub struct WasmTransactionPlan {
+    plan: TransactionPlan,
+}
+
+#[wasm_bindgen]
+impl WasmTransactionPlan {
+    /// Indices of the notes to spend, in the array given to
+    /// `planTransactions`.
+    #[wasm_bindgen(getter)]
+    pub fn spends(&self) -> Vec<u32> {
+        self.plan.spends.iter().map(|index| *index as u32).collect()
+    }
+
+    /// Indices of the outputs to pay, in the arrays given to
+    /// `planTransactions`.
+    #[wasm_bindgen(getter)]
+    pub fn outputs(&self) -> Vec<u32> {
+        self.plan
+            .outputs
+            .iter()
+            .map(|index| *index as u32)
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn fee(&self) -> u64 {
+        self.plan.fee
+    }
+}
+
+/// Split a send into transactions of at most `maxSize` bytes each, paying
+/// `feePerByte` for every byte, and return an array of
+/// `WasmTransactionPlan`s to build and post one by one.
+///
+/// `notes` is an array of serialized notes (as produced by
+/// `WasmNote.serialize`). Output `i` sends `outputValues[i]` to the hex
+/// encoded address `outputAddresses[i]`; outputs are never split between
+/// transactions.
+///
+/// Each output is paid with the smallest note that covers it where one does,
+/// keeping the larger notes for later outputs.
+///
+/// Throws an error with code `OutputTooLarge` if the notes left for an output
+/// can't pay it in a transaction on its own, `InsufficientFunds` if the notes don't cover
+/// the outputs and fees, `LengthMismatch` if the output arrays differ in
+/// length, or `SaplingKeyError` or `NoteError` with the index of an address
+/// or note that could not be read.
+#[wasm_bindgen(js_name = "planTransactions")]
+pub fn plan_transactions(
+    notes: js_sys::Array,
+    output_addresses: js_sys::Array,
+    output_values: &[u64],
+    max_size: usize,
+    fee_per_byte: u64,
+) -> Result<js_sys::Array, JsValue> {
+    let notes: Vec<Vec<u8>> = notes
+        .iter()
+        .map(|note| js_sys::Uint8Array::new(&note).to_vec())
+        .collect();
+    let output_addresses: Vec<String> = output_addresses
+        .iter()
+        .map(|address| address.as_string().unwrap_or_default())
+        .collect();
+    let plans = plan_serialized(
+        &notes,
+        &output_addresses,
+        output_values,
+        max_size,
+        fee_per_byte,
+    )?;
+    Ok(plans.into_iter().map(JsValue::from).collect())
+}
+
+fn plan_serialized(
+    notes: &[Vec<u8>],
+    output_addresses: &[String],
+    output_values: &[u64],
+    max_size: usize,
+    fee_per_byte: u64,
+) -> Result<Vec<WasmTransactionPlan>, WasmSaplingError> {
+    if output_addresses.len() != output_values.len() {
+        return Err(WasmSaplingError::new(
+            "LengthMismatch",
+            format!(
+                "{} output addresses but {} output values",
+                output_addresses.len(),
+                output_values.len()
+            ),
+        ));
+    }
+    let notes = read_notes(notes)?;
+    let outputs = output_addresses
+        .iter()
+        .zip(output_values)
+        .enumerate()
+        .map(|(index, (address, value))| {
+            PublicAddress::from_hex(SAPLING.clone(), address)
+                .map(|address| (address, *value))
+                .map_err(|e| WasmSaplingError::from(e).at_index(index))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let plans = note_selection::plan_transactions(&notes, &outputs, max_size, fee_per_byte)?;
+    Ok(plans
+        .into_iter()
+        .map(|plan| WasmTransactionPlan { plan })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::plan_serialized;
+    use crate::wasm_structs::WasmNote;
+    use ironfish_rust::sapling_bls12::{Key, SAPLING};
+
+    #[test]
+    fn test_plan_serialized() {
+        let key = Key::generate_key(SAPLING.clone());
+        let address = key.generate_public_address().hex_public_address();
+        let notes: Vec<Vec<u8>> = [3, 10, 7]
+            .iter()
+            .map(|value| WasmNote::new(&address, *value, "").unwrap().serialize())
+            .collect();
+        let addresses = vec![address.clone(), address.clone()];
+
+        let plans = plan_serialized(&notes, &addresses, &[5, 8], 100_000, 0).unwrap();
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].spends(), vec![2, 1]);
+        assert_eq!(plans[0].outputs(), vec![0, 1]);
+        assert_eq!(plans[0].fee(), 0);
+
+        let err = plan_serialized(&notes, &addresses, &[5, 30], 100_000, 0).unwrap_err();
+        assert_eq!(err.code(), "InsufficientFunds");
+        let err = plan_serialized(&notes, &addresses, &[5, 8], 0, 0).unwrap_err();
+        assert_eq!(err.code(), "OutputTooLarge");
+        let err = plan_serialized(&notes, &addresses, &[5], 100_000, 0).unwrap_err();
+        assert_eq!(err.code(), "LengthMismatch");
+        let bad_addresses = vec![address, "00".into()];
+        let err = plan_serialized(&notes, &bad_addresses, &[5, 8], 100_000, 0).unwrap_err();
+        assert_eq!(err.code(), "SaplingKeyError");
+        assert!(err.message().starts_with("Item at index 1"));
+    }
+}
@@ -0,0 +1,170 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::collections::{BTreeMap, HashMap};
+
+use blake2b_simd::Params as Blake2b;
+use wasm_bindgen::prelude::*;
+
+use super::WasmTransactionPosted;
+
+const VERIFY_CACHE_PERSONALIZATION: &[u8; 16] = b"Iron Fish vcache";
+
+type TransactionId = [u8; 32];
+
+/// Bounded cache of transaction verification results, so a transaction
+/// received from several peers is only verified once.
+///
+/// Entries are keyed by a hash of the full serialized transaction, and the
+/// least recently used entry is evicted once the cache is full.
+#[wasm_bindgen]
+pub struct WasmVerifyCache {
+    capacity: usize,
+    // Result and last access of each id
+    results: HashMap<TransactionId, (bool, u64)>,
+    // Ids by last access, least recently used first
+    recency: BTreeMap<u64, TransactionId>,
+    accesses: u64,
+}
+
+#[wasm_bindgen]
+impl WasmVerifyCache {
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: usize) -> WasmVerifyCache {
+        // The capacity comes from JS, so grow as entries are added rather
+        // than allocating all of it up front
+        WasmVerifyCache {
+            capacity,
+            results: HashMap::new(),
+            recency: BTreeMap::new(),
+            accesses: 0,
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.results.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn clear(&mut self) {
+        self.results.clear();
+        self.recency.clear();
+    }
+}
+
+impl WasmVerifyCache {
+    fn get(&mut self, id: &TransactionId) -> Option<bool> {
+        let (result, _) = *self.results.get(id)?;
+        self.touch(*id, result);
+        Some(result)
+    }
+
+    fn insert(&mut self, id: TransactionId, result: bool) {
+        if self.capacity == 0 {
+            return;
+        }
+        let is_new = !self.results.contains_key(&id);
+        self.touch(id, result);
+        if is_new && self.results.len() > self.capacity {
+            let oldest = self.recency.keys().next().copied();
+            if let Some(access) = oldest {
+                if let Some(evicted) = self.recency.remove(&access) {
+                    self.results.remove(&evicted);
+                }
+            }
+        }
+    }
+
+    /// Store the result and mark the id as most recently used.
+    fn touch(&mut self, id: TransactionId, result: bool) {
+        self.accesses += 1;
+        if let Some((_, access)) = self.results.insert(id, (result, self.accesses)) {
+            self.recency.remove(&access);
+        }
+        self.recency.insert(self.accesses, id);
+    }
+}
+
+/// Verify the transaction, reusing the result from the cache if this exact
+/// transaction was verified before, and storing the result otherwise.
+#[wasm_bindgen(js_name = "verifyCached")]
+pub fn verify_cached(transaction: &WasmTransactionPosted, cache: &mut WasmVerifyCache) -> bool {
+    let id = match transaction.to_bytes() {
+        Ok(bytes) => transaction_id(&bytes),
+        Err(_) => return transaction.verify(),
+    };
+    if let Some(result) = cache.get(&id) {
+        return result;
+    }
+    let result = transaction.verify();
+    cache.insert(id, result);
+    result
+}
+
+fn transaction_id(serialized_transaction: &[u8]) -> TransactionId {
+    let mut id = [0; 32];
+    id.copy_from_slice(
+        Blake2b::new()
+            .hash_length(32)
+            .personal(VERIFY_CACHE_PERSONALIZATION)
+            .hash(serialized_transaction)
+            .as_bytes(),
+    );
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{transaction_id, WasmVerifyCache};
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = WasmVerifyCache::new(2);
+        let a = transaction_id(b"a");
+        let b = transaction_id(b"b");
+        let c = transaction_id(b"c");
+
+        cache.insert(a, true);
+        cache.insert(b, false);
+        assert_eq!(cache.get(&a), Some(true));
+
+        // b is now the least recently used entry
+        cache.insert(c, true);
+        assert_eq!(cache.size(), 2);
+        assert_eq!(cache.get(&b), None);
+        assert_eq!(cache.get(&a), Some(true));
+        assert_eq!(cache.get(&c), Some(true));
+
+        cache.clear();
+        assert_eq!(cache.size(), 0);
+        assert_eq!(cache.get(&a), None);
+    }
+
+    #[test]
+    fn test_cache_reinsert_refreshes_entry() {
+        let mut cache = WasmVerifyCache::new(2);
+        let a = transaction_id(b"a");
+        let b = transaction_id(b"b");
+        let c = transaction_id(b"c");
+
+        cache.insert(a, true);
+        cache.insert(b, true);
+        cache.insert(a, false);
+        assert_eq!(cache.size(), 2);
+
+        // a was refreshed by the second insert, so b is evicted
+        cache.insert(c, true);
+        assert_eq!(cache.get(&b), None);
+        assert_eq!(cache.get(&a), Some(false));
+        assert_eq!(cache.get(&c), Some(true));
+    }
+
+    #[test]
+    fn test_zero_capacity_cache_stores_nothing() {
+        let mut cache = WasmVerifyCache::new(0);
+        cache.insert(transaction_id(b"a"), true);
+        assert_eq!(cache.size(), 0);
+    }
+}
@@ -0,0 +1,157 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::sapling_bls12::Transaction;
+
+use super::errors::WasmSaplingError;
+use super::root_set::WasmRootSet;
+
+/// Admission rules for `WasmTransactionPosted.verifyWithPolicy`.
+///
+/// A new policy has no limits; each setter adds one rule. The rules are all
+/// cheap to check, so they are applied before any proof is verified.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmVerifyPolicy {
+    max_spends: Option<usize>,
+    max_outputs: Option<usize>,
+    max_size: Option<usize>,
+    min_fee: Option<i64>,
+    max_fee: Option<i64>,
+    valid_roots: Option<WasmRootSet>,
+}
+
+#[wasm_bindgen]
+impl WasmVerifyPolicy {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmVerifyPolicy {
+        WasmVerifyPolicy::default()
+    }
+
+    #[wasm_bindgen(js_name = "setMaxSpends")]
+    pub fn set_max_spends(&mut self, max_spends: usize) {
+        self.max_spends = Some(max_spends);
+    }
+
+    #[wasm_bindgen(js_name = "setMaxOutputs")]
+    pub fn set_max_outputs(&mut self, max_outputs: usize) {
+        self.max_outputs = Some(max_outputs);
+    }
+
+    /// Maximum size of the serialized transaction, in bytes.
+    #[wasm_bindgen(js_name = "setMaxSize")]
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = Some(max_size);
+    }
+
+    #[wasm_bindgen(js_name = "setMinFee")]
+    pub fn set_min_fee(&mut self, min_fee: i64) {
+        self.min_fee = Some(min_fee);
+    }
+
+    #[wasm_bindgen(js_name = "setMaxFee")]
+    pub fn set_max_fee(&mut self, max_fee: i64) {
+        self.max_fee = Some(max_fee);
+    }
+
+    /// Require the anchor of every spend to be one of `roots`.
+    #[wasm_bindgen(js_name = "setValidRoots")]
+    pub fn set_valid_roots(&mut self, roots: &WasmRootSet) {
+        self.valid_roots = Some(roots.clone());
+    }
+}
+
+impl WasmVerifyPolicy {
+    /// Check the structural rules of the policy, returning the first one the
+    /// transaction breaks. Proofs and signatures are not checked here.
+    pub(crate) fn check(&self, transaction: &Transaction) -> Result<(), WasmSaplingError> {
+        let spends = transaction.spends().len();
+        if let Some(max_spends) = self.max_spends.filter(|max| spends > *max) {
+            return Err(WasmSaplingError::new(
+                "TooManySpends",
+                format!("{} spends, at most {} allowed", spends, max_spends),
+            ));
+        }
+
+        let outputs = transaction.receipts().len();
+        if let Some(max_outputs) = self.max_outputs.filter(|max| outputs > *max) {
+            return Err(WasmSaplingError::new(
+                "TooManyOutputs",
+                format!("{} outputs, at most {} allowed", outputs, max_outputs),
+            ));
+        }
+
+        let size = transaction.serialized_size();
+        if let Some(max_size) = self.max_size.filter(|max| size > *max) {
+            return Err(WasmSaplingError::new(
+                "TransactionTooLarge",
+                format!("{} bytes, at most {} allowed", size, max_size),
+            ));
+        }
+
+        let fee = transaction.transaction_fee();
+        if let Some(min_fee) = self.min_fee.filter(|min| fee < *min) {
+            return Err(WasmSaplingError::new(
+                "FeeTooLow",
+                format!("Fee is {}, at least {} required", fee, min_fee),
+            ));
+        }
+        if let Some(max_fee) = self.max_fee.filter(|max| fee > *max) {
+            return Err(WasmSaplingError::new(
+                "FeeTooHigh",
+                format!("Fee is {}, at most {} allowed", fee, max_fee),
+            ));
+        }
+
+        if let Some(roots) = &self.valid_roots {
+            if !roots.contains_anchors_of(transaction) {
+                return Err(WasmSaplingError::new(
+                    "UnknownAnchor",
+                    "A spend refers to a note tree root that is not valid".into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmVerifyPolicy;
+    use crate::wasm_structs::transaction::miners_fee_transaction;
+
+    #[test]
+    fn test_policy_check() {
+        let (_, posted) = miners_fee_transaction();
+        let transaction = &posted.transaction;
+
+        let mut policy = WasmVerifyPolicy::new();
+        assert!(policy.check(transaction).is_ok());
+
+        policy.set_max_spends(0);
+        policy.set_max_outputs(1);
+        policy.set_max_size(transaction.serialized_size());
+        policy.set_min_fee(-5);
+        policy.set_max_fee(-5);
+        assert!(policy.check(transaction).is_ok());
+
+        let mut too_many_outputs = WasmVerifyPolicy::new();
+        too_many_outputs.set_max_outputs(0);
+        let err = too_many_outputs.check(transaction).err().unwrap();
+        assert_eq!(err.code(), "TooManyOutputs");
+
+        let mut too_large = WasmVerifyPolicy::new();
+        too_large.set_max_size(transaction.serialized_size() - 1);
+        let err = too_large.check(transaction).err().unwrap();
+        assert_eq!(err.code(), "TransactionTooLarge");
+
+        let mut fee_too_low = WasmVerifyPolicy::new();
+        fee_too_low.set_min_fee(0);
+        let err = fee_too_low.check(transaction).err().unwrap();
+        assert_eq!(err.code(), "FeeTooLow");
+    }
+}
@@ -0,0 +1,135 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::convert::TryInto;
+
+use wasm_bindgen::prelude::*;
+
+use super::errors::WasmSaplingError;
+
+const STATE_SIZE: usize = 36;
+
+/// Progress of a verification split over several calls to
+/// `WasmTransactionPosted.verifyStep`.
+///
+/// The state records the hash of the transaction it belongs to, so resuming
+/// it against a different transaction fails verification instead of
+/// skipping checks.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WasmVerifyState {
+    pub(crate) transaction_hash: [u8; 32],
+    pub(crate) step: u32,
+}
+
+#[wasm_bindgen]
+impl WasmVerifyState {
+    /// Index of the next step to verify.
+    #[wasm_bindgen(getter)]
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+
+    /// Store the state as 36 bytes, so it can be sent between workers.
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(STATE_SIZE);
+        bytes.extend_from_slice(&self.transaction_hash);
+        bytes.extend_from_slice(&self.step.to_le_bytes());
+        bytes
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(bytes: &[u8]) -> Result<WasmVerifyState, JsValue> {
+        Ok(read_state(bytes)?)
+    }
+}
+
+/// Outcome of a single call to `WasmTransactionPosted.verifyStep`.
+///
+/// `valid` is false as soon as any step fails, and `done` is then set as
+/// well. Otherwise `done` is set once the last step has been verified, and
+/// `state` is passed to the next call until then.
+#[wasm_bindgen]
+pub struct WasmVerifyStepResult {
+    pub(crate) state: WasmVerifyState,
+    pub(crate) done: bool,
+    pub(crate) valid: bool,
+}
+
+#[wasm_bindgen]
+impl WasmVerifyStepResult {
+    #[wasm_bindgen(getter)]
+    pub fn state(&self) -> WasmVerifyState {
+        self.state.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn done(&self) -> bool {
+        self.done
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn valid(&self) -> bool {
+        self.valid
+    }
+}
+
+fn read_state(bytes: &[u8]) -> Result<WasmVerifyState, WasmSaplingError> {
+    if bytes.len() != STATE_SIZE {
+        return Err(WasmSaplingError::new(
+            "InvalidVerifyState",
+            format!(
+                "Verify state must be {} bytes, got {}",
+                STATE_SIZE,
+                bytes.len()
+            ),
+        ));
+    }
+    let mut transaction_hash = [0; 32];
+    transaction_hash.copy_from_slice(&bytes[..32]);
+    let step = u32::from_le_bytes(bytes[32..].try_into().unwrap());
+    Ok(WasmVerifyState {
+        transaction_hash,
+        step,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_state, WasmVerifyState};
+    use crate::wasm_structs::transaction::miners_fee_transaction;
+
+    #[test]
+    fn test_state_round_trip() {
+        let state = WasmVerifyState {
+            transaction_hash: [7; 32],
+            step: 3,
+        };
+        assert_eq!(read_state(&state.serialize()).unwrap(), state);
+        let err = read_state(&[0; 35]).err().unwrap();
+        assert_eq!(err.code(), "InvalidVerifyState");
+    }
+
+    #[test]
+    fn test_verify_in_steps() {
+        let (_, posted) = miners_fee_transaction();
+
+        // The receipt, then the binding signature
+        let first = posted.verify_step(None);
+        assert!(first.valid() && !first.done());
+        let second = posted.verify_step(Some(first.state()));
+        assert!(second.valid() && second.done());
+        assert_eq!(second.state().step(), 2);
+
+        // Nothing is left to check once the last step ran
+        let finished = posted.verify_step(Some(second.state()));
+        assert!(!finished.valid() && finished.done());
+
+        let mut other_state = first.state();
+        other_state.transaction_hash = [0; 32];
+        let mismatched = posted.verify_step(Some(other_state));
+        assert!(!mismatched.valid() && mismatched.done());
+    }
+}
@@ -0,0 +1,86 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use wasm_bindgen::prelude::*;
+
+use ironfish_rust::sapling_bls12::{VerifyingKeys, SAPLING};
+
+use super::errors::WasmSaplingError;
+
+/// Verifying keys of a proof parameter set, for
+/// `WasmTransactionPosted.verifyWithKeys`.
+///
+/// A new instance holds the keys of the parameters built into this module.
+/// Keys of another network or parameter upgrade can be loaded with
+/// `deserialize`.
+#[wasm_bindgen]
+pub struct WasmVerifyingKeys {
+    pub(crate) keys: VerifyingKeys,
+}
+
+#[wasm_bindgen]
+impl WasmVerifyingKeys {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmVerifyingKeys {
+        WasmVerifyingKeys {
+            keys: SAPLING.verifying_keys(),
+        }
+    }
+
+    /// Load the keys written by `serialize`: the spend verifying key
+    /// followed by the receipt verifying key.
+    #[wasm_bindgen]
+    pub fn deserialize(bytes: &[u8]) -> Result<WasmVerifyingKeys, JsValue> {
+        Ok(read_keys(bytes)?)
+    }
+
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Result<Vec<u8>, JsValue> {
+        let mut bytes = vec![];
+        self.keys
+            .write(&mut bytes)
+            .map_err(|e| WasmSaplingError::new("IOError", e.to_string()))?;
+        Ok(bytes)
+    }
+}
+
+impl Default for WasmVerifyingKeys {
+    fn default() -> Self {
+        WasmVerifyingKeys::new()
+    }
+}
+
+fn read_keys(mut bytes: &[u8]) -> Result<WasmVerifyingKeys, WasmSaplingError> {
+    let keys = VerifyingKeys::read(&mut bytes)
+        .map_err(|e| WasmSaplingError::new("InvalidVerifyingKey", e.to_string()))?;
+    if !bytes.is_empty() {
+        return Err(WasmSaplingError::new(
+            "InvalidVerifyingKey",
+            "Unexpected bytes after the verifying keys".into(),
+        ));
+    }
+    Ok(WasmVerifyingKeys { keys })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_keys, WasmVerifyingKeys};
+    use crate::wasm_structs::transaction::miners_fee_transaction;
+
+    #[test]
+    fn test_verify_with_keys() {
+        let (_, posted) = miners_fee_transaction();
+
+        let serialized = WasmVerifyingKeys::new().serialize().unwrap();
+        let keys = read_keys(&serialized).unwrap();
+        assert!(posted.verify_with_keys(&keys));
+
+        let err = read_keys(&serialized[..serialized.len() - 1]).unwrap_err();
+        assert_eq!(err.code(), "InvalidVerifyingKey");
+        let mut extended = serialized.clone();
+        extended.push(0);
+        let err = read_keys(&extended).unwrap_err();
+        assert_eq!(err.code(), "InvalidVerifyingKey");
+    }
+}
@@ -5,8 +5,10 @@
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 
-use ironfish_rust::sapling_bls12::{Bls12, Fr, MerkleNoteHash};
-use ironfish_rust::witness::{WitnessNode, WitnessTrait};
+use ironfish_rust::sapling_bls12::{Bls12, Fr, MerkleNoteHash, SAPLING};
+use ironfish_rust::witness::{Witness, WitnessNode, WitnessTrait, NOTE_TREE_DEPTH};
+
+use super::errors::WasmSaplingError;
 
 #[wasm_bindgen(typescript_custom_section)]
 const IWITNESS: &'static str = r#"
@@ -106,3 +108,252 @@ impl WitnessTrait<Bls12> for JsWitness {
         self.tree_size()
     }
 }
+
+/// Witness stored in this library's own compact binary encoding, as written
+/// by `serialize` and read by `deserialize`. Use `fromAuthPath` to build one
+/// from the root and sibling hashes of a note's auth path.
+///
+/// Implements the same methods as IWitness, so it can be passed anywhere a
+/// witness from the TypeScript side is accepted, such as
+/// `WasmTransaction.spend`.
+#[wasm_bindgen]
+pub struct WasmWitness {
+    pub(crate) witness: Witness<Bls12>,
+}
+
+#[wasm_bindgen]
+impl WasmWitness {
+    /// Number of nodes in the auth path of a witness that can be spent.
+    /// Witnesses of other depths can be built and verified, but
+    /// `WasmTransaction.spend` rejects them with an error with code
+    /// `InconsistentWitness` before creating any proof.
+    #[wasm_bindgen(js_name = "treeDepth")]
+    pub fn tree_depth() -> usize {
+        NOTE_TREE_DEPTH
+    }
+
+    /// Read a witness serialized as root hash (32 bytes), tree size (u32 LE),
+    /// auth path length (u8), and then for each node a side byte (0 for
+    /// Left, 1 for Right) followed by the 32 byte hash of its sibling.
+    #[wasm_bindgen]
+    pub fn deserialize(bytes: &[u8]) -> Result<WasmWitness, JsValue> {
+        let witness = Witness::read(SAPLING.clone(), bytes)
+            .map_err(|e| WasmSaplingError::new("IOError", e.to_string()))?;
+        Ok(WasmWitness { witness })
+    }
+
+    /// Build the witness of the leaf at `leaf_position` in a tree with the
+    /// given 32 byte `root` hash. `siblings` holds the 32 byte hashes of the
+    /// siblings along the path, ordered from the leaf up to the root; which
+    /// side each one is on follows from the bits of the position.
+    ///
+    /// Throws an error with code `InvalidEncoding` if a hash can't be
+    /// decoded, and `InvalidPosition` if the position doesn't fit in a tree
+    /// of that depth. Use `verify` to check the witness against the note
+    /// commitment before spending with it.
+    #[wasm_bindgen(js_name = "fromAuthPath")]
+    pub fn from_auth_path(
+        root: &[u8],
+        leaf_position: u64,
+        siblings: js_sys::Array,
+    ) -> Result<WasmWitness, JsValue> {
+        let siblings: Vec<Vec<u8>> = siblings
+            .iter()
+            .map(|sibling| js_sys::Uint8Array::new(&sibling).to_vec())
+            .collect();
+        Ok(witness_from_auth_path(root, leaf_position, &siblings)?)
+    }
+
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut cursor: Vec<u8> = vec![];
+        self.witness.write(&mut cursor).unwrap();
+        cursor
+    }
+
+    /// Whether hashing `hash`, the note commitment of the leaf, up the auth
+    /// path gives the root hash of this witness.
+    #[wasm_bindgen]
+    pub fn verify(&self, hash: &[u8]) -> bool {
+        let mut cursor: std::io::Cursor<&[u8]> = std::io::Cursor::new(hash);
+        match MerkleNoteHash::read(&mut cursor) {
+            Ok(hash) => self.witness.verify(&hash),
+            Err(_) => false,
+        }
+    }
+
+    #[wasm_bindgen(js_name = "authPath")]
+    pub fn auth_path(&self) -> js_sys::Array {
+        self.witness
+            .auth_path
+            .iter()
+            .map(|node| JsValue::from(WasmWitnessNode::from(node)))
+            .collect()
+    }
+
+    #[wasm_bindgen(js_name = "treeSize")]
+    pub fn tree_size(&self) -> u32 {
+        self.witness.tree_size as u32
+    }
+
+    #[wasm_bindgen(js_name = "serializeRootHash")]
+    pub fn serialize_root_hash(&self) -> Vec<u8> {
+        let mut cursor: Vec<u8> = Vec::with_capacity(32);
+        MerkleNoteHash::new(self.witness.root_hash)
+            .write(&mut cursor)
+            .unwrap();
+        cursor
+    }
+}
+
+fn witness_from_auth_path(
+    root: &[u8],
+    leaf_position: u64,
+    siblings: &[Vec<u8>],
+) -> Result<WasmWitness, WasmSaplingError> {
+    let invalid_hash = || {
+        WasmSaplingError::new(
+            "InvalidEncoding",
+            "Hash must be a valid 32 byte tree hash".into(),
+        )
+    };
+    let root_hash = read_hash(root).ok_or_else(invalid_hash)?;
+    let siblings = siblings
+        .iter()
+        .enumerate()
+        .map(|(index, sibling)| read_hash(sibling).ok_or_else(|| invalid_hash().at_index(index)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let witness = Witness::from_auth_path(SAPLING.clone(), root_hash, leaf_position, siblings)
+        .map_err(|e| WasmSaplingError::new("InvalidPosition", e.to_string()))?;
+    Ok(WasmWitness { witness })
+}
+
+/// Read a tree hash, requiring exactly 32 bytes.
+fn read_hash(bytes: &[u8]) -> Option<Fr> {
+    if bytes.len() != 32 {
+        return None;
+    }
+    let mut cursor: std::io::Cursor<&[u8]> = std::io::Cursor::new(bytes);
+    MerkleNoteHash::read(&mut cursor).ok().map(|hash| hash.0)
+}
+
+/// Node of a WasmWitness auth path, implementing IWitnessNode.
+#[wasm_bindgen]
+pub struct WasmWitnessNode {
+    side: String,
+    hash_of_sibling: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl WasmWitnessNode {
+    #[wasm_bindgen]
+    pub fn side(&self) -> String {
+        self.side.clone()
+    }
+
+    #[wasm_bindgen(js_name = "hashOfSibling")]
+    pub fn hash_of_sibling(&self) -> Vec<u8> {
+        self.hash_of_sibling.clone()
+    }
+}
+
+impl From<&WitnessNode<Fr>> for WasmWitnessNode {
+    fn from(node: &WitnessNode<Fr>) -> WasmWitnessNode {
+        let (side, hash) = match node {
+            WitnessNode::Left(hash) => ("Left", hash),
+            WitnessNode::Right(hash) => ("Right", hash),
+        };
+        let mut hash_of_sibling: Vec<u8> = Vec::with_capacity(32);
+        MerkleNoteHash::new(*hash)
+            .write(&mut hash_of_sibling)
+            .unwrap();
+        WasmWitnessNode {
+            side: side.into(),
+            hash_of_sibling,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{witness_from_auth_path, WasmWitness};
+    use crate::wasm_structs::WasmNote;
+    use ironfish_rust::sapling_bls12::{Fr, Key, MerkleNoteHash, ProposedTransaction, SAPLING};
+    use ironfish_rust::witness::NOTE_TREE_DEPTH;
+
+    fn hash_bytes(hash: Fr) -> Vec<u8> {
+        let mut bytes = vec![];
+        MerkleNoteHash::new(hash).write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_from_auth_path() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let commitment = note.commitment();
+        let mut cursor: std::io::Cursor<&[u8]> = std::io::Cursor::new(&commitment[..]);
+        let mut current = MerkleNoteHash::read(&mut cursor).unwrap().0;
+
+        // Leaf 5 of a tree of depth 4: left, right, left, left of its siblings
+        let leaf_position = 0b0101;
+        let mut siblings = vec![];
+        for depth in 0..4 {
+            let sibling = Fr::from(depth as u64 + 1);
+            current = if (leaf_position >> depth) & 1 == 0 {
+                MerkleNoteHash::combine_hash(&SAPLING, depth, &current, &sibling)
+            } else {
+                MerkleNoteHash::combine_hash(&SAPLING, depth, &sibling, &current)
+            };
+            siblings.push(hash_bytes(sibling));
+        }
+        let root = hash_bytes(current);
+
+        let witness = witness_from_auth_path(&root, leaf_position, &siblings).unwrap();
+        assert!(witness.verify(&commitment));
+        assert_eq!(witness.tree_size(), 6);
+        let read_back = WasmWitness::deserialize(&witness.serialize()).unwrap();
+        assert!(read_back.verify(&commitment));
+
+        let wrong_leaf = witness_from_auth_path(&root, leaf_position + 1, &siblings).unwrap();
+        assert!(!wrong_leaf.verify(&commitment));
+
+        let err = witness_from_auth_path(&root, 16, &siblings).err().unwrap();
+        assert_eq!(err.code(), "InvalidPosition");
+        let err = witness_from_auth_path(&root, leaf_position, &[vec![0; 31]])
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), "InvalidEncoding");
+        assert!(err.message().starts_with("Item at index 0"));
+    }
+
+    #[test]
+    fn test_spend_with_note_commitment() {
+        let key = Key::generate_key(SAPLING.clone());
+        let note =
+            WasmNote::new(&key.generate_public_address().hex_public_address(), 5, "").unwrap();
+        let commitment = note.commitment();
+        let mut cursor: std::io::Cursor<&[u8]> = std::io::Cursor::new(&commitment[..]);
+        let mut current = MerkleNoteHash::read(&mut cursor).unwrap().0;
+
+        // The only leaf of the tree, with empty siblings all the way up
+        let mut siblings = vec![];
+        let mut empty = Fr::from(0u64);
+        for depth in 0..NOTE_TREE_DEPTH {
+            current = MerkleNoteHash::combine_hash(&SAPLING, depth, &current, &empty);
+            siblings.push(hash_bytes(empty));
+            empty = MerkleNoteHash::combine_hash(&SAPLING, depth, &empty, &empty);
+        }
+        let witness = witness_from_auth_path(&hash_bytes(current), 0, &siblings).unwrap();
+        assert!(witness.verify(&commitment));
+
+        let mut transaction = ProposedTransaction::new(SAPLING.clone());
+        transaction
+            .spend(key, &note.note, &witness.witness)
+            .expect("should be able to spend note");
+        transaction
+            .verify_proofs()
+            .expect("spend proof should verify");
+    }
+}
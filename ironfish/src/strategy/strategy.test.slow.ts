@@ -99,7 +99,7 @@ describe('Demonstrate the Sapling API', () => {
 
       minerNote = new WasmNote(owner, BigInt(42), '')
       const transaction = new WasmTransaction()
-      expect(transaction.receive(spenderKey.spending_key, minerNote)).toBe('')
+      expect(transaction.receive(spenderKey.spending_key, minerNote)).toBeUndefined()
       minerTransaction = transaction.post_miners_fee()
       expect(minerTransaction).toBeTruthy()
       expect(minerTransaction.notesLength).toEqual(1)
@@ -129,14 +129,14 @@ describe('Demonstrate the Sapling API', () => {
       const witness = await tree.witness(0)
       if (witness == null) throw new Error('Witness should not be null')
       const result = simpleTransaction.spend(minerNote, witness)
-      expect(result).toEqual('')
+      expect(result).toBeUndefined()
     })
 
     it('Can add a receive to the transaction', () => {
       receiverKey = generateKey()
       const receivingNote = new WasmNote(receiverKey.public_address, BigInt(40), '')
       const result = simpleTransaction.receive(receivingNote)
-      expect(result).toEqual('')
+      expect(result).toBeUndefined()
     })
 
     it('Can post the transaction', () => {